@@ -0,0 +1,115 @@
+//! `#[derive(JinkoType)]` maps a Rust struct with named fields to a jinko type of the
+//! same name. It implements `JinkoType`, `ToObjectInstance` and `FromObjectInstance` on
+//! the struct, so that its values can be given to jinko code and read back. The fields
+//! of the struct must themselves be jinko types: `i64`, `f64`, `char`, `bool`,
+//! `String`, or other structs deriving `JinkoType`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, FieldsNamed};
+
+#[proc_macro_derive(JinkoType)]
+pub fn derive_jinko_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Get the named fields of a struct, which are the only items jinko types map to
+fn named_fields(input: &DeriveInput) -> Result<&FieldsNamed, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "JinkoType cannot be derived on generic types",
+        ));
+    }
+
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields),
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                "JinkoType can only be derived on structs with named fields",
+            )),
+        },
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            "JinkoType can only be derived on structs",
+        )),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let ty = &input.ident;
+    let ty_name = ty.to_string();
+
+    let fields = named_fields(input)?;
+    let idents: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+    let types: Vec<_> = fields.named.iter().map(|f| &f.ty).collect();
+    let names: Vec<_> = idents
+        .iter()
+        .map(|ident| ident.as_ref().unwrap().to_string())
+        .collect();
+    let missing: Vec<_> = names
+        .iter()
+        .map(|name| format!("instance of `{}` has no field `{}`", ty_name, name))
+        .collect();
+
+    Ok(quote! {
+        impl ::jinko::JinkoType for #ty {
+            fn type_dec() -> ::jinko::instruction::TypeDec {
+                ::jinko::instruction::TypeDec::new(
+                    ::std::string::String::from(#ty_name),
+                    vec![#(
+                        ::jinko::instruction::DecArg::new(
+                            ::std::string::String::from(#names),
+                            ::jinko::TypeId::from(
+                                <#types as ::jinko::JinkoType>::type_dec().name()
+                            ),
+                        )
+                    ),*],
+                )
+            }
+        }
+
+        impl ::jinko::ToObjectInstance for #ty {
+            fn to_instance(&self) -> ::jinko::ObjectInstance {
+                let fields: ::std::vec::Vec<(::std::string::String, ::jinko::ObjectInstance)> = vec![#(
+                    (
+                        ::std::string::String::from(#names),
+                        ::jinko::ToObjectInstance::to_instance(&self.#idents),
+                    )
+                ),*];
+
+                let size = fields.iter().map(|(_, instance)| instance.size()).sum();
+                let data = fields
+                    .iter()
+                    .flat_map(|(_, instance)| instance.data().iter().copied())
+                    .collect();
+
+                ::jinko::ObjectInstance::new(
+                    Some(<Self as ::jinko::JinkoType>::type_dec()),
+                    size,
+                    data,
+                    Some(fields),
+                )
+            }
+        }
+
+        impl ::jinko::FromObjectInstance for #ty {
+            fn from_instance(instance: &::jinko::ObjectInstance) -> Self {
+                #ty {
+                    #(
+                        #idents: ::jinko::FromObjectInstance::from_instance(
+                            &instance.get_field(#names).expect(#missing),
+                        )
+                    ),*
+                }
+            }
+        }
+    })
+}
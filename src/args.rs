@@ -16,8 +16,20 @@ pub struct Args {
     #[structopt(short, long)]
     debug: bool,
 
+    /// Run the tests declared in the input file instead of executing it
+    #[structopt(short, long, requires = "input")]
+    test: bool,
+
+    /// Shared library containing external functions. Can be given multiple times
+    #[structopt(short, long = "link", parse(from_os_str), number_of_values = 1)]
+    libraries: Vec<PathBuf>,
+
     #[structopt(parse(from_os_str))]
     input: Option<PathBuf>,
+
+    /// Only run the tests whose name contains this pattern
+    #[structopt(requires = "test")]
+    filter: Option<String>,
 }
 
 impl Args {
@@ -49,8 +61,23 @@ impl Args {
         self.debug
     }
 
+    /// Is the context launched in test mode
+    pub fn test(&self) -> bool {
+        self.test
+    }
+
     /// File input given to the context
     pub fn input(&self) -> Option<&PathBuf> {
         self.input.as_ref()
     }
+
+    /// Shared libraries to load in the context
+    pub fn libraries(&self) -> &Vec<PathBuf> {
+        &self.libraries
+    }
+
+    /// Pattern used to select the tests to run
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
 }
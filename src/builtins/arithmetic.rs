@@ -0,0 +1,78 @@
+//! Arithmetic builtins. Integer operators error out when overflowing, and these
+//! builtins offer the other behaviors: `wrapping_*` functions wrap around the bounds of
+//! `int`, while `saturating_*` functions stop at them
+
+use super::int_arg;
+use crate::{Context, JkInt, ObjectInstance, ToObjectInstance};
+
+/// Apply an operation on the two integers given as arguments
+fn int_op(args: &[ObjectInstance], op: impl Fn(i64, i64) -> i64) -> Option<ObjectInstance> {
+    let res = op(int_arg(args, 0), int_arg(args, 1));
+
+    Some(JkInt::from(res).to_instance())
+}
+
+/// `wrapping_add(lhs: int, rhs: int) -> int`
+pub fn wrapping_add(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    int_op(&args, i64::wrapping_add)
+}
+
+/// `wrapping_sub(lhs: int, rhs: int) -> int`
+pub fn wrapping_sub(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    int_op(&args, i64::wrapping_sub)
+}
+
+/// `wrapping_mul(lhs: int, rhs: int) -> int`
+pub fn wrapping_mul(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    int_op(&args, i64::wrapping_mul)
+}
+
+/// `saturating_add(lhs: int, rhs: int) -> int`
+pub fn saturating_add(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    int_op(&args, i64::saturating_add)
+}
+
+/// `saturating_sub(lhs: int, rhs: int) -> int`
+pub fn saturating_sub(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    int_op(&args, i64::saturating_sub)
+}
+
+/// `saturating_mul(lhs: int, rhs: int) -> int`
+pub fn saturating_mul(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    int_op(&args, i64::saturating_mul)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(lhs: i64, rhs: i64) -> Vec<ObjectInstance> {
+        vec![
+            JkInt::from(lhs).to_instance(),
+            JkInt::from(rhs).to_instance(),
+        ]
+    }
+
+    fn int(i: i64) -> Option<ObjectInstance> {
+        Some(JkInt::from(i).to_instance())
+    }
+
+    #[test]
+    fn t_wrapping() {
+        let mut ctx = Context::new();
+
+        assert_eq!(wrapping_add(&mut ctx, ints(i64::MAX, 1)), int(i64::MIN));
+        assert_eq!(wrapping_sub(&mut ctx, ints(i64::MIN, 1)), int(i64::MAX));
+        assert_eq!(wrapping_mul(&mut ctx, ints(i64::MAX, 2)), int(-2));
+    }
+
+    #[test]
+    fn t_saturating() {
+        let mut ctx = Context::new();
+
+        assert_eq!(saturating_add(&mut ctx, ints(i64::MAX, 1)), int(i64::MAX));
+        assert_eq!(saturating_sub(&mut ctx, ints(i64::MIN, 1)), int(i64::MIN));
+        assert_eq!(saturating_mul(&mut ctx, ints(i64::MIN, 2)), int(i64::MIN));
+        assert_eq!(saturating_add(&mut ctx, ints(2, 3)), int(5));
+    }
+}
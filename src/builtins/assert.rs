@@ -0,0 +1,195 @@
+//! Assertions are used in tests. A failed assertion reports an error describing the
+//! failure, and stops the test it is in
+
+use crate::instance::FieldInstance;
+use crate::{Context, ControlFlow, ErrKind, Error, FromObjectInstance, JkBool, ObjectInstance};
+
+/// Fail if the condition is false
+///
+/// `assert(condition: bool)`
+pub fn assert(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    if !JkBool::from_instance(&args[0]).0 {
+        ctx.error(Error::new(ErrKind::Assertion).with_msg(String::from("assertion failed")));
+        ctx.set_control_flow(ControlFlow::Abort);
+    }
+
+    None
+}
+
+/// Fail if the two instances are not equal. For instances of custom types, the
+/// failure lists the fields which differ
+///
+/// `assert_eq<T>(left: T, right: T)`
+pub fn assert_eq(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let (left, right) = (&args[0], &args[1]);
+
+    if left == right {
+        return None;
+    }
+
+    let mut msg = String::from("assertion failed: `left == right`");
+
+    match left.ty() == right.ty() {
+        true => {
+            msg.push_str(&format!("\n  left: {}", show(left)));
+            msg.push_str(&format!("\n right: {}", show(right)));
+
+            let mut diffs = vec![];
+            diff_fields(left, right, None, &mut diffs);
+
+            if !diffs.is_empty() {
+                msg.push_str("\ndiffering fields:");
+                diffs
+                    .iter()
+                    .for_each(|diff| msg.push_str(&format!("\n  {}", diff)));
+            }
+        }
+        false => {
+            msg.push_str(&format!("\n  left: {} ({})", show(left), type_name(left)));
+            msg.push_str(&format!("\n right: {} ({})", show(right), type_name(right)));
+        }
+    }
+
+    ctx.error(Error::new(ErrKind::Assertion).with_msg(msg));
+    ctx.set_control_flow(ControlFlow::Abort);
+
+    None
+}
+
+fn type_name(instance: &ObjectInstance) -> &str {
+    instance.ty().map_or("no type", |ty| ty.name())
+}
+
+/// Fields of an instance, in the order they were declared in
+fn sorted_fields(instance: &ObjectInstance) -> Vec<(&String, &FieldInstance)> {
+    let mut fields: Vec<_> = instance.fields().iter().flatten().collect();
+    fields.sort_by(|(l_name, l_field), (r_name, r_field)| {
+        (l_field.offset(), l_name).cmp(&(r_field.offset(), r_name))
+    });
+
+    fields
+}
+
+/// Compact representation of an instance, such as `15`, `"jinko"` or
+/// `Point { x: 1, y: 2 }`
+fn show(instance: &ObjectInstance) -> String {
+    match instance.fields() {
+        Some(_) => {
+            let fields: Vec<String> = sorted_fields(instance)
+                .iter()
+                .map(|(name, field)| format!("{}: {}", name, show(field.instance())))
+                .collect();
+
+            format!("{} {{ {} }}", type_name(instance), fields.join(", "))
+        }
+        None => instance.to_string(),
+    }
+}
+
+/// Collect the fields which differ between two instances of the same type. Nested
+/// fields are named after their path, such as `origin.x`
+fn diff_fields(
+    left: &ObjectInstance,
+    right: &ObjectInstance,
+    path: Option<&str>,
+    diffs: &mut Vec<String>,
+) {
+    match (right.fields(), path) {
+        (Some(right_fields), _) => {
+            for (name, field) in sorted_fields(left) {
+                let field_path = match path {
+                    Some(path) => format!("{}.{}", path, name),
+                    None => name.to_owned(),
+                };
+
+                if let Some(right_field) = right_fields.get(name) {
+                    diff_fields(
+                        field.instance(),
+                        right_field.instance(),
+                        Some(&field_path),
+                        diffs,
+                    );
+                }
+            }
+        }
+        (None, Some(path)) if left != right => {
+            diffs.push(format!("{}: {} != {}", path, show(left), show(right)))
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jinko;
+    use crate::parser::{Construct, Parser};
+
+    fn instance(ctx: &mut Context, input: &str) -> ObjectInstance {
+        Construct::instruction(input)
+            .unwrap()
+            .1
+            .execute(ctx)
+            .unwrap()
+    }
+
+    #[test]
+    fn t_assert() {
+        let mut ctx = jinko! {
+            assert(true);
+        };
+        assert!(!ctx.error_handler.has_errors());
+
+        let args = vec![instance(&mut ctx, "false")];
+        assert(&mut ctx, args);
+        assert!(ctx.error_handler.has_errors());
+        assert_eq!(ctx.take_control_flow(), Some(ControlFlow::Abort));
+    }
+
+    #[test]
+    fn t_failed_assertion_stops_execution() {
+        let mut ctx = Parser::parse("assert_eq(1, 2); assert(false);", None);
+
+        assert!(ctx.execute().is_err());
+        assert_eq!(ctx.error_handler.errors().len(), 1);
+    }
+
+    #[test]
+    fn t_assert_eq_primitives() {
+        let mut ctx = Context::new();
+
+        let args = vec![instance(&mut ctx, "15"), instance(&mut ctx, "15")];
+        assert_eq(&mut ctx, args);
+        assert!(!ctx.error_handler.has_errors());
+
+        let args = vec![instance(&mut ctx, "15"), instance(&mut ctx, "16")];
+        assert_eq(&mut ctx, args);
+        assert!(ctx.error_handler.has_errors());
+    }
+
+    #[test]
+    fn t_diff_custom_types() {
+        let mut ctx = jinko! {
+            type Point(x: int, y: int);
+            type Line(start: Point, end: Point);
+        };
+
+        let left = instance(
+            &mut ctx,
+            "Line { start = Point { x = 1, y = 2 }, end = Point { x = 3, y = 4 } }",
+        );
+        let right = instance(
+            &mut ctx,
+            "Line { start = Point { x = 1, y = 5 }, end = Point { x = 3, y = 4 } }",
+        );
+
+        assert_eq!(
+            show(&left),
+            "Line { start: Point { x: 1, y: 2 }, end: Point { x: 3, y: 4 } }"
+        );
+
+        let mut diffs = vec![];
+        diff_fields(&left, &right, None, &mut diffs);
+        assert_eq!(diffs, vec![String::from("start.y: 2 != 5")]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Input and output builtins, used by the standard library to print to the standard
+//! streams, read from the standard input and access files. Failures are reported as
+//! I/O errors
+
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, Write};
+
+use super::string_arg;
+use crate::{Context, ControlFlow, ErrKind, Error, JkString, ObjectInstance, ToObjectInstance};
+
+/// Report an I/O error, describing the operation which failed. A failed I/O operation
+/// stops the program
+fn report<T>(ctx: &mut Context, what: &str, result: io::Result<T>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            ctx.error(Error::new(ErrKind::IO).with_msg(format!("{}: {}", what, e)));
+            ctx.set_control_flow(ControlFlow::Abort);
+
+            None
+        }
+    }
+}
+
+fn write_to(ctx: &mut Context, mut stream: impl Write, what: &str, content: &str) {
+    let result = stream
+        .write_all(content.as_bytes())
+        .and_then(|_| stream.flush());
+
+    report(ctx, what, result);
+}
+
+/// Print a string on the standard output
+///
+/// `print(s: string)`
+pub fn print(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    write_to(ctx, io::stdout(), "cannot print", &string_arg(&args, 0));
+
+    None
+}
+
+/// Print a string followed by a newline on the standard output
+///
+/// `println(s: string)`
+pub fn println(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let line = format!("{}\n", string_arg(&args, 0));
+    write_to(ctx, io::stdout(), "cannot print", &line);
+
+    None
+}
+
+/// Print a string on the standard error
+///
+/// `eprint(s: string)`
+pub fn eprint(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    write_to(ctx, io::stderr(), "cannot print", &string_arg(&args, 0));
+
+    None
+}
+
+/// Print a string followed by a newline on the standard error
+///
+/// `eprintln(s: string)`
+pub fn eprintln(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let line = format!("{}\n", string_arg(&args, 0));
+    write_to(ctx, io::stderr(), "cannot print", &line);
+
+    None
+}
+
+/// Read a line from the standard input. The line keeps its trailing newline, so that
+/// an empty string means that the end of the input has been reached
+///
+/// `read_line() -> string`
+pub fn read_line(ctx: &mut Context, _args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let mut line = String::new();
+    let result = io::stdin().lock().read_line(&mut line);
+
+    report(ctx, "cannot read from the standard input", result)?;
+
+    Some(JkString::from(line).to_instance())
+}
+
+/// Read the whole content of a file
+///
+/// `read_file(path: string) -> string`
+pub fn read_file(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let path = string_arg(&args, 0);
+    let result = std::fs::read_to_string(&path);
+
+    let content = report(ctx, &format!("cannot read file `{}`", path), result)?;
+
+    Some(JkString::from(content).to_instance())
+}
+
+/// Write a string to a file, creating the file or replacing its content
+///
+/// `write_file(path: string, content: string)`
+pub fn write_file(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let path = string_arg(&args, 0);
+    let result = std::fs::write(&path, string_arg(&args, 1));
+
+    report(ctx, &format!("cannot write file `{}`", path), result);
+
+    None
+}
+
+/// Write a string at the end of a file, creating the file if it does not exist
+///
+/// `append_file(path: string, content: string)`
+pub fn append_file(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let path = string_arg(&args, 0);
+    let result = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(string_arg(&args, 1).as_bytes()));
+
+    report(ctx, &format!("cannot append to file `{}`", path), result);
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> ObjectInstance {
+        JkString::from(s).to_instance()
+    }
+
+    #[test]
+    fn t_write_append_read() {
+        let path = std::env::temp_dir().join("jinko_t_write_append_read.txt");
+        let path = string(path.to_str().unwrap());
+        let mut ctx = Context::new();
+
+        write_file(&mut ctx, vec![path.clone(), string("jinko\n")]);
+        append_file(&mut ctx, vec![path.clone(), string("rocks\n")]);
+        let content = read_file(&mut ctx, vec![path]);
+
+        assert!(!ctx.has_errors());
+        assert_eq!(content, Some(string("jinko\nrocks\n")));
+    }
+
+    #[test]
+    fn t_read_missing_file() {
+        let mut ctx = Context::new();
+
+        let content = read_file(&mut ctx, vec![string("does/not/exist.txt")]);
+
+        assert_eq!(content, None);
+        assert_eq!(ctx.error_handler.errors()[0].kind(), ErrKind::IO);
+        assert_eq!(ctx.take_control_flow(), Some(ControlFlow::Abort));
+    }
+}
@@ -0,0 +1,315 @@
+//! Builtins are functions implemented directly in the interpreter, in Rust. They are
+//! called like any other jinko function, but receive the instances of their arguments
+//! instead of executing a block. A function declared in jinko shadows a builtin of the
+//! same name. Programs embedding jinko can register their own native functions, which
+//! are handled the same way.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::instruction::{TypeId, TypeParam};
+use crate::{Context, FromObjectInstance, JkInt, JkString, ObjectInstance};
+
+mod arithmetic;
+mod assert;
+mod io;
+mod panic;
+mod string;
+
+/// Native implementation of a builtin. The arguments are given in order, and have
+/// already been checked against the builtin's signature
+pub type NativeFn = Rc<dyn Fn(&mut Context, Vec<ObjectInstance>) -> Option<ObjectInstance>>;
+
+/// Types of the arguments and of the return value of a builtin
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    /// Type parameters the types of the arguments and of the return value refer to
+    generics: Vec<TypeParam>,
+    args: Vec<TypeId>,
+    ty: Option<TypeId>,
+}
+
+impl Signature {
+    /// Create a new signature. A `None` return type means that the function does not
+    /// return anything
+    pub fn new(args: Vec<TypeId>, ty: Option<TypeId>) -> Signature {
+        Signature {
+            generics: vec![],
+            args,
+            ty,
+        }
+    }
+
+    /// Create a signature whose types can refer to the type parameters named
+    /// `params`. The types bound to the parameters are inferred from the arguments of
+    /// each call
+    fn generic(params: &[&str], args: Vec<TypeId>, ty: Option<TypeId>) -> Signature {
+        Signature {
+            generics: params
+                .iter()
+                .map(|param| TypeParam::new(param.to_string(), None))
+                .collect(),
+            args,
+            ty,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Builtin {
+    signature: Signature,
+    function: NativeFn,
+}
+
+impl Builtin {
+    /// Create a new builtin from its signature and implementation
+    pub fn new(signature: Signature, function: NativeFn) -> Builtin {
+        Builtin {
+            signature,
+            function,
+        }
+    }
+
+    /// Return a reference to the type parameters of the builtin
+    pub fn generics(&self) -> &Vec<TypeParam> {
+        &self.signature.generics
+    }
+
+    /// Return a reference to the types of the builtin's arguments
+    pub fn args(&self) -> &Vec<TypeId> {
+        &self.signature.args
+    }
+
+    /// Return a reference to the builtin's return type
+    pub fn ty(&self) -> Option<&TypeId> {
+        self.signature.ty.as_ref()
+    }
+
+    /// Call the builtin with the instances of its arguments
+    pub fn call(&self, ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+        (self.function)(ctx, args)
+    }
+}
+
+/// Set of all the builtins available to jinko programs
+pub struct Builtins {
+    functions: HashMap<String, Builtin>,
+}
+
+impl Builtins {
+    /// Create the set of builtins
+    pub fn new() -> Builtins {
+        let mut builtins = Builtins {
+            functions: HashMap::new(),
+        };
+
+        builtins.add(
+            "assert",
+            Builtin::new(
+                Signature::new(vec![TypeId::from("bool")], None),
+                Rc::new(assert::assert),
+            ),
+        );
+        builtins.add(
+            "assert_eq",
+            Builtin::new(
+                Signature::generic(&["T"], vec![TypeId::from("T"), TypeId::from("T")], None),
+                Rc::new(assert::assert_eq),
+            ),
+        );
+
+        let string = || TypeId::from("string");
+        builtins.add(
+            "panic",
+            Builtin::new(Signature::new(vec![string()], None), Rc::new(panic::panic)),
+        );
+        builtins.add(
+            "print",
+            Builtin::new(Signature::new(vec![string()], None), Rc::new(io::print)),
+        );
+        builtins.add(
+            "println",
+            Builtin::new(Signature::new(vec![string()], None), Rc::new(io::println)),
+        );
+        builtins.add(
+            "eprint",
+            Builtin::new(Signature::new(vec![string()], None), Rc::new(io::eprint)),
+        );
+        builtins.add(
+            "eprintln",
+            Builtin::new(Signature::new(vec![string()], None), Rc::new(io::eprintln)),
+        );
+        builtins.add(
+            "read_line",
+            Builtin::new(
+                Signature::new(vec![], Some(string())),
+                Rc::new(io::read_line),
+            ),
+        );
+        builtins.add(
+            "read_file",
+            Builtin::new(
+                Signature::new(vec![string()], Some(string())),
+                Rc::new(io::read_file),
+            ),
+        );
+        builtins.add(
+            "write_file",
+            Builtin::new(
+                Signature::new(vec![string(), string()], None),
+                Rc::new(io::write_file),
+            ),
+        );
+        builtins.add(
+            "append_file",
+            Builtin::new(
+                Signature::new(vec![string(), string()], None),
+                Rc::new(io::append_file),
+            ),
+        );
+
+        let int = || TypeId::from("int");
+        let bool = || TypeId::from("bool");
+        builtins.add(
+            "len",
+            Builtin::new(
+                Signature::new(vec![string()], Some(int())),
+                Rc::new(string::len),
+            ),
+        );
+        builtins.add(
+            "lines",
+            Builtin::new(
+                Signature::new(vec![string()], Some(TypeId::array_of(&string()))),
+                Rc::new(string::lines),
+            ),
+        );
+        builtins.add(
+            "split",
+            Builtin::new(
+                Signature::new(vec![string(), string()], Some(TypeId::array_of(&string()))),
+                Rc::new(string::split),
+            ),
+        );
+        builtins.add(
+            "trim",
+            Builtin::new(
+                Signature::new(vec![string()], Some(string())),
+                Rc::new(string::trim),
+            ),
+        );
+        builtins.add(
+            "contains",
+            Builtin::new(
+                Signature::new(vec![string(), string()], Some(bool())),
+                Rc::new(string::contains),
+            ),
+        );
+        builtins.add(
+            "starts_with",
+            Builtin::new(
+                Signature::new(vec![string(), string()], Some(bool())),
+                Rc::new(string::starts_with),
+            ),
+        );
+        builtins.add(
+            "find",
+            Builtin::new(
+                Signature::new(vec![string(), string()], Some(int())),
+                Rc::new(string::find),
+            ),
+        );
+        builtins.add(
+            "to_upper",
+            Builtin::new(
+                Signature::new(vec![string()], Some(string())),
+                Rc::new(string::to_upper),
+            ),
+        );
+        builtins.add(
+            "char_at",
+            Builtin::new(
+                Signature::new(vec![string(), int()], Some(TypeId::from("char"))),
+                Rc::new(string::char_at),
+            ),
+        );
+        builtins.add(
+            "substring",
+            Builtin::new(
+                Signature::new(vec![string(), int(), int()], Some(string())),
+                Rc::new(string::substring),
+            ),
+        );
+
+        builtins.add(
+            "wrapping_add",
+            Builtin::new(
+                Signature::new(vec![int(), int()], Some(int())),
+                Rc::new(arithmetic::wrapping_add),
+            ),
+        );
+        builtins.add(
+            "wrapping_sub",
+            Builtin::new(
+                Signature::new(vec![int(), int()], Some(int())),
+                Rc::new(arithmetic::wrapping_sub),
+            ),
+        );
+        builtins.add(
+            "wrapping_mul",
+            Builtin::new(
+                Signature::new(vec![int(), int()], Some(int())),
+                Rc::new(arithmetic::wrapping_mul),
+            ),
+        );
+        builtins.add(
+            "saturating_add",
+            Builtin::new(
+                Signature::new(vec![int(), int()], Some(int())),
+                Rc::new(arithmetic::saturating_add),
+            ),
+        );
+        builtins.add(
+            "saturating_sub",
+            Builtin::new(
+                Signature::new(vec![int(), int()], Some(int())),
+                Rc::new(arithmetic::saturating_sub),
+            ),
+        );
+        builtins.add(
+            "saturating_mul",
+            Builtin::new(
+                Signature::new(vec![int(), int()], Some(int())),
+                Rc::new(arithmetic::saturating_mul),
+            ),
+        );
+
+        builtins
+    }
+
+    /// Add a builtin, replacing any previous builtin with the same name
+    pub fn add(&mut self, name: &str, builtin: Builtin) {
+        self.functions.insert(name.to_owned(), builtin);
+    }
+
+    /// Get a builtin from its name
+    pub fn get(&self, name: &str) -> Option<&Builtin> {
+        self.functions.get(name)
+    }
+}
+
+/// Get the string given as argument at index `idx`
+fn string_arg(args: &[ObjectInstance], idx: usize) -> String {
+    JkString::from_instance(&args[idx]).0
+}
+
+/// Get the integer given as argument at index `idx`
+fn int_arg(args: &[ObjectInstance], idx: usize) -> i64 {
+    JkInt::from_instance(&args[idx]).0
+}
+
+impl Default for Builtins {
+    fn default() -> Builtins {
+        Builtins::new()
+    }
+}
@@ -0,0 +1,32 @@
+//! Panics stop the program after an unrecoverable error, such as unwrapping a missing
+//! value. Unlike failed assertions, nothing is executed after a panic
+
+use super::string_arg;
+use crate::{Context, ControlFlow, ErrKind, Error, ObjectInstance};
+
+/// Report an error and stop the program
+///
+/// `panic(msg: string)`
+pub fn panic(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    ctx.error(Error::new(ErrKind::Context).with_msg(format!("panicked: {}", string_arg(&args, 0))));
+    ctx.set_control_flow(ControlFlow::Abort);
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    #[test]
+    fn t_panic_stops_program() {
+        let mut ctx = Parser::parse(
+            "func f() -> int { panic(\"stop\"); 1 } x = f(); y = x + 1; assert(false);",
+            None,
+        );
+
+        assert!(ctx.execute().is_err());
+        // Neither the use of `x` nor the assertion are executed
+        assert_eq!(ctx.error_handler.errors().len(), 1);
+    }
+}
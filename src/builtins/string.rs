@@ -0,0 +1,248 @@
+//! String builtins. They take the string they operate on as their first argument, and
+//! are usually called as methods: `s.trim()` is desugared to `trim(s)`. Indices and
+//! lengths are counted in characters, not in bytes
+
+use std::convert::TryFrom;
+
+use super::{int_arg, string_arg};
+use crate::instruction::TypeId;
+use crate::{
+    Context, ControlFlow, ErrKind, Error, JkBool, JkChar, JkInt, JkString, ObjectInstance,
+    ToObjectInstance,
+};
+
+/// Create an array of strings
+fn string_array<'s>(strings: impl Iterator<Item = &'s str>) -> ObjectInstance {
+    let elements = strings.map(|s| JkString::from(s).to_instance()).collect();
+
+    ObjectInstance::array(&TypeId::from("string"), elements)
+}
+
+/// Report an error and stop the program, like indexing outside of an array does
+fn stop(ctx: &mut Context, msg: String) -> Option<ObjectInstance> {
+    ctx.error(Error::new(ErrKind::Context).with_msg(msg));
+    ctx.set_control_flow(ControlFlow::Abort);
+
+    None
+}
+
+/// Report an index which is not valid for a string and stop the program
+fn invalid_index(ctx: &mut Context, idx: i64, s: &str) -> Option<ObjectInstance> {
+    stop(
+        ctx,
+        format!(
+            "index {} is out of bounds for string {:?} of length {}",
+            idx,
+            s,
+            s.chars().count()
+        ),
+    )
+}
+
+/// Number of characters in the string
+///
+/// `len(s: string) -> int`
+pub fn len(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let len = string_arg(&args, 0).chars().count();
+
+    Some(JkInt::from(len as i64).to_instance())
+}
+
+/// Lines of the string, without their line terminators
+///
+/// `lines(s: string) -> [string]`
+pub fn lines(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    Some(string_array(string_arg(&args, 0).lines()))
+}
+
+/// Parts of the string separated by `sep`
+///
+/// `split(s: string, sep: string) -> [string]`
+pub fn split(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let (s, sep) = (string_arg(&args, 0), string_arg(&args, 1));
+
+    if sep.is_empty() {
+        ctx.error(
+            Error::new(ErrKind::Context)
+                .with_msg(String::from("cannot split a string on an empty separator")),
+        );
+        return None;
+    }
+
+    Some(string_array(s.split(sep.as_str())))
+}
+
+/// The string without its leading and trailing whitespaces
+///
+/// `trim(s: string) -> string`
+pub fn trim(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    Some(JkString::from(string_arg(&args, 0).trim()).to_instance())
+}
+
+/// Check if the string contains `pattern`
+///
+/// `contains(s: string, pattern: string) -> bool`
+pub fn contains(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let contains = string_arg(&args, 0).contains(string_arg(&args, 1).as_str());
+
+    Some(JkBool::from(contains).to_instance())
+}
+
+/// Check if the string starts with `prefix`
+///
+/// `starts_with(s: string, prefix: string) -> bool`
+pub fn starts_with(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let starts_with = string_arg(&args, 0).starts_with(string_arg(&args, 1).as_str());
+
+    Some(JkBool::from(starts_with).to_instance())
+}
+
+/// Index of the first occurence of `pattern` in the string, or -1 if the string does
+/// not contain it
+///
+/// `find(s: string, pattern: string) -> int`
+pub fn find(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let s = string_arg(&args, 0);
+
+    let idx = match s.find(string_arg(&args, 1).as_str()) {
+        Some(byte_idx) => s[..byte_idx].chars().count() as i64,
+        None => -1,
+    };
+
+    Some(JkInt::from(idx).to_instance())
+}
+
+/// The string with all its characters in uppercase
+///
+/// `to_upper(s: string) -> string`
+pub fn to_upper(_: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    Some(JkString::from(string_arg(&args, 0).to_uppercase()).to_instance())
+}
+
+/// Character at index `idx` in the string
+///
+/// `char_at(s: string, idx: int) -> char`
+pub fn char_at(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let (s, idx) = (string_arg(&args, 0), int_arg(&args, 1));
+
+    let c = usize::try_from(idx).ok().and_then(|idx| s.chars().nth(idx));
+
+    match c {
+        Some(c) => Some(JkChar::from(c).to_instance()),
+        None => invalid_index(ctx, idx, &s),
+    }
+}
+
+/// Part of the string going from index `start`, included, to index `end`, excluded
+///
+/// `substring(s: string, start: int, end: int) -> string`
+pub fn substring(ctx: &mut Context, args: Vec<ObjectInstance>) -> Option<ObjectInstance> {
+    let s = string_arg(&args, 0);
+    let (start, end) = (int_arg(&args, 1), int_arg(&args, 2));
+    let len = s.chars().count() as i64;
+
+    if start < 0 || start > len {
+        return invalid_index(ctx, start, &s);
+    }
+    if end < start {
+        return stop(
+            ctx,
+            format!(
+                "invalid substring of {:?}: end index {} is before start index {}",
+                s, end, start
+            ),
+        );
+    }
+    if end > len {
+        return invalid_index(ctx, end, &s);
+    }
+
+    let substring: String = s
+        .chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect();
+
+    Some(JkString::from(substring).to_instance())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FromObjectInstance;
+
+    fn string(s: &str) -> ObjectInstance {
+        JkString::from(s).to_instance()
+    }
+
+    fn int(i: i64) -> ObjectInstance {
+        JkInt::from(i).to_instance()
+    }
+
+    #[test]
+    fn t_split_and_lines() {
+        let mut ctx = Context::new();
+
+        let words = split(&mut ctx, vec![string("a,b,,c"), string(",")]).unwrap();
+        assert_eq!(
+            words.elements(),
+            [string("a"), string("b"), string(""), string("c")]
+        );
+
+        let lines = lines(&mut ctx, vec![string("first\nsecond\r\n")]).unwrap();
+        assert_eq!(lines.elements(), [string("first"), string("second")]);
+
+        assert!(split(&mut ctx, vec![string("abc"), string("")]).is_none());
+        assert!(ctx.has_errors());
+    }
+
+    #[test]
+    fn t_character_indices() {
+        let mut ctx = Context::new();
+
+        let idx = find(&mut ctx, vec![string("été là"), string("là")]).unwrap();
+        assert_eq!(JkInt::from_instance(&idx).0, 4);
+
+        let c = char_at(&mut ctx, vec![string("été"), int(1)]).unwrap();
+        assert_eq!(JkChar::from_instance(&c).0, 't');
+
+        let sub = substring(&mut ctx, vec![string("été là"), int(1), int(3)]).unwrap();
+        assert_eq!(JkString::from_instance(&sub).0, "té");
+
+        assert!(!ctx.has_errors());
+    }
+
+    #[test]
+    fn t_invalid_indices() {
+        let mut ctx = Context::new();
+
+        assert!(char_at(&mut ctx, vec![string("abc"), int(3)]).is_none());
+        assert!(char_at(&mut ctx, vec![string("abc"), int(-1)]).is_none());
+        assert!(substring(&mut ctx, vec![string("héllo"), int(3), int(1)]).is_none());
+        assert_eq!(
+            ctx.error_handler.errors().last().unwrap().msg(),
+            Some("invalid substring of \"héllo\": end index 1 is before start index 3")
+        );
+        assert!(substring(&mut ctx, vec![string("abc"), int(0), int(4)]).is_none());
+
+        assert_eq!(ctx.error_handler.errors().len(), 4);
+    }
+
+    #[test]
+    fn t_invalid_index_stops_program() {
+        let mut ctx = crate::parser::Parser::parse(
+            "s = \"abc\";\nc = s.char_at(3); println(\"{c}\"); assert(false);",
+            None,
+        );
+
+        assert!(ctx.execute().is_err());
+
+        // Neither the use of `c` nor the assertion are executed
+        let errors = ctx.error_handler.errors();
+        assert_eq!(errors.len(), 1);
+
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (2, 5));
+        assert_eq!((loc.end_line, loc.end_column), (2, 17));
+    }
+}
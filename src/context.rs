@@ -13,9 +13,15 @@ use scope_map::ScopeMap;
 
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
-
-use crate::error::{ErrKind, Error, ErrorHandler};
-use crate::instruction::{Block, FunctionDec, FunctionKind, Instruction, TypeDec, TypeId, Var};
+use std::time::Instant;
+
+use crate::builtins::{Builtin, Builtins, Signature};
+use crate::error::{ErrKind, ErrSpaceLocation, Error, ErrorHandler};
+use crate::ffi::Libraries;
+use crate::instruction::{
+    Block, FunctionDec, FunctionKind, Incl, Instruction, TypeDec, TypeId, Var,
+};
+use crate::typechecker::{TypeCheck, TypeCtx};
 use crate::ObjectInstance;
 
 /// Type the context uses for keys
@@ -24,6 +30,22 @@ type CtxKey = String;
 /// Name of the entry point in jinko
 const ENTRY_NAME: &str = "__entry";
 
+/// Control flow signals are raised by instructions which interrupt the normal,
+/// sequential execution of the program. Once raised, a signal stops the execution of
+/// the enclosing blocks until the instruction it targets handles it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlFlow {
+    /// Stop the enclosing loop, which might evaluate to the given value
+    Break(Option<ObjectInstance>),
+    /// Skip to the next iteration of the enclosing loop
+    Continue,
+    /// Stop the enclosing function, which returns the given value
+    Return(Option<ObjectInstance>),
+    /// Stop the whole program after an unrecoverable error, such as a failed assertion
+    /// or `unwrap`
+    Abort,
+}
+
 // FIXME: Rework visibility here
 /// A context represents the state of a jinko program. It contains functions,
 /// variables, tests... and can be optimized, typechecked, executed or
@@ -45,9 +67,24 @@ pub struct Context {
     /// Tests registered in the context
     tests: HashMap<CtxKey, FunctionDec>,
 
+    /// Mocks registered in the context, replacing functions while tests run
+    mocks: HashMap<CtxKey, FunctionDec>,
+
+    /// Functions implemented in the interpreter
+    builtins: Builtins,
+
+    /// Shared libraries containing the external functions
+    libraries: Libraries,
+
     /// Sources included by the context
     included: HashSet<PathBuf>,
 
+    /// Control flow signal currently interrupting the execution, if any
+    control_flow: Option<ControlFlow>,
+
+    /// Location of the statement currently being executed
+    location: Option<ErrSpaceLocation>,
+
     /// Errors being kept by the context
     pub(crate) error_handler: ErrorHandler,
 }
@@ -76,7 +113,12 @@ impl Context {
             path: None,
             scope_map: ScopeMap::new(),
             tests: HashMap::new(),
+            mocks: HashMap::new(),
+            builtins: Builtins::new(),
+            libraries: Libraries::default(),
             included: HashSet::new(),
+            control_flow: None,
+            location: None,
             error_handler: ErrorHandler::default(),
         };
 
@@ -107,17 +149,25 @@ impl Context {
         self.error_handler
             .set_path(self.path.clone().unwrap_or_default());
 
-        match &self.path {
-            Some(p) => {
-                self.included.insert(p.clone());
-            }
-            None => {}
-        };
+        if let Some(p) = &self.path {
+            self.included.insert(p.clone());
+        }
     }
 
-    /// Add an error to the context
+    /// Add an error to the context. Errors which do not have a location are reported
+    /// at the location of the statement being executed
     pub fn error(&mut self, err: Error) {
-        self.error_handler.add(err)
+        self.error_handler.add(err.with_loc(self.location.clone()))
+    }
+
+    /// Change the location of the statement being executed, returning the previous one
+    pub fn set_location(&mut self, loc: Option<ErrSpaceLocation>) -> Option<ErrSpaceLocation> {
+        std::mem::replace(&mut self.location, loc)
+    }
+
+    /// Check if the context currently keeps any error
+    pub fn has_errors(&self) -> bool {
+        self.error_handler.has_errors()
     }
 
     /// Emit all the errors currently kept in the context and remove them
@@ -130,6 +180,21 @@ impl Context {
         self.error_handler.clear();
     }
 
+    /// Raise a control flow signal, interrupting the execution until it gets handled
+    pub fn set_control_flow(&mut self, signal: ControlFlow) {
+        self.control_flow = Some(signal)
+    }
+
+    /// Get the control flow signal currently interrupting the execution, if any
+    pub fn control_flow(&self) -> Option<&ControlFlow> {
+        self.control_flow.as_ref()
+    }
+
+    /// Handle the current control flow signal, removing it from the context
+    pub fn take_control_flow(&mut self) -> Option<ControlFlow> {
+        self.control_flow.take()
+    }
+
     /// Set the debug mode of a previously created ctx
     pub fn set_debug(&mut self, debug: bool) {
         self.debug_mode = debug
@@ -158,12 +223,10 @@ impl Context {
         self.scope_map.remove_variable(var)
     }
 
-    /// Replace a variable or create it if it does not exist
+    /// Replace a variable in the scope it was declared in, or create it if it does not
+    /// exist
     pub fn replace_variable(&mut self, var: Var) -> Result<(), Error> {
-        // Remove the variable if it exists
-        let _ = self.remove_variable(&var);
-
-        self.add_variable(var)
+        self.scope_map.replace_variable(var)
     }
 
     /// Get a mutable reference on an existing function
@@ -171,16 +234,53 @@ impl Context {
         self.scope_map.get_function(name)
     }
 
+    /// Get a reference on a builtin function
+    pub fn get_builtin(&self, name: &str) -> Option<&Builtin> {
+        self.builtins.get(name)
+    }
+
+    /// Register a native Rust function, which jinko code can call like any other
+    /// function. A function of the same name declared in jinko shadows it
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        signature: Signature,
+        function: impl Fn(&mut Context, Vec<ObjectInstance>) -> Option<ObjectInstance> + 'static,
+    ) {
+        self.builtins
+            .add(name, Builtin::new(signature, Rc::new(function)));
+    }
+
+    /// Load a shared library, in which external functions will be looked up
+    pub fn load_library(&mut self, path: &Path) -> Result<(), Error> {
+        self.libraries.load(path)
+    }
+
+    /// Get a reference to the shared libraries loaded by the context
+    pub(crate) fn libraries(&self) -> &Libraries {
+        &self.libraries
+    }
+
     /// Get a reference on an existing variable
     pub fn get_variable(&self, name: &str) -> Option<&Var> {
         self.scope_map.get_variable(name)
     }
 
+    /// Get a mutable reference on an existing variable
+    pub fn get_variable_mut(&mut self, name: &str) -> Option<&mut Var> {
+        self.scope_map.get_variable_mut(name)
+    }
+
     /// Get a reference on an existing type
     pub fn get_type(&self, type_id: &TypeId) -> Option<&Rc<TypeDec>> {
         self.scope_map.get_type(type_id.id())
     }
 
+    /// Get a reference on the enum declaring a variant
+    pub fn get_variant(&self, name: &str) -> Option<&Rc<TypeDec>> {
+        self.scope_map.get_variant(name)
+    }
+
     /// Create a new empty scope
     pub fn scope_enter(&mut self) {
         self.scope_map.scope_enter()
@@ -234,17 +334,176 @@ impl Context {
         }
     }
 
+    /// Register a mock, which replaces the function of the same name while tests run
+    pub fn add_mock(&mut self, mock: FunctionDec) -> Result<(), Error> {
+        match self.mocks.get(mock.name()) {
+            Some(mock) => Err(Error::new(ErrKind::Context)
+                .with_msg(format!("mock function already declared: {}", mock.name()))),
+            None => {
+                self.mocks.insert(mock.name().to_owned(), mock);
+                Ok(())
+            }
+        }
+    }
+
     /// Check if a source is included or not
     pub fn is_included(&self, source: &Path) -> bool {
         self.included.contains(source)
     }
 
+    /// Get the sources included by the context
+    pub(crate) fn included(&self) -> &HashSet<PathBuf> {
+        &self.included
+    }
+
+    /// Typecheck the context's entry point without executing it. Errors are kept in
+    /// the context
+    pub fn type_check(&mut self) {
+        // The entry point always has a block
+        let mut ep = self.entry_point.block().unwrap().clone();
+
+        let mut type_ctx = TypeCtx::new(self);
+        ep.type_of(&mut type_ctx);
+
+        // Keep the types recorded during typechecking for the execution
+        self.entry_point.set_block(ep);
+    }
+
+    /// Run the tests declared in the context whose name contains `filter`, or all of
+    /// them if no filter is given. The declarations of the entry point are typechecked
+    /// and executed, but not its other statements. Each test runs in its own scope,
+    /// with mocks replacing the functions they mock, and fails if it reports any error.
+    /// Returns an error if any of the tests failed
+    pub fn run_tests(&mut self, filter: Option<&str>) -> Result<(), Error> {
+        // The entry point always has a block. Its other statements are not typechecked
+        // either, so that tests cannot use the variables they declare
+        let mut declarations = Block::new();
+        declarations.set_instructions(
+            self.entry_point
+                .block()
+                .unwrap()
+                .instructions()
+                .iter()
+                .filter(|inst| {
+                    inst.is::<FunctionDec>() || inst.is::<TypeDec>() || inst.is::<Incl>()
+                })
+                .cloned()
+                .collect(),
+        );
+
+        let mut type_ctx = TypeCtx::new(self);
+        declarations.type_of(&mut type_ctx);
+
+        if self.error_handler.has_errors() {
+            self.emit_errors();
+            return Err(Error::new(ErrKind::TypeChecker));
+        }
+
+        declarations.instructions().iter().for_each(|inst| {
+            inst.execute(self);
+        });
+
+        if self.error_handler.has_errors() {
+            self.emit_errors();
+            return Err(Error::new(ErrKind::Context));
+        }
+
+        let mut tests: Vec<FunctionDec> = self
+            .tests
+            .values()
+            .filter(|test| filter.map_or(true, |pattern| test.name().contains(pattern)))
+            .cloned()
+            .collect();
+        tests.sort_by(|lhs, rhs| lhs.name().cmp(rhs.name()));
+
+        let mocks: HashMap<CtxKey, Rc<FunctionDec>> = self
+            .mocks
+            .iter()
+            .map(|(name, mock)| (name.clone(), Rc::new(mock.clone())))
+            .collect();
+
+        let filtered_out = self.tests.len() - tests.len();
+        let mut failed = 0;
+
+        println!("running {} tests", tests.len());
+
+        for test in tests.iter() {
+            let start = Instant::now();
+
+            self.scope_enter();
+            self.scope_map.set_mocks(mocks.clone());
+
+            match test.args().is_empty() {
+                true => {
+                    test.run(self);
+                    // An aborted test does not stop the other tests
+                    self.take_control_flow();
+                }
+                false => self.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!("test `{}` cannot take arguments", test.name()))
+                        .with_loc(test.location().cloned()),
+                ),
+            }
+
+            self.scope_map.clear_mocks();
+            self.scope_exit();
+
+            let status = match self.error_handler.has_errors() {
+                true => "FAILED".red(),
+                false => "ok".green(),
+            };
+            println!(
+                "test {} ... {} ({:?})",
+                test.name(),
+                status,
+                start.elapsed()
+            );
+
+            if self.error_handler.has_errors() {
+                failed += 1;
+                self.emit_errors();
+                self.clear_errors();
+            }
+        }
+
+        let status = match failed {
+            0 => "ok".green(),
+            _ => "FAILED".red(),
+        };
+        println!(
+            "\ntest result: {}. {} passed; {} failed; {} filtered out",
+            status,
+            tests.len() - failed,
+            failed,
+            filtered_out
+        );
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(Error::new(ErrKind::Context)),
+        }
+    }
+
     pub fn execute(&mut self) -> Result<Option<ObjectInstance>, Error> {
+        self.type_check();
+
+        if self.error_handler.has_errors() {
+            self.emit_errors();
+            return Err(Error::new(ErrKind::TypeChecker));
+        }
+
         // The entry point always has a block
         let ep = self.entry_point.block().unwrap().clone();
 
         let res = ep.execute(self);
 
+        // Returning from the entry point stops the program
+        let res = match self.take_control_flow() {
+            Some(ControlFlow::Return(value)) => value,
+            _ => res,
+        };
+
         self.emit_errors();
 
         match self.error_handler.has_errors() {
@@ -257,6 +516,7 @@ impl Context {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::Parser;
 
     #[test]
     fn t_redefinition_of_function() {
@@ -279,4 +539,116 @@ mod tests {
         assert_eq!(i.add_variable(v0), Ok(()));
         assert!(i.add_variable(v0_copy).is_err());
     }
+
+    #[test]
+    fn t_run_tests() {
+        let mut ctx = Parser::parse(
+            "test passes() { x = 1; } test fails() { x = 1; x = 2; } 1 / 0",
+            None,
+        );
+
+        assert!(ctx.run_tests(Some("pass")).is_ok());
+        assert!(ctx.run_tests(None).is_err());
+    }
+
+    #[test]
+    fn t_tests_cannot_use_entry_point_variables() {
+        let mut ctx = Parser::parse(
+            "mut counter = 0; test uses_counter() { assert_eq(counter, 0) }",
+            None,
+        );
+
+        assert!(ctx.run_tests(None).is_err());
+        assert_eq!(ctx.error_handler.errors()[0].kind(), ErrKind::TypeChecker);
+    }
+
+    #[test]
+    fn t_typechecker_error_location() {
+        let mut ctx = Parser::parse("a = 1;\nb = a + \"jinko\";\n", None);
+
+        ctx.type_check();
+
+        let errors = ctx.error_handler.errors();
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (2, 5));
+        assert_eq!((loc.end_line, loc.end_column), (2, 16));
+    }
+
+    #[test]
+    fn t_field_type_error_location() {
+        let mut ctx = Parser::parse("type Node<T>(value: T,\n  next: Node);", None);
+
+        ctx.type_check();
+
+        let errors = ctx.error_handler.errors();
+        assert_eq!(errors.len(), 1);
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (2, 3));
+        assert_eq!((loc.end_line, loc.end_column), (2, 13));
+    }
+
+    #[test]
+    fn t_error_location_falls_back_to_statement() {
+        let mut ctx = Parser::parse("a = 1;\n\nfunc f() {}\n  func f() {}", None);
+
+        assert!(ctx.execute().is_err());
+
+        let errors = ctx.error_handler.errors();
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (4, 3));
+    }
+
+    #[test]
+    fn t_duplicate_mock_location() {
+        let mut ctx = Parser::parse(
+            "func f() {}\nmock f() {}\n  mock f() {}\ntest t() { f() }",
+            None,
+        );
+
+        assert!(ctx.run_tests(None).is_err());
+
+        let errors = ctx.error_handler.errors();
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (3, 3));
+    }
+
+    #[test]
+    fn t_register_native() {
+        use crate::{FromObjectInstance, JkInt, ToObjectInstance};
+
+        let mut ctx = Parser::parse("double(21)", None);
+        ctx.register_native(
+            "double",
+            Signature::new(vec![TypeId::from("int")], Some(TypeId::from("int"))),
+            |_, args| Some(JkInt::from(JkInt::from_instance(&args[0]).0 * 2).to_instance()),
+        );
+
+        assert_eq!(ctx.execute().unwrap(), Some(JkInt::from(42).to_instance()));
+    }
+
+    #[test]
+    fn t_native_is_typechecked() {
+        let mut ctx = Parser::parse("double(\"jinko\")", None);
+        ctx.register_native(
+            "double",
+            Signature::new(vec![TypeId::from("int")], Some(TypeId::from("int"))),
+            |_, _| unreachable!("ill-typed calls are not executed"),
+        );
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_native_is_shadowed_by_function() {
+        use crate::{JkInt, ToObjectInstance};
+
+        let mut ctx = Parser::parse("func double(x: int) -> int { x } double(21)", None);
+        ctx.register_native(
+            "double",
+            Signature::new(vec![TypeId::from("int")], Some(TypeId::from("int"))),
+            |_, _| unreachable!("the jinko function shadows the native one"),
+        );
+
+        assert_eq!(ctx.execute().unwrap(), Some(JkInt::from(21).to_instance()));
+    }
 }
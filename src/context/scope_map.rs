@@ -16,6 +16,8 @@ struct Scope {
     variables: HashMap<String, Var>,
     functions: HashMap<String, Rc<FunctionDec>>,
     types: HashMap<String, Rc<TypeDec>>,
+    /// Enums declared in the scope, by the names of their variants
+    variants: HashMap<String, Rc<TypeDec>>,
 }
 
 impl Scope {
@@ -25,6 +27,7 @@ impl Scope {
             variables: HashMap::new(),
             functions: HashMap::new(),
             types: HashMap::new(),
+            variants: HashMap::new(),
         }
     }
 
@@ -33,6 +36,12 @@ impl Scope {
         self.variables.get(name)
     }
 
+    /// Get a mutable reference on a variable from the scope map if is has been inserted
+    /// already
+    pub fn get_variable_mut(&mut self, name: &str) -> Option<&mut Var> {
+        self.variables.get_mut(name)
+    }
+
     /// Get a reference on a function from the scope map if is has been inserted already
     pub fn get_function(&self, name: &str) -> Option<&Rc<FunctionDec>> {
         self.functions.get(name)
@@ -43,6 +52,11 @@ impl Scope {
         self.types.get(name)
     }
 
+    /// Get a reference on the enum declaring a variant, if it has been inserted already
+    pub fn get_variant(&self, name: &str) -> Option<&Rc<TypeDec>> {
+        self.variants.get(name)
+    }
+
     /// Add a variable to the most recently created scope, if it doesn't already exist
     pub fn add_variable(&mut self, var: Var) -> Result<(), Error> {
         match self.get_variable(var.name()) {
@@ -79,17 +93,32 @@ impl Scope {
         }
     }
 
-    /// Add a type to the most recently created scope, if it doesn't already exist
+    /// Add a type to the most recently created scope, if it doesn't already exist. The
+    /// variants of enums are added along with them
     pub fn add_type(&mut self, type_dec: TypeDec) -> Result<(), Error> {
-        match self.get_type(type_dec.name()) {
-            Some(_) => Err(Error::new(ErrKind::Context)
-                .with_msg(format!("type already declared: {}", type_dec.name()))),
-            None => {
-                self.types
-                    .insert(type_dec.name().to_owned(), Rc::new(type_dec));
-                Ok(())
-            }
+        if self.get_type(type_dec.name()).is_some() {
+            return Err(Error::new(ErrKind::Context)
+                .with_msg(format!("type already declared: {}", type_dec.name())));
+        }
+
+        if let Some(variant) = type_dec
+            .variants()
+            .iter()
+            .find(|variant| self.get_variant(variant.name()).is_some())
+        {
+            return Err(Error::new(ErrKind::Context)
+                .with_msg(format!("variant already declared: {}", variant.name())));
         }
+
+        let type_dec = Rc::new(type_dec);
+        for variant in type_dec.variants() {
+            self.variants
+                .insert(variant.name().to_owned(), type_dec.clone());
+        }
+
+        self.types.insert(type_dec.name().to_owned(), type_dec);
+
+        Ok(())
     }
 
     /// Display all contained information on stdout
@@ -116,6 +145,9 @@ type ScopeStack<T> = LinkedList<T>;
 #[derive(Clone)]
 pub struct ScopeMap {
     scopes: ScopeStack<Scope>,
+
+    /// Functions replacing the ones of the same name, regardless of scopes
+    mocks: HashMap<String, Rc<FunctionDec>>,
 }
 
 impl ScopeMap {
@@ -123,9 +155,20 @@ impl ScopeMap {
     pub fn new() -> ScopeMap {
         ScopeMap {
             scopes: ScopeStack::new(),
+            mocks: HashMap::new(),
         }
     }
 
+    /// Replace functions by mocks of the same name until the mocks are cleared
+    pub fn set_mocks(&mut self, mocks: HashMap<String, Rc<FunctionDec>>) {
+        self.mocks = mocks
+    }
+
+    /// Restore the functions replaced by mocks
+    pub fn clear_mocks(&mut self) {
+        self.mocks.clear()
+    }
+
     /// Enter into a new scope
     pub fn scope_enter(&mut self) {
         self.scopes.push_front(Scope::new());
@@ -151,8 +194,20 @@ impl ScopeMap {
         None
     }
 
-    /// Maybe get a function in any available scopes
+    /// Maybe get a mutable reference on a variable in any available scopes
+    pub fn get_variable_mut(&mut self, name: &str) -> Option<&mut Var> {
+        self.scopes
+            .iter_mut()
+            .find_map(|scope| scope.get_variable_mut(name))
+    }
+
+    /// Maybe get a function in any available scopes. If the function is mocked, the
+    /// mock is returned instead
     pub fn get_function(&self, name: &str) -> Option<&Rc<FunctionDec>> {
+        if let Some(mock) = self.mocks.get(name) {
+            return Some(mock);
+        }
+
         // FIXME: Use find for code quality?
         for scope in self.scopes.iter() {
             match scope.get_function(name) {
@@ -177,6 +232,11 @@ impl ScopeMap {
         None
     }
 
+    /// Maybe get the enum declaring a variant in any available scopes
+    pub fn get_variant(&self, name: &str) -> Option<&Rc<TypeDec>> {
+        self.scopes.iter().find_map(|scope| scope.get_variant(name))
+    }
+
     /// Add a variable to the current scope if it hasn't been added before
     pub fn add_variable(&mut self, var: Var) -> Result<(), Error> {
         match self.scopes.front_mut() {
@@ -195,6 +255,22 @@ impl ScopeMap {
         }
     }
 
+    /// Replace a variable in the scope it was declared in, or add it to the current
+    /// scope if it does not exist yet
+    pub fn replace_variable(&mut self, var: Var) -> Result<(), Error> {
+        match self
+            .scopes
+            .iter_mut()
+            .find(|scope| scope.get_variable(var.name()).is_some())
+        {
+            Some(scope) => {
+                scope.remove_variable(&var)?;
+                scope.add_variable(var)
+            }
+            None => self.add_variable(var),
+        }
+    }
+
     /// Add a function to the current scope if it hasn't been added before
     pub fn add_function(&mut self, func: FunctionDec) -> Result<(), Error> {
         match self.scopes.front_mut() {
@@ -224,6 +300,7 @@ impl ScopeMap {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instruction::TypeId;
 
     #[test]
     #[should_panic]
@@ -260,6 +337,22 @@ mod tests {
         assert!(s.get_variable("a").is_some());
     }
 
+    #[test]
+    fn t_replace_var_from_inner_scope() {
+        let mut s = ScopeMap::new();
+
+        s.scope_enter();
+        s.add_variable(Var::new("a".to_owned())).unwrap();
+
+        s.scope_enter();
+        let mut replacement = Var::new("a".to_owned());
+        replacement.set_mutable(true);
+        s.replace_variable(replacement).unwrap();
+        s.scope_exit();
+
+        assert!(s.get_variable("a").unwrap().mutable());
+    }
+
     #[test]
     fn t_add_var_and_get_it_from_inner_scope() {
         let mut s = ScopeMap::new();
@@ -288,4 +381,44 @@ mod tests {
 
         assert!(s.get_variable("a").is_none());
     }
+
+    #[test]
+    fn t_mock_replaces_function() {
+        let mut s = ScopeMap::new();
+
+        s.scope_enter();
+        s.add_function(FunctionDec::new("f".to_owned(), None))
+            .unwrap();
+
+        let mock = Rc::new(FunctionDec::new("f".to_owned(), Some(TypeId::from("int"))));
+        s.set_mocks(vec![("f".to_owned(), mock)].into_iter().collect());
+        assert!(s.get_function("f").unwrap().ty().is_some());
+
+        s.clear_mocks();
+        assert!(s.get_function("f").unwrap().ty().is_none());
+    }
+
+    #[test]
+    fn t_add_enum_declares_variants() {
+        use crate::instruction::Variant;
+
+        let mut s = ScopeMap::new();
+
+        s.scope_enter();
+        let variants = vec![
+            Variant::new("Circle".to_owned(), vec![]),
+            Variant::new("Empty".to_owned(), vec![]),
+        ];
+        s.add_type(TypeDec::new_enum("Shape".to_owned(), variants))
+            .unwrap();
+
+        assert_eq!(s.get_variant("Empty").unwrap().name(), "Shape");
+        assert!(s.get_variant("Shape").is_none());
+
+        let variants = vec![Variant::new("Circle".to_owned(), vec![])];
+        assert!(s
+            .add_type(TypeDec::new_enum("Round".to_owned(), variants))
+            .is_err());
+        assert!(s.get_type("Round").is_none());
+    }
 }
@@ -1,8 +1,10 @@
 //! The Error module contains helpful wrapper around possible errors in jinko. They
 //! are used by the context as well as the parser.
 
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use colored::Colorize;
 
@@ -30,9 +32,9 @@ impl ErrorHandler {
         self.errors.clear()
     }
 
-    // FIXME: Remove this once location is implemented
-    /// Set the file that should be used by the error handler. This function should be
-    /// removed once locations are kept properly in the different instructions
+    /// Set the file that should be used by the error handler for errors which do not
+    /// carry a location, such as errors coming from instructions which were not parsed
+    /// from a source file
     pub fn set_path(&mut self, file: PathBuf) {
         self.file = file;
     }
@@ -41,26 +43,88 @@ impl ErrorHandler {
     pub fn has_errors(&self) -> bool {
         !self.errors.is_empty()
     }
+
+    #[cfg(test)]
+    pub fn errors(&self) -> &Vec<Error> {
+        &self.errors
+    }
 }
 
-// FIXME: Location should not be in the error part only
-/// Contains indications vis-a-vis the error's location in the source file
+/// Contains indications vis-a-vis the location of an instruction in its source file.
+/// Lines and columns start at 1. The end of the location is exclusive
 #[derive(Debug, PartialEq, Clone)]
 pub struct ErrSpaceLocation {
+    pub path: Option<Arc<PathBuf>>,
     pub line: usize,
-    pub offset: usize,
-    pub input: &'static str,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// Source code the location points into, shared by all the locations computed
+    /// while parsing it
+    source: Option<Arc<str>>,
 }
 
-// FIXME: Add better API?
 impl ErrSpaceLocation {
-    pub fn new(line: usize, offset: usize, input: &'static str) -> ErrSpaceLocation {
+    pub fn new(
+        path: Option<Arc<PathBuf>>,
+        (line, column): (usize, usize),
+        (end_line, end_column): (usize, usize),
+    ) -> ErrSpaceLocation {
         ErrSpaceLocation {
+            path,
             line,
-            offset,
-            input,
+            column,
+            end_line,
+            end_column,
+            source: None,
+        }
+    }
+
+    /// Keep the source code the location points into, so that it can be printed
+    /// when emitting an error
+    pub fn with_source(self, source: Arc<str>) -> ErrSpaceLocation {
+        ErrSpaceLocation {
+            source: Some(source),
+            ..self
         }
     }
+
+    /// Number of characters to underline on the first line of the location
+    fn underline_len(&self, source_line: &str) -> usize {
+        let end = match self.end_line == self.line {
+            true => self.end_column,
+            false => source_line.chars().count() + 1,
+        };
+
+        end.saturating_sub(self.column).max(1)
+    }
+
+    /// Print the source line the location starts on, underlining the location
+    fn emit_snippet(&self) {
+        // Instructions which were not parsed, such as the ones created by the
+        // interpreter, have no source to print
+        let source = match &self.source {
+            Some(source) => source,
+            None => return,
+        };
+        let source_line = match source.lines().nth(self.line - 1) {
+            Some(l) => l,
+            None => return,
+        };
+
+        eprintln!("{:>3} | {}", self.line, source_line);
+        eprintln!(
+            "    | {}{}",
+            " ".repeat(self.column - 1),
+            "^".repeat(self.underline_len(source_line)).red()
+        );
+    }
+}
+
+impl Display for ErrSpaceLocation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -70,6 +134,7 @@ pub enum ErrKind {
     Context,
     TypeChecker,
     IO,
+    Assertion,
 }
 
 impl ErrKind {
@@ -79,6 +144,7 @@ impl ErrKind {
             ErrKind::Context => "Interpreter",
             ErrKind::TypeChecker => "Typechecker",
             ErrKind::IO => "I/O",
+            ErrKind::Assertion => "Assertion",
         }
     }
 }
@@ -88,6 +154,9 @@ pub struct Error {
     kind: ErrKind,
     msg: Option<String>,
     loc: Option<ErrSpaceLocation>,
+    /// Length of the input which was left when parsing failed. The parser locates
+    /// syntax errors using it, once it knows which ones to report
+    remaining: Option<usize>,
 }
 
 impl Error {
@@ -95,10 +164,22 @@ impl Error {
         let kind_str = self.kind.as_str();
 
         eprintln!("Error type: {}", kind_str.red());
-        eprintln!(" ===> {}", file.to_string_lossy().green());
 
-        // FIXME: Is the formatting correct?
-        eprintln!("    |");
+        match &self.loc {
+            Some(loc) => {
+                let file = loc.path.as_deref().map_or(file, PathBuf::as_path);
+                let position = format!("{}:{}", file.to_string_lossy(), loc);
+
+                eprintln!(" ===> {}", position.green());
+                eprintln!("    |");
+                loc.emit_snippet();
+            }
+            None => {
+                eprintln!(" ===> {}", file.to_string_lossy().green());
+                eprintln!("    |");
+            }
+        }
+
         for line in self.msg.as_deref().unwrap_or("").lines() {
             eprintln!("    | {}", line);
         }
@@ -110,6 +191,7 @@ impl Error {
             kind,
             msg: None,
             loc: None,
+            remaining: None,
         }
     }
 
@@ -120,13 +202,45 @@ impl Error {
         }
     }
 
-    pub fn with_loc(self, loc: ErrSpaceLocation) -> Error {
+    /// Attach a location to the error, if it does not have one already. This way, the
+    /// innermost instruction responsible for an error is the one being reported
+    pub fn with_loc(self, loc: Option<ErrSpaceLocation>) -> Error {
         Error {
-            loc: Some(loc),
+            loc: self.loc.or(loc),
             ..self
         }
     }
 
+    /// Record that parsing failed with `input` left to parse, if the error does not
+    /// know where it happened already
+    pub(crate) fn with_remaining(self, input: &str) -> Error {
+        Error {
+            remaining: self.remaining.or(Some(input.len())),
+            ..self
+        }
+    }
+
+    /// Length of the input which was left when parsing failed, if this is a syntax
+    /// error produced while parsing
+    pub(crate) fn remaining(&self) -> Option<usize> {
+        self.remaining
+    }
+
+    #[cfg(test)]
+    pub fn kind(&self) -> ErrKind {
+        self.kind
+    }
+
+    #[cfg(test)]
+    pub fn msg(&self) -> Option<&str> {
+        self.msg.as_deref()
+    }
+
+    #[cfg(test)]
+    pub fn loc(&self) -> Option<&ErrSpaceLocation> {
+        self.loc.as_ref()
+    }
+
     pub fn exit(&self) {
         // The exit code depends on the kind of error
         std::process::exit(self.kind as i32 + 1);
@@ -164,19 +278,36 @@ impl From<nom::Err<Error>> for Error {
     }
 }
 
+/// Errors produced by nom's parsers remember where the parser failed. Among the errors
+/// of alternative parsers, the one which went the furthest in the input is kept, as it
+/// is the most likely to describe what the user meant to write
 impl nom::error::ParseError<&str> for Error {
     fn from_error_kind(input: &str, _: nom::error::ErrorKind) -> Error {
-        Error::new(ErrKind::Parsing).with_msg(String::from(input))
+        Error::new(ErrKind::Parsing)
+            .with_msg(String::from("unexpected input"))
+            .with_remaining(input)
+    }
+
+    fn append(_: &str, _: nom::error::ErrorKind, other: Error) -> Error {
+        // The innermost error is the most precise one
+        other
     }
 
-    fn append(input: &str, _: nom::error::ErrorKind, _other: Error) -> Error {
-        // FIXME: Should we accumulate errors this way?
-        // let other_msg = match other.msg {
-        //     Some(msg) => format!("{}\n", msg),
-        //     None => String::new(),
-        // };
+    fn or(self, other: Error) -> Error {
+        // Errors which do not know where they happened are the least relevant ones
+        let position = |e: &Error| e.remaining.map(std::cmp::Reverse);
 
-        Error::new(ErrKind::Parsing).with_msg(String::from(input)) // /* FIXME  */ with_msg(format!("{}{}", other_msg, input))
+        match position(&other).cmp(&position(&self)) {
+            Ordering::Greater => other,
+            Ordering::Less => self,
+            // Parsers failing at the same position expected different things, none of
+            // which is more relevant than the others
+            Ordering::Equal if self.msg != other.msg => Error {
+                msg: Some(String::from("unexpected input")),
+                ..self
+            },
+            Ordering::Equal => self,
+        }
     }
 }
 
@@ -0,0 +1,359 @@
+//! The FFI module allows jinko programs to call functions from shared libraries. These
+//! functions are declared using the `ext` keyword, and looked up by name in the
+//! libraries given to the context. Arguments and return values are converted between
+//! jinko's primitive types and their C equivalents:
+//!
+//! | jinko    | C               |
+//! |----------|-----------------|
+//! | `int`    | `int64_t`       |
+//! | `float`  | `double`        |
+//! | `bool`   | `bool`          |
+//! | `char`   | `uint32_t`      |
+//! | `string` | `const char *`  |
+//!
+//! Strings given to native functions are only valid for the duration of the call.
+//! Strings returned by native functions are copied, and never freed by jinko.
+
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+
+use libloading::Library;
+
+use crate::instruction::{FunctionDec, TypeId};
+use crate::{
+    Context, ErrKind, Error, FromObjectInstance, JkBool, JkChar, JkFloat, JkInt, JkString,
+    ObjectInstance, ToObjectInstance,
+};
+
+/// Maximum number of arguments an external function can take
+const MAX_ARGS: usize = 4;
+
+/// Argument given to a native function, with the C type it is passed as
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Arg {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(u32),
+    String(*const c_char),
+}
+
+/// C type returned by a native function
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RetKind {
+    Void,
+    Int,
+    Float,
+    Bool,
+    Char,
+    String,
+}
+
+/// Value returned by a native function
+#[derive(Debug, PartialEq)]
+enum Ret {
+    Void,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(u32),
+    String(*const c_char),
+}
+
+/// Call a native function through a function pointer of the right signature. The
+/// signature is built by matching the arguments one by one
+macro_rules! native_call {
+    ($symbol:expr, $ret:expr, [$($ty:ty => $arg:expr),*]) => {
+        match $ret {
+            RetKind::Void => {
+                std::mem::transmute::<*const c_void, extern "C" fn($($ty),*)>($symbol)($($arg),*);
+                Ret::Void
+            }
+            RetKind::Int => Ret::Int(
+                std::mem::transmute::<*const c_void, extern "C" fn($($ty),*) -> i64>($symbol)($($arg),*)
+            ),
+            RetKind::Float => Ret::Float(
+                std::mem::transmute::<*const c_void, extern "C" fn($($ty),*) -> f64>($symbol)($($arg),*)
+            ),
+            RetKind::Bool => Ret::Bool(
+                std::mem::transmute::<*const c_void, extern "C" fn($($ty),*) -> bool>($symbol)($($arg),*)
+            ),
+            RetKind::Char => Ret::Char(
+                std::mem::transmute::<*const c_void, extern "C" fn($($ty),*) -> u32>($symbol)($($arg),*)
+            ),
+            RetKind::String => Ret::String(
+                std::mem::transmute::<*const c_void, extern "C" fn($($ty),*) -> *const c_char>($symbol)($($arg),*)
+            ),
+        }
+    };
+    ($symbol:expr, $ret:expr, [$($ty:ty => $arg:expr),*], $next:expr $(, $rest:expr)*) => {
+        match $next {
+            Arg::Int(value) => native_call!($symbol, $ret, [$($ty => $arg,)* i64 => value] $(, $rest)*),
+            Arg::Float(value) => native_call!($symbol, $ret, [$($ty => $arg,)* f64 => value] $(, $rest)*),
+            Arg::Bool(value) => native_call!($symbol, $ret, [$($ty => $arg,)* bool => value] $(, $rest)*),
+            Arg::Char(value) => native_call!($symbol, $ret, [$($ty => $arg,)* u32 => value] $(, $rest)*),
+            Arg::String(value) => native_call!($symbol, $ret, [$($ty => $arg,)* *const c_char => value] $(, $rest)*),
+        }
+    };
+}
+
+/// Call the native function located at `symbol`.
+///
+/// # Safety
+///
+/// The native function must have the signature described by `args` and `ret`, and
+/// `args` must not contain more than `MAX_ARGS` arguments
+unsafe fn dispatch(symbol: *const c_void, ret: RetKind, args: &[Arg]) -> Ret {
+    match *args {
+        [] => native_call!(symbol, ret, []),
+        [a] => native_call!(symbol, ret, [], a),
+        [a, b] => native_call!(symbol, ret, [], a, b),
+        [a, b, c] => native_call!(symbol, ret, [], a, b, c),
+        [a, b, c, d] => native_call!(symbol, ret, [], a, b, c, d),
+        _ => unreachable!("external functions take at most {} arguments", MAX_ARGS),
+    }
+}
+
+fn unsupported_type(ty: &TypeId) -> Error {
+    Error::new(ErrKind::Context).with_msg(format!(
+        "type `{}` cannot be used by external functions",
+        ty.id()
+    ))
+}
+
+/// Convert an instance to the argument expected by a native function. Strings are
+/// kept alive in `strings` until the end of the call
+fn to_arg(
+    ty: &TypeId,
+    instance: &ObjectInstance,
+    strings: &mut Vec<CString>,
+) -> Result<Arg, Error> {
+    match ty.id() {
+        "int" => Ok(Arg::Int(JkInt::from_instance(instance).0)),
+        "float" => Ok(Arg::Float(JkFloat::from_instance(instance).0)),
+        "bool" => Ok(Arg::Bool(JkBool::from_instance(instance).0)),
+        "char" => Ok(Arg::Char(JkChar::from_instance(instance).0 as u32)),
+        "string" => {
+            let string = CString::new(JkString::from_instance(instance).0).map_err(|_| {
+                Error::new(ErrKind::Context).with_msg(String::from(
+                    "strings given to external functions cannot contain null bytes",
+                ))
+            })?;
+            let arg = Arg::String(string.as_ptr());
+            strings.push(string);
+
+            Ok(arg)
+        }
+        _ => Err(unsupported_type(ty)),
+    }
+}
+
+fn ret_kind(ty: Option<&TypeId>) -> Result<RetKind, Error> {
+    match ty.map(TypeId::id) {
+        None => Ok(RetKind::Void),
+        Some("int") => Ok(RetKind::Int),
+        Some("float") => Ok(RetKind::Float),
+        Some("bool") => Ok(RetKind::Bool),
+        Some("char") => Ok(RetKind::Char),
+        Some("string") => Ok(RetKind::String),
+        Some(_) => Err(unsupported_type(ty.unwrap())),
+    }
+}
+
+/// Convert the value returned by a native function to an instance
+fn from_ret(ret: Ret) -> Result<Option<ObjectInstance>, Error> {
+    let instance = match ret {
+        Ret::Void => return Ok(None),
+        Ret::Int(value) => JkInt::from(value).to_instance(),
+        Ret::Float(value) => JkFloat::from(value).to_instance(),
+        Ret::Bool(value) => JkBool::from(value).to_instance(),
+        Ret::Char(value) => match std::char::from_u32(value) {
+            Some(c) => JkChar::from(c).to_instance(),
+            None => {
+                return Err(Error::new(ErrKind::Context).with_msg(format!(
+                    "external function returned an invalid character: {:#x}",
+                    value
+                )))
+            }
+        },
+        Ret::String(ptr) if ptr.is_null() => {
+            return Err(Error::new(ErrKind::Context)
+                .with_msg(String::from("external function returned a null string")))
+        }
+        Ret::String(ptr) => {
+            let string = unsafe { CStr::from_ptr(ptr) };
+            JkString::from(string.to_string_lossy().as_ref()).to_instance()
+        }
+    };
+
+    Ok(Some(instance))
+}
+
+/// Shared libraries in which external functions are looked up
+#[derive(Default)]
+pub struct Libraries {
+    libraries: Vec<Library>,
+}
+
+impl Libraries {
+    /// Load a shared library
+    pub fn load(&mut self, path: &Path) -> Result<(), Error> {
+        // Loading a library runs its initialization routines, which we have to trust
+        let library = unsafe { Library::new(path) }.map_err(|e| {
+            Error::new(ErrKind::IO).with_msg(format!("cannot load library {:?}: {}", path, e))
+        })?;
+
+        self.libraries.push(library);
+
+        Ok(())
+    }
+
+    /// Address of a symbol in the first library which contains it
+    fn symbol(&self, name: &str) -> Option<*const c_void> {
+        self.libraries.iter().find_map(|library| {
+            let symbol = unsafe { library.get::<*const c_void>(name.as_bytes()) };
+
+            symbol.ok().map(|symbol| *symbol)
+        })
+    }
+}
+
+/// Call an external function with the instances given to its arguments by the
+/// function call
+pub fn call(
+    function: &FunctionDec,
+    instances: &[ObjectInstance],
+    ctx: &mut Context,
+) -> Option<ObjectInstance> {
+    match try_call(function, instances, ctx) {
+        Ok(ret_val) => ret_val,
+        Err(e) => {
+            ctx.error(e);
+            None
+        }
+    }
+}
+
+fn try_call(
+    function: &FunctionDec,
+    instances: &[ObjectInstance],
+    ctx: &mut Context,
+) -> Result<Option<ObjectInstance>, Error> {
+    let symbol = ctx.libraries().symbol(function.name()).ok_or_else(|| {
+        Error::new(ErrKind::Context).with_msg(format!(
+            "cannot find external function `{}` in the linked libraries",
+            function.name()
+        ))
+    })?;
+
+    if function.args().len() > MAX_ARGS {
+        return Err(Error::new(ErrKind::Context).with_msg(format!(
+            "external function `{}` takes more than {} arguments",
+            function.name(),
+            MAX_ARGS
+        )));
+    }
+
+    let ret = ret_kind(function.ty())?;
+
+    let mut strings = vec![];
+    let mut args = vec![];
+    for (arg, instance) in function.args().iter().zip(instances) {
+        args.push(to_arg(arg.get_type(), instance, &mut strings)?);
+    }
+
+    ctx.debug("EXT CALL", function.name());
+
+    // The declaration of the function is trusted to match its native signature
+    let ret_val = unsafe { dispatch(symbol, ret, &args) };
+
+    from_ret(ret_val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn add(lhs: i64, rhs: i64) -> i64 {
+        lhs + rhs
+    }
+
+    extern "C" fn mixed(a: i64, b: f64, c: bool, d: u32) -> f64 {
+        match c {
+            true => a as f64 * b + d as f64,
+            false => 0.0,
+        }
+    }
+
+    extern "C" fn first_byte(s: *const c_char) -> i64 {
+        unsafe { *s as i64 }
+    }
+
+    extern "C" fn greeting() -> *const c_char {
+        b"hello\0".as_ptr() as *const c_char
+    }
+
+    #[test]
+    fn t_dispatch_int() {
+        let ret = unsafe {
+            dispatch(
+                add as *const c_void,
+                RetKind::Int,
+                &[Arg::Int(4), Arg::Int(5)],
+            )
+        };
+
+        assert_eq!(ret, Ret::Int(9));
+    }
+
+    #[test]
+    fn t_dispatch_mixed_args() {
+        let args = [
+            Arg::Int(2),
+            Arg::Float(1.5),
+            Arg::Bool(true),
+            Arg::Char('a' as u32),
+        ];
+        let ret = unsafe { dispatch(mixed as *const c_void, RetKind::Float, &args) };
+
+        assert_eq!(ret, Ret::Float(100.0));
+    }
+
+    #[test]
+    fn t_dispatch_string() {
+        let string = CString::new("jinko").unwrap();
+        let args = [Arg::String(string.as_ptr())];
+        let ret = unsafe { dispatch(first_byte as *const c_void, RetKind::Int, &args) };
+
+        assert_eq!(ret, Ret::Int('j' as i64));
+    }
+
+    #[test]
+    fn t_returned_string_is_copied() {
+        let ret = unsafe { dispatch(greeting as *const c_void, RetKind::String, &[]) };
+
+        assert_eq!(
+            from_ret(ret).unwrap(),
+            Some(JkString::from("hello").to_instance())
+        );
+    }
+
+    #[test]
+    fn t_unsupported_types() {
+        assert!(ret_kind(Some(&TypeId::from("Point"))).is_err());
+        assert!(to_arg(
+            &TypeId::from("Point"),
+            &ObjectInstance::empty(),
+            &mut vec![]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn t_missing_library() {
+        let mut libraries = Libraries::default();
+
+        assert!(libraries.load(Path::new("does/not/exist.so")).is_err());
+    }
+}
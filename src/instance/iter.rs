@@ -0,0 +1,107 @@
+//! Iterable instances produce their values one at a time, so that a `for` loop never
+//! needs to collect all of them before running its first iteration. Ranges count from
+//! their start to their end, arrays produce their elements by index, and maps produce
+//! their entries in insertion order.
+//! The typechecker and the interpreter both go through this module to know which
+//! types are iterable, and what the values they produce are.
+
+use crate::instruction::{TypeDec, TypeId};
+use crate::{ErrKind, Error, FromObjectInstance, JkInt, ObjectInstance, ToObjectInstance};
+
+/// Name of the standard library's type returned by `range(start, end)`
+const RANGE_TYPE: &str = "Range";
+
+/// Type of the values produced when iterating over an instance of the given type, if
+/// that type is iterable
+pub fn iterated_type(ty: &TypeId) -> Option<TypeId> {
+    match ty.id() {
+        RANGE_TYPE => Some(TypeId::from("int")),
+        _ => ty.element_type(),
+    }
+}
+
+/// Types of the keys and values produced when iterating over the entries of an
+/// instance of the given type, if that type is a map
+pub fn iterated_entry_types(ty: &TypeId) -> Option<(TypeId, TypeId)> {
+    ty.map_types()
+        .map(|(key, value)| (key.clone(), value.clone()))
+}
+
+/// Iterator over the values of an iterable instance
+pub enum InstanceIter<'i> {
+    /// Integers going from `next` to `end`, excluded
+    Range { next: i64, end: i64 },
+    /// Elements of an array, starting at index `next`
+    Elements {
+        instance: &'i ObjectInstance,
+        next: usize,
+    },
+}
+
+/// Iterator over the entries of a map, as pairs of a key and its value
+pub struct EntryIter<'i>(Box<dyn Iterator<Item = (&'i ObjectInstance, &'i ObjectInstance)> + 'i>);
+
+impl Iterator for InstanceIter<'_> {
+    type Item = ObjectInstance;
+
+    fn next(&mut self) -> Option<ObjectInstance> {
+        match self {
+            InstanceIter::Range { next, end } if *next < *end => {
+                let value = JkInt::from(*next).to_instance();
+                *next += 1;
+
+                Some(value)
+            }
+            InstanceIter::Range { .. } => None,
+            InstanceIter::Elements { instance, next } => {
+                let element = instance.elements().get(*next)?.clone();
+                *next += 1;
+
+                Some(element)
+            }
+        }
+    }
+}
+
+impl Iterator for EntryIter<'_> {
+    type Item = (ObjectInstance, ObjectInstance);
+
+    fn next(&mut self) -> Option<(ObjectInstance, ObjectInstance)> {
+        let (key, value) = self.0.next()?;
+
+        Some((key.clone(), value.clone()))
+    }
+}
+
+impl ObjectInstance {
+    /// Start iterating over the values of the instance, or return an error if the
+    /// instance is not iterable
+    pub fn iter(&self) -> Result<InstanceIter<'_>, Error> {
+        match self.ty().map(TypeDec::name) {
+            Some(RANGE_TYPE) => Ok(InstanceIter::Range {
+                next: JkInt::from_instance(&self.get_field("start")?).0,
+                end: JkInt::from_instance(&self.get_field("end")?).0,
+            }),
+            _ if self.is_array() => Ok(InstanceIter::Elements {
+                instance: self,
+                next: 0,
+            }),
+            ty => Err(Error::new(ErrKind::Context).with_msg(format!(
+                "cannot iterate over an instance of type `{}`",
+                ty.unwrap_or("")
+            ))),
+        }
+    }
+
+    /// Start iterating over the entries of the instance, or return an error if the
+    /// instance is not a map
+    pub fn iter_entries(&self) -> Result<EntryIter<'_>, Error> {
+        match self.map_entries() {
+            Some(entries) => Ok(EntryIter(Box::new(entries.iter()))),
+            None => Err(Error::new(ErrKind::Context).with_msg(format!(
+                "cannot iterate over the entries of an instance of type `{}`",
+                self.ty().map_or("", TypeDec::name)
+            ))),
+        }
+    }
+}
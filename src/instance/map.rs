@@ -0,0 +1,202 @@
+//! Maps keep their entries in insertion order, along with a table giving the position
+//! of each key, so that looking up, inserting or removing a key does not go through
+//! all the entries. Removing an entry leaves an empty slot behind, and the slots are
+//! compacted once they make up half of the entries.
+
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+use super::{Name, ObjectInstance};
+
+/// Identity of an instance used as the key of a map. Keys are compared structurally:
+/// by their type and variant, the raw data of primitive values, and the keys of their
+/// fields, elements and entries. Two instances are the same key if they contain the
+/// same value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct MapKey {
+    ty: Option<Name>,
+    variant: Option<Name>,
+    data: Vec<u8>,
+    fields: Vec<(Name, MapKey)>,
+    elements: Vec<MapKey>,
+    entries: Vec<(MapKey, MapKey)>,
+}
+
+impl From<&ObjectInstance> for MapKey {
+    fn from(instance: &ObjectInstance) -> MapKey {
+        // The data of an instance with fields is the concatenation of their data, which
+        // does not tell where each field starts
+        let (data, fields) = match &instance.fields {
+            Some(fields) => {
+                let mut fields: Vec<(Name, MapKey)> = fields
+                    .iter()
+                    .map(|(name, field)| (name.clone(), MapKey::from(field.instance())))
+                    .collect();
+                fields.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+
+                (vec![], fields)
+            }
+            None => (instance.data.clone(), vec![]),
+        };
+
+        MapKey {
+            ty: instance.ty().map(|ty| ty.name().to_string()),
+            variant: instance.variant.clone(),
+            data,
+            fields,
+            elements: instance.elements().iter().map(MapKey::from).collect(),
+            entries: instance
+                .map_entries()
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .map(|(key, value)| (MapKey::from(key), MapKey::from(value)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Entries of a map, as pairs of a key and its value
+#[derive(Debug, Clone, Default)]
+pub struct MapEntries {
+    slots: Vec<Option<(ObjectInstance, ObjectInstance)>>,
+    positions: HashMap<MapKey, usize>,
+}
+
+impl MapEntries {
+    /// Number of entries in the map
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Does the map contain any entry
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Get the value associated to `key`, if there is one
+    pub fn get(&self, key: &ObjectInstance) -> Option<&ObjectInstance> {
+        let idx = *self.positions.get(&MapKey::from(key))?;
+
+        self.slots[idx].as_ref().map(|(_, value)| value)
+    }
+
+    /// Check if there is a value associated to `key`
+    pub fn contains(&self, key: &ObjectInstance) -> bool {
+        self.positions.contains_key(&MapKey::from(key))
+    }
+
+    /// Associate `value` to `key`. A key which is already present keeps its position
+    /// and gets the new value
+    pub fn insert(&mut self, key: ObjectInstance, value: ObjectInstance) {
+        match self.positions.get(&MapKey::from(&key)) {
+            Some(&idx) => self.slots[idx] = Some((key, value)),
+            None => {
+                self.positions.insert(MapKey::from(&key), self.slots.len());
+                self.slots.push(Some((key, value)));
+            }
+        }
+    }
+
+    /// Remove `key` from the map, returning its value if it was present
+    pub fn remove(&mut self, key: &ObjectInstance) -> Option<ObjectInstance> {
+        let idx = self.positions.remove(&MapKey::from(key))?;
+        let (_, value) = self.slots[idx].take()?;
+
+        if self.slots.len() > 2 * self.positions.len() {
+            self.compact();
+        }
+
+        Some(value)
+    }
+
+    /// Iterate over the entries, in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&ObjectInstance, &ObjectInstance)> {
+        self.slots.iter().flatten().map(|(key, value)| (key, value))
+    }
+
+    /// Remove the empty slots left by removed entries
+    fn compact(&mut self) {
+        let mut new_positions = Vec::with_capacity(self.slots.len());
+        let mut next = 0;
+        for slot in self.slots.iter() {
+            new_positions.push(next);
+            if slot.is_some() {
+                next += 1;
+            }
+        }
+
+        self.slots.retain(Option::is_some);
+        for idx in self.positions.values_mut() {
+            *idx = new_positions[*idx];
+        }
+    }
+}
+
+/// Two maps are equal if they contain the same entries in the same order, regardless
+/// of the slots left by removed entries
+impl PartialEq for MapEntries {
+    fn eq(&self, other: &MapEntries) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl FromIterator<(ObjectInstance, ObjectInstance)> for MapEntries {
+    fn from_iter<I: IntoIterator<Item = (ObjectInstance, ObjectInstance)>>(iter: I) -> MapEntries {
+        let mut entries = MapEntries::default();
+        for (key, value) in iter {
+            entries.insert(key, value);
+        }
+
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::JkString;
+    use crate::{JkInt, ToObjectInstance};
+
+    fn int(value: i64) -> ObjectInstance {
+        JkInt::from(value).to_instance()
+    }
+
+    #[test]
+    fn t_insert_and_remove() {
+        let mut entries: MapEntries = (0..10).map(|i| (int(i), int(i * 10))).collect();
+
+        for i in 0..8 {
+            assert_eq!(entries.remove(&int(i)), Some(int(i * 10)));
+        }
+        entries.insert(int(3), int(3));
+        entries.insert(int(9), int(0));
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries.get(&int(9)), Some(&int(0)));
+        assert!(!entries.contains(&int(0)));
+
+        let keys: Vec<_> = entries.iter().map(|(key, _)| key.clone()).collect();
+        assert_eq!(keys, vec![int(8), int(9), int(3)]);
+    }
+
+    #[test]
+    fn t_keys_with_fields_do_not_collide() {
+        let pair = |a: &str, b: &str| {
+            ObjectInstance::new(
+                None,
+                0,
+                vec![],
+                Some(vec![
+                    (String::from("a"), JkString::from(a).to_instance()),
+                    (String::from("b"), JkString::from(b).to_instance()),
+                ]),
+            )
+        };
+
+        assert!(MapKey::from(&pair("ab", "c")) != MapKey::from(&pair("a", "bc")));
+        assert!(MapKey::from(&pair("a", "bc")) == MapKey::from(&pair("a", "bc")));
+    }
+}
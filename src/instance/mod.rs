@@ -4,13 +4,19 @@
 //! of "raw" data. Because Jinko is strongly typed, this isn't an issue. An integer will
 //! always be an integer.
 //! For example, a variable contains an ObjectInstance. Since a variable cannot be uninitialized,
-//! the instance is always there. The type of the ObjectInstance might be resolved later, after
-//! different passes of the typechecker.
+//! the instance is always there. The type of every expression is inferred by the typechecker
+//! before execution, so instances created at runtime always know their type.
 
 use std::collections::HashMap;
 
-use crate::instruction::TypeDec;
-use crate::{ErrKind, Error, Indent};
+use crate::instruction::{TypeDec, TypeId};
+use crate::{ErrKind, Error, Indent, Instruction, JkConstant};
+
+mod iter;
+mod map;
+
+pub use iter::{iterated_entry_types, iterated_type, EntryIter, InstanceIter};
+pub use map::MapEntries;
 
 pub type Name = String;
 type Offset = usize;
@@ -30,19 +36,27 @@ impl FieldInstance {
 
 type FieldsMap = HashMap<Name, FieldInstance>;
 
-/// The type is optional. At first, the type might not be known, and will only be
-/// revealed during the typechecking phase. `size` is the size of the instance in bytes.
+/// The type is optional, as empty instances used as placeholders do not have one. Instances
+/// produced by executing an expression are always typed. `size` is the size of the instance
+/// in bytes.
 /// It's the same as `data.len()`. `data` is the raw byte value of the instance.
+/// Instances of enums record the name of their variant, and contain its fields.
+/// Arrays and maps keep their elements and entries outside of their data, so that they
+/// can be accessed and modified without copying the other elements.
 #[derive(Debug, PartialEq, Clone)]
 pub struct ObjectInstance {
     ty: Option<TypeDec>,
     size: usize,
     data: Vec<u8>,
     fields: Option<FieldsMap>,
+    variant: Option<Name>,
+    elements: Option<Vec<ObjectInstance>>,
+    entries: Option<MapEntries>,
 }
 
 impl ObjectInstance {
-    /// Create a new, empty instance without a type or a size
+    /// Create a new, empty instance without a type or a size. This is only used as a
+    /// placeholder, and is never the result of an expression
     pub fn empty() -> ObjectInstance {
         ObjectInstance::new(None, 0, vec![], None)
     }
@@ -61,9 +75,31 @@ impl ObjectInstance {
             size,
             data,
             fields,
+            variant: None,
+            elements: None,
+            entries: None,
         }
     }
 
+    /// Create an instance of the variant named `variant` of an enum, containing the
+    /// fields of the variant
+    pub fn enum_variant(
+        ty: TypeDec,
+        variant: &str,
+        fields: Vec<(Name, ObjectInstance)>,
+    ) -> ObjectInstance {
+        let size = fields.iter().map(|(_, field)| field.size()).sum();
+        let data = fields
+            .iter()
+            .flat_map(|(_, field)| field.data().iter().copied())
+            .collect();
+
+        let mut instance = ObjectInstance::new(Some(ty), size, data, Some(fields));
+        instance.variant = Some(variant.to_string());
+
+        instance
+    }
+
     /// Create a new instance from raw bytes instead of a vector
     pub fn from_bytes(
         ty: Option<TypeDec>,
@@ -106,10 +142,120 @@ impl ObjectInstance {
         }
     }
 
+    /// Create an array containing the given elements, which are all of type `element_ty`
+    pub fn array(element_ty: &TypeId, elements: Vec<ObjectInstance>) -> ObjectInstance {
+        let mut array = ObjectInstance::new(
+            Some(TypeDec::from(TypeId::array_of(element_ty).to_string())),
+            0,
+            vec![],
+            None,
+        );
+        array.elements = Some(elements);
+
+        array
+    }
+
+    /// Check if the instance is an array
+    pub fn is_array(&self) -> bool {
+        self.element_type().is_some()
+    }
+
+    /// Get the type of the elements of an array, or `None` if the instance is not an
+    /// array
+    pub fn element_type(&self) -> Option<TypeId> {
+        self.ty()
+            .and_then(|ty| TypeId::from(ty.name()).element_type())
+    }
+
+    /// Get the elements of an array, in order. Instances which are not arrays do not
+    /// have any elements
+    pub fn elements(&self) -> &[ObjectInstance] {
+        self.elements.as_deref().unwrap_or(&[])
+    }
+
+    /// Get a mutable reference to the elements of an array, in order to modify them in
+    /// place. Returns `None` if the instance is not an array
+    pub fn elements_mut(&mut self) -> Option<&mut Vec<ObjectInstance>> {
+        self.elements.as_mut()
+    }
+
+    /// Create a map associating each key to its value, whose keys are of type `key_ty`
+    /// and values of type `value_ty`. Entries are kept in insertion order, and a key
+    /// given more than once keeps its first position and its last value
+    pub fn map(
+        key_ty: &TypeId,
+        value_ty: &TypeId,
+        entries: Vec<(ObjectInstance, ObjectInstance)>,
+    ) -> ObjectInstance {
+        let mut map = ObjectInstance::new(
+            Some(TypeDec::from(TypeId::map_of(key_ty, value_ty).to_string())),
+            0,
+            vec![],
+            None,
+        );
+        map.entries = Some(entries.into_iter().collect());
+
+        map
+    }
+
+    /// Check if the instance is a map
+    pub fn is_map(&self) -> bool {
+        self.ty().is_some_and(|ty| TypeId::from(ty.name()).is_map())
+    }
+
+    /// Get the types of the keys and values of a map, or `None` if the instance is not
+    /// a map
+    pub fn map_types(&self) -> Option<(TypeId, TypeId)> {
+        let ty = TypeId::from(self.ty()?.name());
+        let (key, value) = ty.map_types()?;
+
+        Some((key.clone(), value.clone()))
+    }
+
+    /// Get the entries of a map, or `None` if the instance is not a map
+    pub fn map_entries(&self) -> Option<&MapEntries> {
+        self.entries.as_ref()
+    }
+
+    /// Get a mutable reference to the entries of a map, in order to modify them in
+    /// place
+    pub fn map_entries_mut(&mut self) -> Option<&mut MapEntries> {
+        self.entries.as_mut()
+    }
+
+    /// Create an instance referring to the function named `name`, whose type is the
+    /// function type `ty`
+    pub fn function(name: &str, ty: &TypeId) -> ObjectInstance {
+        ObjectInstance::from_bytes(
+            Some(TypeDec::from(ty.to_string())),
+            name.len(),
+            name.as_bytes(),
+            None,
+        )
+    }
+
+    /// Get the name of the function an instance refers to, if the instance is a
+    /// function
+    pub fn function_name(&self) -> Option<String> {
+        let is_function = self
+            .ty()
+            .is_some_and(|ty| TypeId::from(ty.name()).signature().is_some());
+
+        match is_function {
+            true => String::from_utf8(self.data.clone()).ok(),
+            false => None,
+        }
+    }
+
     pub fn fields(&self) -> &Option<FieldsMap> {
         &self.fields
     }
 
+    /// Get the name of the variant of an enum instance
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+
     fn fields_vec_to_hash_map(vec: Vec<(Name, ObjectInstance)>) -> FieldsMap {
         let mut current_offset: usize = 0;
         let mut hashmap = FieldsMap::new();
@@ -130,6 +276,10 @@ impl ObjectInstance {
             None => base = format!("{}{}type: `no type`\n", base, indent),
         }
 
+        if let Some(variant) = &instance.variant {
+            base = format!("{}{}variant: {}\n", base, indent, variant);
+        }
+
         base = format!("{}{}size: {}\n", base, indent, instance.size);
 
         if let Some(fields) = &instance.fields {
@@ -154,6 +304,48 @@ impl ObjectInstance {
     }
 }
 
+/// Primitive instances are displayed as their value, arrays as the list of their
+/// elements and other instances as their fields.
+/// Strings and characters are displayed as is, without quotes or escape sequences
+impl std::fmt::Display for ObjectInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self.ty() {
+                Some(ty) => match ty.name() {
+                    "int" => JkConstant::<i64>::from_instance(self).print(),
+                    "float" => JkConstant::<f64>::from_instance(self).print(),
+                    "char" => JkConstant::<char>::from_instance(self).0.to_string(),
+                    "string" => JkConstant::<String>::from_instance(self).0,
+                    "bool" => JkConstant::<bool>::from_instance(self).print(),
+                    _ if self.is_array() => format!(
+                        "[{}]",
+                        self.elements()
+                            .iter()
+                            .map(ObjectInstance::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    _ if self.is_map() => match self.map_entries() {
+                        Some(entries) if !entries.is_empty() => format!(
+                            "[{}]",
+                            entries
+                                .iter()
+                                .map(|(key, value)| format!("{}: {}", key, value))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        _ => String::from("[:]"),
+                    },
+                    _ => self.as_string(),
+                },
+                None => String::new(),
+            }
+        )
+    }
+}
+
 /// Convert a Jinko type to an instance. This is handled by jinko's primitive types
 /// as well as user defined ones
 pub trait ToObjectInstance {
@@ -166,6 +358,14 @@ pub trait FromObjectInstance {
     fn from_instance(i: &ObjectInstance) -> Self;
 }
 
+/// Rust types which correspond to a jinko type. Deriving this trait on a struct also
+/// implements `ToObjectInstance` and `FromObjectInstance`, so that instances of the
+/// struct can be given to jinko code and read back
+pub trait JinkoType {
+    /// Declaration of the jinko type
+    fn type_dec() -> TypeDec;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,4 +427,44 @@ mod tests {
         assert_eq!(v_f_x, JkInt::from(1).to_instance());
         assert_eq!(v_f_y, JkInt::from(2).to_instance());
     }
+
+    #[derive(Debug, PartialEq, crate::JinkoType)]
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    #[derive(Debug, PartialEq, crate::JinkoType)]
+    struct Vec2 {
+        f: Point,
+        s: Point,
+    }
+
+    #[test]
+    fn t_derived_type_dec() {
+        let type_dec = Vec2::type_dec();
+
+        assert_eq!(type_dec.name(), "Vec2");
+        assert_eq!(type_dec.fields()[0].get_type(), &TypeId::from("Point"));
+        assert_eq!(
+            Point::type_dec().fields()[1].get_type(),
+            &TypeId::from("int")
+        );
+    }
+
+    #[test]
+    fn t_derived_instance_matches_jinko_instance() {
+        let mut ctx = setup();
+
+        let inst = Construct::instruction("v").unwrap().1;
+        let v = inst.execute(&mut ctx).unwrap();
+
+        let rust_v = Vec2 {
+            f: Point { x: 1, y: 2 },
+            s: Point { x: 1, y: 2 },
+        };
+
+        assert_eq!(rust_v.to_instance(), v);
+        assert_eq!(Vec2::from_instance(&v), rust_v);
+    }
 }
@@ -0,0 +1,358 @@
+//! Arrays are growable lists of values which all have the same type. They are created
+//! from literals, and the type of an array of integers is written `[int]`.
+//!
+//! ```text
+//! mut values = [1, 2, 3];
+//! values.push(4);
+//! values[0] = values[3]; // [4, 2, 3, 4]
+//! ```
+//!
+//! Arrays have a few built-in methods: `len()` gives the number of elements, `push(x)`
+//! adds an element at the end, `pop()` removes the last element and returns it in an
+//! `Option`, and `iter()` gives a copy of the array which can be iterated over in a
+//! `for` loop. `push` and `pop` modify the array in place, and can only be called on
+//! mutable variables.
+
+use super::method_call::{
+    caller_instance, caller_instance_mut, check_variable_caller, make_option, option_of,
+};
+use crate::error::ErrSpaceLocation;
+use crate::instance::ToObjectInstance;
+use crate::instruction::{TypeDec, TypeId};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::value::JkInt;
+use crate::{Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
+
+#[derive(Clone)]
+pub struct Array {
+    elements: Vec<Box<dyn Instruction>>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl Array {
+    /// Create a new array literal from its elements
+    pub fn new(elements: Vec<Box<dyn Instruction>>) -> Array {
+        Array {
+            elements,
+            cached_type: None,
+            location: None,
+        }
+    }
+}
+
+impl Instruction for Array {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Expression(None)
+    }
+
+    fn print(&self) -> String {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.print()).collect();
+
+        format!("[{}]", elements.join(", "))
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("ARRAY", &self.print());
+
+        let mut elements = vec![];
+        for element in &self.elements {
+            elements.push(element.execute_expression(ctx)?);
+        }
+
+        // The elements carry their concrete type, even in generic functions. The type
+        // of empty arrays is only known once an element is pushed to them
+        let element_ty = match (
+            elements.first().and_then(ObjectInstance::ty),
+            &self.cached_type,
+        ) {
+            (Some(ty), _) => TypeId::from(ty.name()),
+            (None, Some(CheckedType::Resolved(ty))) => {
+                ty.element_type().unwrap_or_else(|| TypeId::from(""))
+            }
+            (None, _) => TypeId::from(""),
+        };
+
+        Some(ObjectInstance::array(&element_ty, elements))
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Array {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let mut element_ty: Option<TypeId> = None;
+
+        for element in self.elements.iter_mut() {
+            match element.type_of(ctx) {
+                CheckedType::Resolved(ty) => match &element_ty {
+                    Some(expected) if expected != &ty => ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "wrong type for element `{}` of array: expected `{}`, got `{}`",
+                                element.print(),
+                                expected,
+                                ty
+                            ))
+                            .with_loc(self.location.clone()),
+                    ),
+                    Some(_) => {}
+                    None => element_ty = Some(ty),
+                },
+                CheckedType::Void => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to use statement `{}` as an array element",
+                            element.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                ),
+                CheckedType::Unknown => {}
+            }
+        }
+
+        // The type of an empty array cannot be known from its literal
+        element_ty.map_or(CheckedType::Unknown, |ty| {
+            CheckedType::Resolved(TypeId::array_of(&ty))
+        })
+    }
+
+    cached_type!();
+}
+
+/// Methods built into every array
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArrayMethod {
+    Len,
+    Push,
+    Pop,
+    Iter,
+}
+
+impl ArrayMethod {
+    /// Get the array method with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<ArrayMethod> {
+        match name {
+            "len" => Some(ArrayMethod::Len),
+            "push" => Some(ArrayMethod::Push),
+            "pop" => Some(ArrayMethod::Pop),
+            "iter" => Some(ArrayMethod::Iter),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ArrayMethod::Len => "len",
+            ArrayMethod::Push => "push",
+            ArrayMethod::Pop => "pop",
+            ArrayMethod::Iter => "iter",
+        }
+    }
+
+    /// Does the method modify the array it is called on
+    fn is_mutating(self) -> bool {
+        matches!(self, ArrayMethod::Push | ArrayMethod::Pop)
+    }
+
+    /// Typecheck a call to the method on `array`, an array of `element_ty`
+    pub fn resolve_type(
+        self,
+        array: &dyn Instruction,
+        element_ty: &TypeId,
+        args: &mut [Box<dyn Instruction>],
+        ctx: &mut TypeCtx,
+        location: Option<ErrSpaceLocation>,
+    ) -> CheckedType {
+        let arity = match self {
+            ArrayMethod::Push => 1,
+            _ => 0,
+        };
+
+        if args.len() != arity {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "wrong number of arguments for array method `{}`: expected {}, got {}",
+                        self.name(),
+                        arity,
+                        args.len()
+                    ))
+                    .with_loc(location.clone()),
+            );
+        }
+
+        if self.is_mutating() {
+            check_variable_caller(array, self.name(), ctx, location.clone());
+        }
+
+        for arg in args.iter_mut() {
+            match arg.type_of(ctx) {
+                CheckedType::Resolved(ty) if &ty != element_ty => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "cannot push `{}` of type `{}` to an array of `{}`",
+                            arg.print(),
+                            ty,
+                            element_ty
+                        ))
+                        .with_loc(location.clone()),
+                ),
+                CheckedType::Void => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to push statement `{}` to an array",
+                            arg.print()
+                        ))
+                        .with_loc(location.clone()),
+                ),
+                _ => {}
+            }
+        }
+
+        match self {
+            ArrayMethod::Len => CheckedType::Resolved(TypeId::from("int")),
+            ArrayMethod::Push => CheckedType::Void,
+            ArrayMethod::Pop => CheckedType::Resolved(option_of(element_ty)),
+            ArrayMethod::Iter => CheckedType::Resolved(TypeId::array_of(element_ty)),
+        }
+    }
+
+    /// Call the method on `array`. Methods modifying the array modify the instance of
+    /// the variable it is stored in, without copying its elements
+    pub fn execute(
+        self,
+        array: &dyn Instruction,
+        args: &[Box<dyn Instruction>],
+        ctx: &mut Context,
+        location: Option<ErrSpaceLocation>,
+    ) -> Option<ObjectInstance> {
+        let mut values = vec![];
+        for arg in args {
+            values.push(arg.execute_expression(ctx)?);
+        }
+
+        match self {
+            ArrayMethod::Len => {
+                let len = caller_instance(array, ctx)?.elements().len();
+
+                Some(JkInt::from(len as i64).to_instance())
+            }
+            ArrayMethod::Iter => Some(caller_instance(array, ctx)?.into_owned()),
+            ArrayMethod::Push => {
+                let instance = caller_instance_mut(array, ctx, location)?;
+
+                // Empty arrays get the type of the first element pushed to them
+                let untyped = instance.element_type().is_some_and(|ty| ty.id().is_empty());
+                if let (true, Some(ty)) = (untyped, values.first().and_then(ObjectInstance::ty)) {
+                    let array_ty = TypeId::array_of(&TypeId::from(ty.name()));
+                    instance.set_ty(Some(TypeDec::from(array_ty.to_string())));
+                }
+
+                if let Some(elements) = instance.elements_mut() {
+                    elements.extend(values);
+                }
+
+                None
+            }
+            ArrayMethod::Pop => {
+                let instance = caller_instance_mut(array, ctx, location.clone())?;
+                let last = instance.elements_mut().and_then(Vec::pop);
+
+                make_option(last, ctx, location)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jinko_ex;
+    use crate::parser::{Construct, Parser};
+    use crate::value::JkBool;
+
+    #[test]
+    fn t_array_pretty_print() {
+        let array = Construct::instruction("[1, 2, 3]").unwrap().1;
+
+        assert_eq!(array.print(), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn t_array_elements() {
+        let res = jinko_ex! {
+            values = [1, 2, 3];
+            values
+        };
+
+        let expected = ObjectInstance::array(
+            &TypeId::from("int"),
+            vec![
+                JkInt::from(1).to_instance(),
+                JkInt::from(2).to_instance(),
+                JkInt::from(3).to_instance(),
+            ],
+        );
+
+        assert_eq!(res, Some(expected));
+    }
+
+    #[test]
+    fn t_array_push_and_len() {
+        let res = jinko_ex! {
+            mut values = [1, 2];
+            values.push(3);
+            values.len()
+        };
+
+        assert_eq!(res, Some(JkInt::from(3).to_instance()));
+    }
+
+    #[test]
+    fn t_array_push_to_empty() {
+        let res = jinko_ex! {
+            mut values = [];
+            values.push(15);
+            values[0]
+        };
+
+        assert_eq!(res, Some(JkInt::from(15).to_instance()));
+    }
+
+    #[test]
+    fn t_array_pop() {
+        let res = jinko_ex! {
+            mut values = [1, 2];
+            last = unwrap(values.pop());
+            first = unwrap(values.pop());
+            last * 10 + first + values.len()
+        };
+
+        assert_eq!(res, Some(JkInt::from(21).to_instance()));
+    }
+
+    #[test]
+    fn t_array_pop_empty() {
+        let res = jinko_ex! {
+            mut values = [1];
+            values.pop();
+            is_none(values.pop())
+        };
+
+        assert_eq!(res, Some(JkBool::from(true).to_instance()));
+    }
+
+    #[test]
+    fn t_array_push_immutable() {
+        let mut ctx = Parser::parse("values = [1]; values.push(2);", None);
+
+        assert!(ctx.execute().is_err());
+    }
+}
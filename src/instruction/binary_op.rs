@@ -2,23 +2,32 @@
 //! 1 + 2, a BinaryOp will be created containing "1" as a left hand side operand, "2" as
 //! a right hand side operand and "+" as the operator.
 //!
-//! The available operators are `+`, `-`, `*` and `/`.
-//! That is `Add`, `Substract`, `Multiply` and `Divide`.
-
+//! The available arithmetic operators are `+`, `-`, `*`, `/` and `%`.
+//! That is `Add`, `Substract`, `Multiply`, `Divide` and `Modulo`. Integer operations
+//! are checked: Overflowing or dividing by zero is a runtime error.
+//! The available comparison operators are `==`, `!=`, `<`, `<=`, `>` and `>=`. They
+//! return a boolean.
+//! The available logical operators are `&&` and `||`. They take two booleans and
+//! short-circuit: the right hand side is only executed if the left hand side does not
+//! already decide the result.
+
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
 use crate::{
-    instruction::Operator, Context, ErrKind, Error, FromObjectInstance, InstrKind, Instruction,
-    JkFloat, JkInt, ObjectInstance, Value,
+    instruction::{Operator, TypeDec, TypeId},
+    Context, ControlFlow, ErrKind, Error, FromObjectInstance, InstrKind, Instruction, JkBool,
+    JkChar, JkFloat, JkInt, JkString, ObjectInstance, ToObjectInstance, Value,
 };
 
-/// The `BinaryOp` struct contains two expressions and an operator, which can be an arithmetic
-/// or a comparison one
+/// The `BinaryOp` struct contains two expressions and an operator, which can be an arithmetic,
+/// a comparison or a logical one
 #[derive(Clone)]
 pub struct BinaryOp {
     lhs: Box<dyn Instruction>,
     rhs: Box<dyn Instruction>,
     op: Operator,
-
-    value: Option<Box<dyn Instruction>>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl BinaryOp {
@@ -28,7 +37,8 @@ impl BinaryOp {
             lhs,
             rhs,
             op,
-            value: None,
+            cached_type: None,
+            location: None,
         }
     }
 
@@ -40,30 +50,61 @@ impl BinaryOp {
 
     // Get a reference on the left side member of a BinaryOp
     #[cfg(test)]
-    pub fn lhs(&self) -> &Box<dyn Instruction> {
-        &self.lhs
+    pub fn lhs(&self) -> &dyn Instruction {
+        &*self.lhs
     }
 
     /// Get a reference on the right side member of a BinaryOp
     #[cfg(test)]
-    pub fn rhs(&self) -> &Box<dyn Instruction> {
-        &self.rhs
+    pub fn rhs(&self) -> &dyn Instruction {
+        &*self.rhs
+    }
+
+    /// Primitive types on which the operator is available
+    fn available_types(&self) -> &'static [&'static str] {
+        match self.op {
+            Operator::Equals | Operator::NotEquals => &["int", "float", "char", "string", "bool"],
+            op if op.is_logical() => &["bool"],
+            op if op.is_comparison() => &["int", "float", "char", "string"],
+            Operator::Add => &["int", "float", "string"],
+            _ => &["int", "float"],
+        }
     }
 
     // FIXME: Use Context::execute_expression
     /// Execute a node of the binary operation
     fn execute_node(&self, node: &dyn Instruction, ctx: &mut Context) -> Option<ObjectInstance> {
         match node.execute(ctx) {
+            None if ctx.control_flow().is_some() => None,
             None => {
-                ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                    "invalid use of statement in binary operation: {}",
-                    node.print()
-                )));
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "invalid use of statement in binary operation: {}",
+                            node.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
                 None
             }
             Some(v) => Some(v),
         }
     }
+
+    /// Execute a logical operation, only executing the right hand side if needed
+    fn execute_logical(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        let lhs = self.lhs.as_bool(ctx)?;
+
+        let value = match (self.op, lhs) {
+            (Operator::And, false) => false,
+            (Operator::Or, true) => true,
+            _ => self.rhs.as_bool(ctx)?,
+        };
+
+        ctx.debug_step("BINOP EXIT");
+
+        Some(JkBool::from(value).to_instance())
+    }
 }
 
 impl Instruction for BinaryOp {
@@ -85,66 +126,155 @@ impl Instruction for BinaryOp {
 
         ctx.debug("OP", self.op.as_str());
 
+        if self.op.is_logical() {
+            return self.execute_logical(ctx);
+        }
+
         let l_value = self.execute_node(&*self.lhs, ctx)?;
         let r_value = self.execute_node(&*self.rhs, ctx)?;
 
-        if l_value.ty() != r_value.ty() {
-            ctx.error(Error::new(ErrKind::TypeChecker).with_msg(
-                // FIXME: If we unwrap and panic here, this is another typechecking
-                // error. Implement this once typechecking is implemented
-                format!(
-                    "Trying to do binary operation on invalid types: `{}` {} `{}`",
-                    l_value.ty().unwrap(),
-                    self.op.as_str(),
-                    r_value.ty().unwrap()
-                ),
-            ));
-            return None;
-        }
-
-        let return_value;
-
-        // FIXME: DISGUSTING and do not unwap
-        match l_value.ty().unwrap().name() {
-            // FIXME: Absolutely DISGUSTING
-            "int" => {
-                let res =
-                    JkInt::from_instance(&l_value).do_op(&JkInt::from_instance(&r_value), self.op);
-                return_value = match res {
-                    Ok(r) => r,
-                    Err(e) => {
-                        ctx.error(e);
-                        return None;
-                    }
-                };
+        // Instances always carry their type, and the typechecker made sure that both
+        // operands are of the same type. We still check it in case the operation was
+        // not typechecked, for example when executing instructions one by one
+        let ty = match (l_value.ty(), r_value.ty()) {
+            (Some(l_ty), Some(r_ty)) if l_ty == r_ty => l_ty.name(),
+            (l_ty, r_ty) => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "Trying to do binary operation on invalid types: `{}` {} `{}`",
+                            l_ty.map_or("", TypeDec::name),
+                            self.op.as_str(),
+                            r_ty.map_or("", TypeDec::name)
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
             }
+        };
 
+        let res = match ty {
+            "int" => JkInt::from_instance(&l_value).do_op(&JkInt::from_instance(&r_value), self.op),
             "float" => {
-                let res = JkFloat::from_instance(&l_value)
-                    .do_op(&JkFloat::from_instance(&r_value), self.op);
-                return_value = match res {
-                    Ok(r) => r,
-                    Err(e) => {
-                        ctx.error(e);
-                        return None;
-                    }
-                }
+                JkFloat::from_instance(&l_value).do_op(&JkFloat::from_instance(&r_value), self.op)
             }
-            _ => todo!("Implement empty types?"),
-        }
+            "char" => {
+                JkChar::from_instance(&l_value).do_op(&JkChar::from_instance(&r_value), self.op)
+            }
+            "string" => {
+                JkString::from_instance(&l_value).do_op(&JkString::from_instance(&r_value), self.op)
+            }
+            "bool" => {
+                JkBool::from_instance(&l_value).do_op(&JkBool::from_instance(&r_value), self.op)
+            }
+            _ => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "binary operation `{}` is not available for type `{}`",
+                            self.op.as_str(),
+                            ty
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
+            }
+        };
+
+        let return_value = match res {
+            Ok(r) => r,
+            Err(e) => {
+                // Divisions by zero and overflows stop the program, as nothing sensible
+                // can be computed from their result
+                ctx.error(e.with_loc(self.location.clone()));
+                ctx.set_control_flow(ControlFlow::Abort);
+                return None;
+            }
+        };
 
         ctx.debug_step("BINOP EXIT");
 
         Some(return_value)
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for BinaryOp {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let l_type = self.lhs.type_of(ctx);
+        let r_type = self.rhs.type_of(ctx);
+
+        for (node, ty) in [(&self.lhs, &l_type), (&self.rhs, &r_type)] {
+            match ty {
+                CheckedType::Void => {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "invalid use of statement in binary operation: {}",
+                                node.print()
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                    return CheckedType::Unknown;
+                }
+                CheckedType::Resolved(ty) if !self.available_types().contains(&ty.id()) => {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "binary operation `{}` is not available for type `{}`",
+                                self.op.as_str(),
+                                ty
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                    return CheckedType::Unknown;
+                }
+                _ => {}
+            }
+        }
+
+        let operand_ty = match (l_type, r_type) {
+            (CheckedType::Resolved(l_ty), CheckedType::Resolved(r_ty)) if l_ty != r_ty => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "Trying to do binary operation on invalid types: `{}` {} `{}`",
+                            l_ty,
+                            self.op.as_str(),
+                            r_ty
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return CheckedType::Unknown;
+            }
+            (CheckedType::Resolved(ty), _) | (_, CheckedType::Resolved(ty)) => {
+                CheckedType::Resolved(ty)
+            }
+            _ => CheckedType::Unknown,
+        };
+
+        match self.op.is_comparison() || self.op.is_logical() {
+            true => CheckedType::Resolved(TypeId::from("bool")),
+            false => operand_ty,
+        }
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::jinko;
     use crate::value::JkInt;
     use crate::Context;
-    use crate::ToObjectInstance;
 
     fn binop_assert(l_num: i64, r_num: i64, op_string: &str, res: i64) {
         let l = Box::new(JkInt::from(l_num));
@@ -192,6 +322,36 @@ mod tests {
         binop_assert(2, 99, "*", 198);
     }
 
+    #[test]
+    fn t_binop_logical_short_circuit() {
+        // The right hand side would error out if it was executed
+        let ctx = jinko! {
+            func boom() -> bool { undefined_variable }
+            a = false && boom();
+            b = true || boom();
+        };
+
+        assert!(!ctx.error_handler.has_errors());
+        assert_eq!(
+            ctx.get_variable("a").unwrap().instance(),
+            JkBool::from(false).to_instance()
+        );
+        assert_eq!(
+            ctx.get_variable("b").unwrap().instance(),
+            JkBool::from(true).to_instance()
+        );
+    }
+
+    #[test]
+    fn t_binop_logical_evaluates_rhs() {
+        let ctx = jinko! {
+            func boom() -> bool { undefined_variable }
+            a = true && boom();
+        };
+
+        assert!(ctx.error_handler.has_errors());
+    }
+
     #[test]
     fn t_binop_rhs_execute() {
         let r_bin = BinaryOp::new(
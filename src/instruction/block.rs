@@ -17,12 +17,16 @@
 //! The return value of the function is the last instruction if it is an expression.
 //! Otherwise, it's `void`
 
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
 use crate::{Context, InstrKind, Instruction, ObjectInstance};
 
 #[derive(Clone)]
 pub struct Block {
     instructions: Vec<Box<dyn Instruction>>,
     last: Option<Box<dyn Instruction>>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl Default for Block {
@@ -37,6 +41,8 @@ impl Block {
         Block {
             instructions: Vec::new(),
             last: None,
+            cached_type: None,
+            location: None,
         }
     }
 
@@ -71,6 +77,40 @@ impl Block {
     }
 }
 
+impl Block {
+    /// Execute an instruction of the block. Errors caused by the instruction which do
+    /// not have a more precise location are reported at its location
+    fn execute_instruction(inst: &dyn Instruction, ctx: &mut Context) -> Option<ObjectInstance> {
+        let previous = inst
+            .location()
+            .map(|loc| ctx.set_location(Some(loc.clone())));
+
+        let res = inst.execute(ctx);
+
+        if let Some(previous) = previous {
+            ctx.set_location(previous);
+        }
+
+        res
+    }
+
+    /// Typecheck an instruction of the block, reporting errors which do not have a
+    /// more precise location at its location
+    fn type_instruction(inst: &mut dyn Instruction, ctx: &mut TypeCtx) -> CheckedType {
+        let previous = inst
+            .location()
+            .map(|loc| ctx.set_location(Some(loc.clone())));
+
+        let ty = inst.type_of(ctx);
+
+        if let Some(previous) = previous {
+            ctx.set_location(previous);
+        }
+
+        ty
+    }
+}
+
 impl Instruction for Block {
     fn kind(&self) -> InstrKind {
         match self.last() {
@@ -103,13 +143,19 @@ impl Instruction for Block {
         ctx.scope_enter();
         ctx.debug_step("BLOCK ENTER");
 
-        self.instructions().iter().for_each(|inst| {
-            inst.execute(ctx);
-        });
+        for inst in self.instructions() {
+            Block::execute_instruction(&**inst, ctx);
+
+            // Stop executing the block if a `return`, `break` or `continue` was
+            // encountered
+            if ctx.control_flow().is_some() {
+                break;
+            }
+        }
 
-        let ret_val = match &self.last {
-            Some(e) => e.execute(ctx),
-            None => None,
+        let ret_val = match (&self.last, ctx.control_flow()) {
+            (Some(e), None) => Block::execute_instruction(&**e, ctx),
+            _ => None,
         };
 
         ctx.scope_exit();
@@ -117,6 +163,38 @@ impl Instruction for Block {
 
         ret_val
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Block {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        ctx.scope_enter();
+
+        // Functions and types can be used before being declared
+        ctx.hoist_declarations(self.instructions());
+
+        self.instructions.iter_mut().for_each(|inst| {
+            Block::type_instruction(&mut **inst, ctx);
+        });
+
+        let last_ty = match &mut self.last {
+            Some(last) => Block::type_instruction(&mut **last, ctx),
+            None => CheckedType::Void,
+        };
+
+        ctx.scope_exit();
+
+        last_ty
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
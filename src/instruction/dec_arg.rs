@@ -1,17 +1,32 @@
 use std::fmt::{Display, Formatter, Result};
 
+use crate::error::ErrSpaceLocation;
 use crate::instruction::TypeId;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct DecArg {
     name: String,
     ty: TypeId,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl DecArg {
     /// Create a new function declaration argument with a name and a type
     pub fn new(name: String, ty: TypeId) -> DecArg {
-        DecArg { name, ty }
+        DecArg {
+            name,
+            ty,
+            location: None,
+        }
+    }
+
+    /// Location of the argument in the source code, if it was parsed from it
+    pub fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    pub fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
     }
 
     /// Return a reference to the argument's name
@@ -25,8 +40,16 @@ impl DecArg {
     }
 }
 
+/// Arguments are the same if they have the same name and type, wherever they were
+/// declared
+impl PartialEq for DecArg {
+    fn eq(&self, other: &DecArg) -> bool {
+        self.name == other.name && self.ty == other.ty
+    }
+}
+
 impl Display for DecArg {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{}: {}", self.name, self.ty.id())
+        write!(f, "{}: {}", self.name, self.ty)
     }
 }
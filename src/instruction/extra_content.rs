@@ -3,6 +3,7 @@
 //! of such an instruction produces no results. But they are useful when it comes to
 //! pretty printing and code formatting.
 
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
 use crate::{Context, InstrKind, Instruction, ObjectInstance};
 
 #[derive(Clone)]
@@ -73,3 +74,9 @@ impl Instruction for ExtraContent {
         }
     }
 }
+
+impl TypeCheck for ExtraContent {
+    fn resolve_type(&mut self, _ctx: &mut TypeCtx) -> CheckedType {
+        CheckedType::Void
+    }
+}
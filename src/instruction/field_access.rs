@@ -1,12 +1,17 @@
 //! FieldAccesses represent an access onto a type instance's members.
 //! FIXME: Add doc
 
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::generics::Bindings;
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
 use crate::{Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
 
 #[derive(Clone)]
 pub struct FieldAccess {
     instance: Box<dyn Instruction>,
     field_name: String,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl FieldAccess {
@@ -15,6 +20,8 @@ impl FieldAccess {
         FieldAccess {
             instance,
             field_name,
+            cached_type: None,
+            location: None,
         }
     }
 }
@@ -34,11 +41,16 @@ impl Instruction for FieldAccess {
         ctx.debug("FIELD ACCESS ENTER", &self.print());
 
         let calling_instance = match self.instance.execute(ctx) {
+            None if ctx.control_flow().is_some() => return None,
             None => {
-                ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                    "instance `{}` is a statement and cannot be accessed",
-                    self.instance.print()
-                )));
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "instance `{}` is a statement and cannot be accessed",
+                            self.instance.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
                 return None;
             }
             Some(i) => i,
@@ -55,6 +67,71 @@ impl Instruction for FieldAccess {
 
         Some(field_instance)
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for FieldAccess {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let instance_ty = match self.instance.type_of(ctx) {
+            CheckedType::Resolved(ty) => ty,
+            CheckedType::Void => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "instance `{}` is a statement and cannot be accessed",
+                            self.instance.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return CheckedType::Unknown;
+            }
+            CheckedType::Unknown => return CheckedType::Unknown,
+        };
+
+        // If the type does not exist, an error has already been emitted by the instance
+        let type_dec = match ctx.get_type(&instance_ty) {
+            Some(t) => t,
+            None => return CheckedType::Unknown,
+        };
+
+        // The fields of generic types are of the types the instance was created with
+        let bindings: Bindings = type_dec
+            .generics()
+            .iter()
+            .map(|param| param.name().to_string())
+            .zip(ctx.normalize(&instance_ty).generics().iter().cloned())
+            .collect();
+
+        match type_dec
+            .fields()
+            .iter()
+            .find(|field| field.name() == self.field_name)
+        {
+            Some(field) => {
+                CheckedType::Resolved(ctx.normalize(field.get_type()).substitute(&bindings))
+            }
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "type `{}` has no field named `{}`",
+                            instance_ty, self.field_name
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                CheckedType::Unknown
+            }
+        }
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
@@ -66,13 +143,11 @@ mod tests {
     use crate::JkInt;
 
     fn setup() -> Context {
-        let ctx = jinko! {
+        jinko! {
             type Point(x: int, y:int);
             func basic() -> Point { Point { x = 15, y = 14 }}
             b = basic();
-        };
-
-        ctx
+        }
     }
 
     #[test]
@@ -82,7 +157,7 @@ mod tests {
         let inst = Construct::instruction("b.x").unwrap().1;
         let res = match inst.execute(&mut ctx) {
             Some(i) => i,
-            None => return assert!(false, "Error when accessing valid field"),
+            None => unreachable!("Error when accessing valid field"),
         };
 
         let exp = JkInt::from(15).to_instance();
@@ -0,0 +1,161 @@
+//! Format strings are string literals containing interpolated expressions, such as
+//! `"{name} is {age} years old"`. The expressions are evaluated in the current scope,
+//! and their values are displayed in place of the braces surrounding them.
+
+use crate::error::ErrSpaceLocation;
+use crate::instruction::TypeId;
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::value::escape_in_string;
+use crate::{
+    Context, ErrKind, Error, InstrKind, Instruction, JkString, ObjectInstance, ToObjectInstance,
+};
+
+/// Part of a format string: Either raw text or an expression to evaluate
+#[derive(Clone)]
+pub enum FormatPart {
+    Text(String),
+    Expr(Box<dyn Instruction>),
+}
+
+#[derive(Clone)]
+pub struct FormatString {
+    parts: Vec<FormatPart>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl FormatString {
+    /// Create a new format string from its parts
+    pub fn new(parts: Vec<FormatPart>) -> FormatString {
+        FormatString {
+            parts,
+            location: None,
+        }
+    }
+
+    /// Return a reference to the parts of the format string
+    pub fn parts(&self) -> &Vec<FormatPart> {
+        &self.parts
+    }
+}
+
+impl Instruction for FormatString {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Expression(None)
+    }
+
+    fn print(&self) -> String {
+        let mut base = String::from("\"");
+
+        for part in &self.parts {
+            match part {
+                FormatPart::Text(text) => base.extend(text.chars().map(escape_in_string)),
+                FormatPart::Expr(expr) => base.push_str(&format!("{{{}}}", expr.print())),
+            }
+        }
+
+        base.push('"');
+
+        base
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("FORMAT", &self.print());
+
+        let mut string = String::new();
+
+        for part in &self.parts {
+            match part {
+                FormatPart::Text(text) => string.push_str(text),
+                FormatPart::Expr(expr) => {
+                    let instance = expr.execute_expression(ctx)?;
+                    string.push_str(&instance.to_string());
+                }
+            }
+        }
+
+        Some(JkString::from(string).to_instance())
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for FormatString {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        for part in self.parts.iter_mut() {
+            if let FormatPart::Expr(expr) = part {
+                if expr.type_of(ctx) == CheckedType::Void {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "trying to interpolate statement `{}` in a string",
+                                expr.print()
+                            ))
+                            .with_loc(expr.location().cloned()),
+                    );
+                }
+            }
+        }
+
+        CheckedType::Resolved(TypeId::from("string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Construct;
+    use crate::{jinko, FromObjectInstance};
+
+    fn format(ctx: &mut Context, input: &str) -> String {
+        let instruction = Construct::instruction(input).unwrap().1;
+
+        JkString::from_instance(&instruction.execute(ctx).unwrap()).0
+    }
+
+    #[test]
+    fn t_format_expressions() {
+        let mut ctx = jinko! {
+            name = "jinko";
+            version = 2;
+        };
+
+        assert_eq!(
+            format(&mut ctx, r#""{name} v{version + 1}: {'c'} {1.5} {true}\n""#),
+            "jinko v3: c 1.5 true\n"
+        );
+    }
+
+    #[test]
+    fn t_format_escaped_braces() {
+        let mut ctx = jinko! {
+            x = 1;
+        };
+
+        assert_eq!(format(&mut ctx, r#""{{x}} = { x }""#), "{x} = 1");
+    }
+
+    #[test]
+    fn t_format_print_round_trips() {
+        let input = r#""{{\"{1 + 2}\"}}\t{f('}', 2)}""#;
+        let printed = Construct::instruction(input).unwrap().1.print();
+        let reparsed = Construct::instruction(&printed).unwrap().1;
+
+        assert_eq!(reparsed.print(), printed);
+    }
+
+    #[test]
+    fn t_format_statement() {
+        let mut ctx = Context::new();
+        let mut instruction = Construct::instruction(r#""{x = 2}""#).unwrap().1;
+
+        instruction.type_of(&mut TypeCtx::new(&mut ctx));
+
+        assert!(ctx.has_errors());
+    }
+}
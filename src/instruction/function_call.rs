@@ -1,7 +1,13 @@
 //! FunctionCalls are used when calling a function. The argument list is given to the
-//! function on execution.
-
-use crate::instruction::{FunctionDec, Var};
+//! function on execution. Calling a variable containing a function, such as `f(x)` in
+//! `func apply(f: func(int) -> int, x: int) -> int { f(x) }`, calls the function it
+//! contains.
+
+use crate::builtins::Builtin;
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{FunctionDec, FunctionKind, TypeId, TypeParam, Var};
+use crate::typechecker::generics::{self, Bindings};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
 use crate::{Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
 use std::rc::Rc;
 
@@ -10,8 +16,13 @@ pub struct FunctionCall {
     /// Name of the function to call
     fn_name: String,
 
+    /// Types given explicitly to the type parameters of a generic function
+    generics: Vec<TypeId>,
+
     /// Arguments to give to the function
     args: Vec<Box<dyn Instruction>>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl FunctionCall {
@@ -19,7 +30,10 @@ impl FunctionCall {
     pub fn new(fn_name: String) -> FunctionCall {
         FunctionCall {
             fn_name,
+            generics: Vec::new(),
             args: Vec::new(),
+            cached_type: None,
+            location: None,
         }
     }
 
@@ -34,6 +48,17 @@ impl FunctionCall {
         self.args.insert(0, arg)
     }
 
+    /// Remove the first argument of the function call's argument list, undoing
+    /// `add_arg_front`
+    pub fn remove_arg_front(&mut self) -> Box<dyn Instruction> {
+        self.args.remove(0)
+    }
+
+    /// Set the types given explicitly to the type parameters of the function
+    pub fn set_generics(&mut self, generics: Vec<TypeId>) {
+        self.generics = generics
+    }
+
     /// Return a reference the called function's name
     pub fn name(&self) -> &str {
         &self.fn_name
@@ -44,33 +69,190 @@ impl FunctionCall {
         &self.args
     }
 
+    /// Return a mutable reference to the list of arguments
+    pub fn args_mut(&mut self) -> &mut Vec<Box<dyn Instruction>> {
+        &mut self.args
+    }
+
+    /// Name of the function to call: The function contained in the variable named like
+    /// the call if there is one, and the function named like the call otherwise
+    fn callee(&self, ctx: &Context) -> String {
+        ctx.get_variable(self.name())
+            .and_then(|var| var.instance().function_name())
+            .unwrap_or_else(|| self.name().to_string())
+    }
+
     /// Get the corresponding declaration from a context
-    fn get_declaration(&self, ctx: &mut Context) -> Result<Rc<FunctionDec>, Error> {
-        match ctx.get_function(self.name()) {
+    fn get_declaration(&self, name: &str, ctx: &mut Context) -> Result<Rc<FunctionDec>, Error> {
+        match ctx.get_function(name) {
             // get_function() return a Rc, so this clones the Rc, not the FunctionDec
             Some(f) => Ok(f.clone()),
-            // FIXME: Fix Location and input
             None => Err(Error::new(ErrKind::Context)
-                .with_msg(format!("cannot find function {}", self.name()))),
+                .with_msg(format!("cannot find function {}", name))
+                .with_loc(self.location.clone())),
         }
     }
 
     /// Check if the arguments received and the arguments expected match
-    fn check_args_count(&self, function: &FunctionDec) -> Result<(), Error> {
-        match self.args().len() == function.args().len() {
+    fn check_args_count(&self, expected: usize) -> Result<(), Error> {
+        match self.args().len() == expected {
             true => Ok(()),
-            false => Err(Error::new(ErrKind::Context).with_msg(format!(
-                "wrong number of arguments \
+            false => Err(Error::new(ErrKind::Context)
+                .with_msg(format!(
+                    "wrong number of arguments \
                     for call to function `{}`: expected {}, got {}",
-                self.name(),
-                function.args().len(),
-                self.args().len()
-            ))),
+                    self.name(),
+                    expected,
+                    self.args().len()
+                ))
+                .with_loc(self.location.clone())),
         }
     }
 
-    /// Map each argument to its corresponding instruction
-    fn map_args(&self, function: &FunctionDec, ctx: &mut Context) {
+    /// Call a function implemented in the interpreter. Errors reported by the builtin
+    /// are located at the call
+    fn call_builtin(&self, builtin: &Builtin, ctx: &mut Context) -> Option<ObjectInstance> {
+        if let Err(e) = self.check_args_count(builtin.args().len()) {
+            ctx.error(e);
+            return None;
+        }
+
+        ctx.debug("BUILTIN CALL", self.name());
+
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.execute_expression(ctx))
+            .collect::<Option<Vec<ObjectInstance>>>()?;
+
+        self.at_call_location(ctx, |ctx| builtin.call(ctx, args))
+    }
+
+    /// Run `f` with the call as the location of the statement being executed, so that
+    /// errors reported without a location are located at the call
+    fn at_call_location(
+        &self,
+        ctx: &mut Context,
+        f: impl FnOnce(&mut Context) -> Option<ObjectInstance>,
+    ) -> Option<ObjectInstance> {
+        let previous = self
+            .location
+            .as_ref()
+            .map(|loc| ctx.set_location(Some(loc.clone())));
+
+        let ret_val = f(ctx);
+
+        if let Some(previous) = previous {
+            ctx.set_location(previous);
+        }
+
+        ret_val
+    }
+
+    /// Typecheck a call to a function which is not declared in jinko, whose arguments
+    /// are of the given types. This is the case of functions implemented in the
+    /// interpreter and of the functions contained in variables. The types can refer to
+    /// the type parameters `params`, and a `None` return type means that the function
+    /// does not return anything
+    fn resolve_signature_type(
+        &mut self,
+        params: &[TypeParam],
+        args: &[TypeId],
+        ty: Option<&TypeId>,
+        ctx: &mut TypeCtx,
+    ) -> CheckedType {
+        if self.args().len() != args.len() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "wrong number of arguments \
+                    for call to function `{}`: expected {}, got {}",
+                        self.fn_name,
+                        args.len(),
+                        self.args().len()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+        }
+
+        let arg_types: Vec<CheckedType> =
+            self.args.iter_mut().map(|arg| arg.type_of(ctx)).collect();
+
+        let bindings = match self.bind_generics(params, args, &arg_types) {
+            Ok(bindings) => bindings,
+            // Type parameters cannot be inferred from arguments of unknown types
+            Err(_) if arg_types.contains(&CheckedType::Unknown) => return CheckedType::Unknown,
+            Err(e) => {
+                ctx.error(e.with_loc(self.location.clone()));
+                return CheckedType::Unknown;
+            }
+        };
+
+        for (idx, ((call_arg, arg_ty), expected)) in
+            self.args.iter().zip(arg_types).zip(args).enumerate()
+        {
+            let expected = expected.substitute(&bindings);
+
+            match arg_ty {
+                CheckedType::Resolved(arg_ty) if arg_ty != expected => {
+                    ctx.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                        "wrong type for argument {} in call to function `{}`: expected `{}`, got `{}`",
+                        idx + 1,
+                        self.fn_name,
+                        expected,
+                        arg_ty
+                    )).with_loc(self.location.clone()))
+                }
+                CheckedType::Void => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to use statement `{}` as argument to function `{}`",
+                            call_arg.print(),
+                            self.fn_name
+                        ))
+                        .with_loc(self.location.clone()),
+                ),
+                _ => {}
+            }
+        }
+
+        match ty {
+            Some(ty) => CheckedType::Resolved(ty.substitute(&bindings)),
+            None => CheckedType::Void,
+        }
+    }
+
+    /// Bind the type parameters `params` of the called function to the types given
+    /// explicitly, or to the types inferred from the arguments, whose declared types
+    /// are `declared`
+    fn bind_generics(
+        &self,
+        params: &[TypeParam],
+        declared: &[TypeId],
+        arg_types: &[CheckedType],
+    ) -> Result<Bindings, Error> {
+        if !self.generics.is_empty() {
+            return generics::bind(self.name(), params, &self.generics);
+        }
+
+        let mut bindings = Bindings::new();
+        for (declared, arg_ty) in declared.iter().zip(arg_types) {
+            if let CheckedType::Resolved(ty) = arg_ty {
+                generics::infer(params, declared, ty, &mut bindings);
+            }
+        }
+
+        generics::complete(self.name(), params, &mut bindings)?;
+
+        Ok(bindings)
+    }
+
+    /// Map each argument to its corresponding instruction, declaring the function's
+    /// arguments as variables in the current scope. Returns the instances given to the
+    /// arguments, or `None` if the arguments could not be mapped
+    fn map_args(&self, function: &FunctionDec, ctx: &mut Context) -> Option<Vec<ObjectInstance>> {
+        let mut instances = vec![];
+
         for (call_arg, func_arg) in self.args.iter().zip(function.args()) {
             ctx.debug(
                 "VAR MAP",
@@ -82,37 +264,59 @@ impl FunctionCall {
             let mut new_var = Var::new(func_arg.name().to_owned());
             let mut instance = match call_arg.execute_expression(ctx) {
                 Some(i) => i,
-                None => {
-                    ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                        "trying to map statement to function argument: {} -> {}",
-                        call_arg.print(),
-                        func_arg
-                    )));
-                    return;
-                }
-            };
-
-            let ty = match ctx.get_type(func_arg.get_type()) {
-                // Double dereferencing: Some(t) gives us a &Rc<TypeDec>. We dereference
-                // it to access the Rc, and dereference it again to access the TypeDec.
-                Some(t) => (**t).clone(),
+                None if ctx.control_flow().is_some() => return None,
                 None => {
                     ctx.error(
                         Error::new(ErrKind::Context)
-                            .with_msg(format!("type not found: {}", func_arg.get_type().id())),
+                            .with_msg(format!(
+                                "trying to map statement to function argument: {} -> {}",
+                                call_arg.print(),
+                                func_arg
+                            ))
+                            .with_loc(self.location.clone()),
                     );
-                    return;
+                    return None;
                 }
             };
 
-            instance.set_ty(Some(ty));
+            // Instances given in place of a type parameter keep their own type, as do
+            // functions and arrays
+            let is_type_param = function
+                .generics()
+                .iter()
+                .any(|param| param.name() == func_arg.get_type().id());
+            let is_function = func_arg.get_type().signature().is_some();
+            let is_array = func_arg.get_type().element_type().is_some();
+
+            if !is_type_param && !is_function && !is_array {
+                let ty = match ctx.get_type(func_arg.get_type()) {
+                    // Double dereferencing: Some(t) gives us a &Rc<TypeDec>. We dereference
+                    // it to access the Rc, and dereference it again to access the TypeDec.
+                    Some(t) => (**t).clone(),
+                    None => {
+                        ctx.error(
+                            Error::new(ErrKind::Context)
+                                .with_msg(format!("type not found: {}", func_arg.get_type()))
+                                .with_loc(self.location.clone()),
+                        );
+                        return None;
+                    }
+                };
+
+                instance.set_ty(Some(ty));
+            }
 
-            new_var.set_instance(instance);
+            new_var.set_instance(instance.clone());
 
             if let Err(e) = ctx.add_variable(new_var) {
                 ctx.error(e);
+                return None;
             }
+
+            instances.push(instance);
         }
+
+        Some(instances)
     }
 }
 
@@ -123,7 +327,14 @@ impl Instruction for FunctionCall {
     }
 
     fn print(&self) -> String {
-        let mut base = format!("{}(", self.fn_name);
+        let mut base = self.fn_name.clone();
+
+        if !self.generics.is_empty() {
+            let generics: Vec<String> = self.generics.iter().map(TypeId::to_string).collect();
+            base.push_str(&format!("<{}>", generics.join(", ")));
+        }
+
+        base.push('(');
 
         let mut first_arg = true;
         for arg in &self.args {
@@ -140,7 +351,16 @@ impl Instruction for FunctionCall {
     }
 
     fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
-        let function = match self.get_declaration(ctx) {
+        let name = self.callee(ctx);
+
+        // Functions declared in jinko shadow builtins
+        if ctx.get_function(&name).is_none() {
+            if let Some(builtin) = ctx.get_builtin(&name).cloned() {
+                return self.call_builtin(&builtin, ctx);
+            }
+        }
+
+        let function = match self.get_declaration(&name, ctx) {
             Ok(f) => f,
             Err(e) => {
                 ctx.error(e);
@@ -148,7 +368,7 @@ impl Instruction for FunctionCall {
             }
         };
 
-        if let Err(e) = self.check_args_count(&function) {
+        if let Err(e) = self.check_args_count(function.args().len()) {
             ctx.error(e);
             return None;
         }
@@ -157,14 +377,141 @@ impl Instruction for FunctionCall {
 
         ctx.debug("CALL", self.name());
 
-        self.map_args(&function, ctx);
+        // An argument such as `opt?` might have interrupted the call, in which case the
+        // function is not run
+        let args = match self.map_args(&function, ctx) {
+            Some(args) => args,
+            None => {
+                ctx.scope_exit();
+                return None;
+            }
+        };
 
-        let ret_val = function.run(ctx);
+        let mut ret_val = match function.fn_kind() {
+            FunctionKind::Ext => self.at_call_location(ctx, |ctx| function.run_ext(ctx, args)),
+            _ => function.run(ctx),
+        };
 
         ctx.scope_exit();
 
+        // Much like arguments, the returned instance is of the function's return type
+        if let (Some(instance), Some(ty)) = (&mut ret_val, function.ty()) {
+            if let Some(ty_dec) = ctx.get_type(ty) {
+                instance.set_ty(Some((**ty_dec).clone()));
+            }
+        }
+
         ret_val
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for FunctionCall {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        // Variables containing a function shadow the functions of the same name
+        if let Some(CheckedType::Resolved(ty)) = ctx.get_variable(self.name()) {
+            if let Some((args, ret)) = ty.signature() {
+                return self.resolve_signature_type(&[], args, Some(ret), ctx);
+            }
+        }
+
+        // Functions declared in jinko shadow builtins
+        let function = match (ctx.get_function(self.name()), ctx.get_builtin(self.name())) {
+            (Some(f), _) => f,
+            (None, Some(builtin)) => {
+                return self.resolve_signature_type(
+                    builtin.generics(),
+                    builtin.args(),
+                    builtin.ty(),
+                    ctx,
+                )
+            }
+            (None, None) => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!("cannot find function {}", self.name()))
+                        .with_loc(self.location.clone()),
+                );
+                return CheckedType::Unknown;
+            }
+        };
+
+        if self.args().len() != function.args().len() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "wrong number of arguments \
+                    for call to function `{}`: expected {}, got {}",
+                        self.fn_name,
+                        function.args().len(),
+                        self.args().len()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+        }
+
+        let arg_types: Vec<CheckedType> =
+            self.args.iter_mut().map(|arg| arg.type_of(ctx)).collect();
+        let declared: Vec<TypeId> = function
+            .args()
+            .iter()
+            .map(|arg| ctx.normalize(arg.get_type()))
+            .collect();
+
+        let bindings = match self.bind_generics(function.generics(), &declared, &arg_types) {
+            Ok(bindings) => bindings,
+            // Type parameters cannot be inferred from arguments of unknown types
+            Err(_) if arg_types.contains(&CheckedType::Unknown) => return CheckedType::Unknown,
+            Err(e) => {
+                ctx.error(e.with_loc(self.location.clone()));
+                return CheckedType::Unknown;
+            }
+        };
+
+        for (((call_arg, arg_ty), func_arg), declared) in self
+            .args
+            .iter()
+            .zip(arg_types)
+            .zip(function.args())
+            .zip(declared)
+        {
+            let expected = declared.substitute(&bindings);
+
+            match arg_ty {
+                CheckedType::Resolved(arg_ty) if arg_ty != expected => {
+                    ctx.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                        "wrong type for argument `{}` in call to function `{}`: expected `{}`, got `{}`",
+                        func_arg.name(),
+                        self.fn_name,
+                        expected,
+                        arg_ty
+                    )).with_loc(self.location.clone()))
+                }
+                CheckedType::Void => {
+                    ctx.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                        "trying to map statement to function argument: {} -> {}",
+                        call_arg.print(),
+                        func_arg
+                    )).with_loc(self.location.clone()))
+                }
+                _ => {}
+            }
+        }
+
+        match function.ty() {
+            Some(ty) => CheckedType::Resolved(ctx.normalize(ty).substitute(&bindings)),
+            None => CheckedType::Void,
+        }
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
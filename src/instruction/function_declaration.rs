@@ -1,8 +1,11 @@
 //! Function Declarations are used when adding a new function to the source. They contain
 //! a name, a list of required arguments as well as an associated code block
 
-use crate::instruction::{Block, DecArg, InstrKind, Instruction, TypeId};
-use crate::{Context, ErrKind, Error, ObjectInstance};
+use crate::builtins::Builtin;
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{Block, DecArg, InstrKind, Instruction, TypeId, TypeParam};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ErrKind, Error, ObjectInstance};
 
 /// What "kind" of function is defined. There are four types of functions in jinko,
 /// the normal ones, the external ones, the unit tests and the mocks
@@ -20,8 +23,10 @@ pub struct FunctionDec {
     name: String,
     ty: Option<TypeId>,
     kind: FunctionKind,
+    generics: Vec<TypeParam>,
     args: Vec<DecArg>,
     block: Option<Block>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl FunctionDec {
@@ -31,8 +36,10 @@ impl FunctionDec {
             name,
             ty,
             kind: FunctionKind::Unknown,
+            generics: Vec::new(),
             args: Vec::new(),
             block: None,
+            location: None,
         }
     }
 
@@ -51,10 +58,12 @@ impl FunctionDec {
                 b.add_instruction(instruction);
                 Ok(())
             }
-            None => Err(Error::new(ErrKind::Context).with_msg(format!(
+            None => Err(Error::new(ErrKind::Context)
+                .with_msg(format!(
                 "function {} has no instruction block. It might be an extern function or an error",
                 self.name
-            ))),
+            ))
+                .with_loc(self.location.clone())),
         }
     }
 
@@ -68,6 +77,16 @@ impl FunctionDec {
         self.ty.as_ref()
     }
 
+    /// Type of the function when it is used as a value, such as `func(int) -> bool`.
+    /// Only functions returning a value can be used as values
+    pub fn value_type(&self) -> Option<TypeId> {
+        let args = self.args.iter().map(|arg| arg.get_type().clone()).collect();
+
+        self.ty
+            .as_ref()
+            .map(|ty| TypeId::function(args, ty.clone()))
+    }
+
     /// Set the type of the function
     pub fn set_ty(&mut self, ty: Option<TypeId>) {
         self.ty = ty
@@ -84,6 +103,16 @@ impl FunctionDec {
         self.kind = kind
     }
 
+    /// Return a reference to the function's type parameters
+    pub fn generics(&self) -> &Vec<TypeParam> {
+        &self.generics
+    }
+
+    /// Set the type parameters of a generic function
+    pub fn set_generics(&mut self, generics: Vec<TypeParam>) {
+        self.generics = generics
+    }
+
     /// Return a reference to the function's arguments
     pub fn args(&self) -> &Vec<DecArg> {
         &self.args
@@ -109,17 +138,110 @@ impl FunctionDec {
     pub fn run(&self, ctx: &mut Context) -> Option<ObjectInstance> {
         let block = match self.block() {
             Some(b) => b,
-            // FIXME: Fix Location and input
             None => {
-                ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                    "cannot execute function {} as it is marked `ext`",
-                    self.name()
-                )));
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "cannot execute function {} as it is marked `ext`",
+                            self.name()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
                 return None;
             }
         };
 
-        block.execute(ctx)
+        let ret_val = block.execute(ctx);
+
+        // A `return` interrupts the function's block and gives the function its value.
+        // The typechecker makes sure that no loop signal can escape a function, but an
+        // abort keeps stopping the function's callers
+        match ctx.take_control_flow() {
+            Some(ControlFlow::Return(value)) => value,
+            Some(ControlFlow::Abort) => {
+                ctx.set_control_flow(ControlFlow::Abort);
+                None
+            }
+            _ => ret_val,
+        }
+    }
+
+    /// Run an external function with the instances given to its arguments. External
+    /// functions named after a builtin are implemented by the interpreter, and looked up
+    /// in the linked libraries otherwise
+    pub(crate) fn run_ext(
+        &self,
+        ctx: &mut Context,
+        args: Vec<ObjectInstance>,
+    ) -> Option<ObjectInstance> {
+        match ctx.get_builtin(self.name()) {
+            Some(builtin) => builtin.clone().call(ctx, args),
+            None => crate::ffi::call(self, &args, ctx),
+        }
+    }
+
+    /// Check if the function can be used in place of a builtin
+    fn matches_builtin(&self, builtin: &Builtin) -> bool {
+        builtin.ty() == self.ty()
+            && builtin.generics() == self.generics()
+            && builtin
+                .args()
+                .iter()
+                .eq(self.args.iter().map(DecArg::get_type))
+    }
+
+    /// Check that an external function implemented by a builtin is declared with the
+    /// builtin's signature
+    fn check_ext(&self, ctx: &mut TypeCtx) {
+        match ctx.get_builtin(self.name()) {
+            Some(builtin) if !self.matches_builtin(&builtin) => ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "external function `{}` does not have the same signature as the builtin implementing it",
+                        self.name()
+                    ))
+                    .with_loc(self.location.clone()),
+            ),
+            _ => {}
+        }
+    }
+
+    /// Check that a mock replaces an existing function, and that they have the same
+    /// signature
+    fn check_mock(&self, ctx: &mut TypeCtx) {
+        let same_signature = match (ctx.get_function(self.name()), ctx.get_builtin(self.name())) {
+            (Some(function), _) => {
+                function.ty() == self.ty()
+                    && function
+                        .args()
+                        .iter()
+                        .map(DecArg::get_type)
+                        .eq(self.args.iter().map(DecArg::get_type))
+            }
+            (None, Some(builtin)) => self.matches_builtin(&builtin),
+            (None, None) => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "cannot mock function `{}`: function does not exist",
+                            self.name()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return;
+            }
+        };
+
+        if !same_signature {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "mock `{}` does not have the same signature as the function it mocks",
+                        self.name()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+        }
     }
 }
 
@@ -142,9 +264,15 @@ impl Instruction for FunctionDec {
                     ctx.error(e);
                 }
             }
-            FunctionKind::Mock | FunctionKind::Unknown => ctx.error(
+            FunctionKind::Mock => {
+                if let Err(e) = ctx.add_mock(self.clone()) {
+                    ctx.error(e.with_loc(self.location.clone()));
+                }
+            }
+            FunctionKind::Unknown => ctx.error(
                 Error::new(ErrKind::Context)
-                    .with_msg(format!("unknown type for function {}", self.name())),
+                    .with_msg(format!("unknown type for function {}", self.name()))
+                    .with_loc(self.location.clone()),
             ),
         }
 
@@ -162,7 +290,14 @@ impl Instruction for FunctionDec {
             FunctionKind::Unknown => "UNKNOWN",
         });
 
-        base = format!("{} {}(", base, self.name);
+        base = format!("{} {}", base, self.name);
+
+        if !self.generics.is_empty() {
+            let generics: Vec<String> = self.generics.iter().map(TypeParam::to_string).collect();
+            base.push_str(&format!("<{}>", generics.join(", ")));
+        }
+
+        base.push('(');
 
         let mut first_arg = true;
         for arg in &self.args {
@@ -170,13 +305,13 @@ impl Instruction for FunctionDec {
                 base.push_str(", ");
             }
 
-            base.push_str(&format!("{}: {}", arg.name(), arg.get_type().id()));
+            base.push_str(&arg.to_string());
 
             first_arg = false;
         }
 
         base = match &self.ty {
-            Some(ty) => format!("{}) -> {}", base, ty.id()),
+            Some(ty) => format!("{}) -> {}", base, ty),
             None => format!("{})", base),
         };
 
@@ -185,6 +320,14 @@ impl Instruction for FunctionDec {
             None => format!("{} {{}}", base),
         }
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
 }
 
 impl Default for FunctionDec {
@@ -199,6 +342,69 @@ impl std::fmt::Debug for FunctionDec {
     }
 }
 
+impl TypeCheck for FunctionDec {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        ctx.declare_function(self.clone());
+
+        match self.kind {
+            FunctionKind::Mock => self.check_mock(ctx),
+            FunctionKind::Ext => self.check_ext(ctx),
+            _ => {}
+        }
+
+        // The type parameters and the arguments are only visible in the function
+        ctx.scope_enter();
+        ctx.declare_type_params(&self.generics);
+
+        for arg in &self.args {
+            ctx.check_arg_type_exists(arg);
+        }
+
+        if let Some(ty) = &self.ty {
+            ctx.check_type_exists(ty);
+        }
+
+        // External functions do not have a block to typecheck
+        if let Some(block) = &mut self.block {
+            let return_type = self.ty.as_ref().map(|ty| ctx.normalize(ty));
+            ctx.function_enter(return_type.clone());
+
+            for arg in &self.args {
+                let ty = ctx.normalize(arg.get_type());
+                ctx.declare_variable(arg.name(), CheckedType::Resolved(ty));
+            }
+
+            let block_ty = block.type_of(ctx);
+
+            let always_returns = ctx.function_exit();
+
+            match (&return_type, block_ty) {
+                (Some(expected), CheckedType::Resolved(found)) if expected != &found => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "function `{}` should return `{}` but returns `{}`",
+                            self.name, expected, found
+                        ))
+                        .with_loc(self.location.clone()),
+                ),
+                (Some(expected), CheckedType::Void) if !always_returns => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "function `{}` should return `{}` but does not return anything",
+                            self.name, expected,
+                        ))
+                        .with_loc(self.location.clone()),
+                ),
+                _ => {}
+            }
+        }
+
+        ctx.scope_exit();
+
+        CheckedType::Void
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +431,21 @@ mod tests {
 
         assert_eq!(function.print(), "func fn(arg0: int, arg1: int) -> int {}");
     }
+
+    #[test]
+    fn t_ext_implemented_by_builtin() {
+        let mut ctx = crate::Parser::parse("ext func assert(value: bool); assert(1 == 2)", None);
+
+        assert!(ctx.execute().is_err());
+        assert_eq!(ctx.error_handler.errors()[0].kind(), ErrKind::Assertion);
+    }
+
+    #[test]
+    fn t_ext_different_signature_than_builtin() {
+        let mut ctx = crate::Parser::parse("ext func assert(value: int);", None);
+
+        ctx.type_check();
+
+        assert!(ctx.has_errors());
+    }
 }
@@ -1,7 +1,7 @@
 //! `IfElse`s are used to represent an if/else statement in the source code. They have
 //! a condition, a body and an optional else body.
 //!
-//! ```
+//! ```text
 //! if condition {
 //!     condition_evaluates_to_true();
 //! } else {
@@ -11,18 +11,22 @@
 //!
 //! They can be used to return values, just like you would with any block.
 //!
-//! ```
+//! ```text
 //! x = if condition { 12 } else { 13 };
 //! ```
 
+use crate::error::ErrSpaceLocation;
 use crate::instruction::{Block, InstrKind, Instruction};
-use crate::{Context, ObjectInstance};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ErrKind, Error, ObjectInstance};
 
 #[derive(Clone)]
 pub struct IfElse {
     condition: Box<dyn Instruction>,
     if_body: Block,
     else_body: Option<Block>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl IfElse {
@@ -36,6 +40,8 @@ impl IfElse {
             condition,
             if_body,
             else_body,
+            cached_type: None,
+            location: None,
         }
     }
 }
@@ -75,6 +81,80 @@ impl Instruction for IfElse {
             }
         }
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for IfElse {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        ctx.check_condition(&mut *self.condition);
+
+        let returned = ctx.has_returned();
+
+        let if_ty = self.if_body.type_of(ctx);
+        let if_returns = ctx.has_returned();
+        ctx.set_returned(returned);
+
+        // Without an `else`, the condition does not evaluate to a value, and its body
+        // might not be executed
+        let else_body = match &mut self.else_body {
+            Some(body) => body,
+            None => return CheckedType::Void,
+        };
+
+        let else_ty = else_body.type_of(ctx);
+        let else_returns = ctx.has_returned();
+        ctx.set_returned(returned || (if_returns && else_returns));
+
+        // A branch which returns from the function does not give its value to the
+        // condition
+        let if_ty = if if_returns {
+            CheckedType::Unknown
+        } else {
+            if_ty
+        };
+        let else_ty = if else_returns {
+            CheckedType::Unknown
+        } else {
+            else_ty
+        };
+
+        match (if_ty, else_ty) {
+            (CheckedType::Resolved(if_ty), CheckedType::Resolved(else_ty)) if if_ty != else_ty => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "if and else branches have different types: `{}` and `{}`",
+                            if_ty, else_ty
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                CheckedType::Unknown
+            }
+            (CheckedType::Resolved(ty), CheckedType::Void)
+            | (CheckedType::Void, CheckedType::Resolved(ty)) => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "if and else branches have different types: only one of them evaluates to a value, of type `{}`",
+                            ty
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                CheckedType::Unknown
+            }
+            (CheckedType::Unknown, else_ty) => else_ty,
+            (if_ty, _) => if_ty,
+        }
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
@@ -151,4 +231,32 @@ mod tests {
             JkInt::from(69).to_instance()
         );
     }
+
+    #[test]
+    fn t_if_function_condition() {
+        use crate::instance::ToObjectInstance;
+        use crate::jinko_ex;
+        use crate::value::JkInt;
+
+        let res = jinko_ex! {
+            func is_true() -> bool { true }
+            if is_true() { 42 } else { 69 }
+        };
+
+        assert_eq!(res, Some(JkInt::from(42).to_instance()));
+    }
+
+    #[test]
+    fn t_if_variable_condition() {
+        use crate::instance::ToObjectInstance;
+        use crate::jinko_ex;
+        use crate::value::JkInt;
+
+        let res = jinko_ex! {
+            cond = false;
+            if cond { 42 } else { 69 }
+        };
+
+        assert_eq!(res, Some(JkInt::from(69).to_instance()));
+    }
 }
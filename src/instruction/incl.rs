@@ -1,9 +1,11 @@
 //! This module is used to parse external code and make it available to other source
 //! files.
 
+use crate::error::ErrSpaceLocation;
 use std::path::{Path, PathBuf};
 
-use crate::{parser::Construct, Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{parser::Parser, Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
 
 /// An `Incl` is constituted of a path, an optional alias and contains a context.
 /// The ctx is built from parsing the source file in the path.
@@ -12,14 +14,35 @@ use crate::{parser::Construct, Context, ErrKind, Error, InstrKind, Instruction,
 pub struct Incl {
     path: String,
     alias: Option<String>,
+    /// Path and instructions of the included source, with the types recorded while
+    /// typechecking them
+    content: Option<(PathBuf, Vec<Box<dyn Instruction>>)>,
+    location: Option<ErrSpaceLocation>,
 }
 
 /// Default file that gets included when including a directory in jinko source code
 const DEFAULT_INCL: &str = "/lib.jk";
 
+/// Sources of the standard library, included by every context. They are embedded in the
+/// interpreter so that the standard library does not depend on the directory jinko is
+/// run from
+const STDLIB: &[(&str, &str)] = &[
+    ("stdlib/lib.jk", include_str!("../../stdlib/lib.jk")),
+    ("stdlib/io.jk", include_str!("../../stdlib/io.jk")),
+    ("stdlib/option.jk", include_str!("../../stdlib/option.jk")),
+    ("stdlib/pair.jk", include_str!("../../stdlib/pair.jk")),
+    ("stdlib/range.jk", include_str!("../../stdlib/range.jk")),
+    ("stdlib/result.jk", include_str!("../../stdlib/result.jk")),
+];
+
 impl Incl {
     pub fn new(path: String, alias: Option<String>) -> Incl {
-        Incl { path, alias }
+        Incl {
+            path,
+            alias,
+            content: None,
+            location: None,
+        }
     }
 
     fn format_candidates(&self, base: &Path) -> (PathBuf, PathBuf) {
@@ -38,26 +61,60 @@ impl Incl {
         (PathBuf::from(dir_fmt), PathBuf::from(file_fmt))
     }
 
+    /// Embedded source of the standard library file located at `path`, if any
+    fn embedded_source(path: &Path) -> Option<&'static str> {
+        STDLIB
+            .iter()
+            .find(|(stdlib_path, _)| Path::new(stdlib_path) == path)
+            .map(|(_, source)| *source)
+    }
+
+    /// Check if there is a source file at `path`, either embedded or on the filesystem
+    fn is_source(path: &Path) -> bool {
+        Incl::embedded_source(path).is_some() || path.is_file()
+    }
+
     fn find_include_path(&self, base: &Path) -> Result<PathBuf, Error> {
         let (dir_candidate, file_candidate) = self.format_candidates(base);
 
-        let (dir_valid, file_valid) = (dir_candidate.is_file(), file_candidate.is_file());
+        let (dir_valid, file_valid) = (
+            Incl::is_source(&dir_candidate),
+            Incl::is_source(&file_candidate),
+        );
 
         match (dir_valid, file_valid) {
             // We cannot have both <path>/lib.jk and <path>.jk be valid files
-            (true, true) => Err(Error::new(ErrKind::Context).with_msg(format!(
-                "invalid include: {:?} and {:?} are both valid candidates",
-                dir_candidate, file_candidate
-            ))),
-            (false, false) => Err(Error::new(ErrKind::Context).with_msg(format!(
-                "no candidate for include: {:?} and {:?} do not exist",
-                dir_candidate, file_candidate
-            ))),
+            (true, true) => Err(Error::new(ErrKind::Context)
+                .with_msg(format!(
+                    "invalid include: {:?} and {:?} are both valid candidates",
+                    dir_candidate, file_candidate
+                ))
+                .with_loc(self.location.clone())),
+            (false, false) => Err(Error::new(ErrKind::Context)
+                .with_msg(format!(
+                    "no candidate for include: {:?} and {:?} do not exist",
+                    dir_candidate, file_candidate
+                ))
+                .with_loc(self.location.clone())),
             (false, true) => Ok(file_candidate),
             (true, false) => Ok(dir_candidate),
         }
     }
 
+    /// Read and parse the source file located at `path`. Standard library files are
+    /// read from the interpreter instead of the filesystem
+    fn parse_source(path: &Path) -> Result<Vec<Box<dyn Instruction>>, Vec<Error>> {
+        let input = match Incl::embedded_source(path) {
+            Some(source) => source.to_owned(),
+            None => std::fs::read_to_string(path).map_err(|e| vec![Error::from(e)])?,
+        };
+
+        // We can't just parse the input, since it adds the instructions
+        // to an entry block in order to execute them. What we can do, is
+        // parse many instructions and add them to an empty ctx
+        Parser::parse_instructions(&input, Some(path))
+    }
+
     /// Parse the code and load it in the Incl's ctx
     fn inner_load(
         &self,
@@ -80,33 +137,10 @@ impl Incl {
 
         ctx.debug("FINAL PATH", &format!("{:?}", formatted));
 
-        let input = match std::fs::read_to_string(&formatted) {
-            Ok(i) => i,
-            Err(e) => {
-                ctx.error(Error::from(e));
-                return None;
-            }
-        };
-
-        // We can't just parse the input, since it adds the instructions
-        // to an entry block in order to execute them. What we can do, is
-        // parse many instructions and add them to an empty ctx
-        let (remaining_input, instructions) = match Construct::many_instructions(input.as_str()) {
-            Ok(tuple) => tuple,
-            Err(e) => {
-                ctx.error(Error::from(e));
-                return None;
-            }
-        };
-
-        match remaining_input.len() {
-            // The remaining input is empty: We parsed the whole file properly
-            0 => Some((formatted, instructions)),
-            _ => {
-                ctx.error(Error::new(ErrKind::Parsing).with_msg(format!(
-                    "error when parsing included file: {:?},\non the following input:\n{}",
-                    formatted, remaining_input
-                )));
+        match Incl::parse_source(&formatted) {
+            Ok(instructions) => Some((formatted, instructions)),
+            Err(errors) => {
+                errors.into_iter().for_each(|e| ctx.error(e));
                 None
             }
         }
@@ -151,8 +185,8 @@ impl Incl {
         }
     }
 
-    fn get_base(&self, ctx: &mut Context) -> PathBuf {
-        match ctx.path() {
+    fn get_base(path: Option<&PathBuf>) -> PathBuf {
+        match path {
             // Get the parent directory of the context's source file. We can unwrap
             // since there's always a base
             Some(path) => path.parent().unwrap().to_owned(),
@@ -187,19 +221,38 @@ impl Instruction for Incl {
     fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
         ctx.debug("INCL ENTER", self.print().as_str());
 
-        let base = self.get_base(ctx);
+        let base = Incl::get_base(ctx.path());
         let _prefix = self.format_prefix()?;
 
         ctx.debug("BASE DIR", &format!("{:#?}", base));
 
         let old_path = ctx.path().cloned();
 
-        let (new_path, mut content) = self.load(&base, ctx)?;
+        let (new_path, content) = match &self.content {
+            // A source is only included once, even if it was typechecked
+            Some((path, _)) if ctx.is_included(path) => (path.clone(), vec![]),
+            Some((path, content)) => (path.clone(), content.clone()),
+            None => {
+                let (new_path, mut content) = self.load(&base, ctx)?;
+
+                // Sources which are included without being typechecked first, such as
+                // the standard library, still need the types of their instructions. Type
+                // errors are reported to the context like any other error
+                let mut type_ctx = TypeCtx::new(ctx);
+                type_ctx.set_path(Some(new_path.clone()));
+                type_ctx.hoist_declarations(&content);
+                content.iter_mut().for_each(|instr| {
+                    instr.type_of(&mut type_ctx);
+                });
+
+                (new_path, content)
+            }
+        };
 
         // Temporarily change the path of the context
         ctx.set_path(Some(new_path));
 
-        content.iter_mut().for_each(|instr| {
+        content.iter().for_each(|instr| {
             // FIXME: Rework prefixing
             // instr.prefix(&prefix);
 
@@ -213,4 +266,55 @@ impl Instruction for Incl {
 
         None
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Incl {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let base = Incl::get_base(ctx.path());
+
+        let path = match self.find_include_path(&base) {
+            Ok(p) => p,
+            Err(e) => {
+                ctx.error(e);
+                return CheckedType::Void;
+            }
+        };
+
+        if ctx.is_included(&path) {
+            return CheckedType::Void;
+        }
+
+        let mut content = match Incl::parse_source(&path) {
+            Ok(instructions) => instructions,
+            Err(errors) => {
+                errors.into_iter().for_each(|e| ctx.error(e));
+                return CheckedType::Void;
+            }
+        };
+
+        // Included sources are typechecked in the includer's scope, just like they
+        // are executed in it
+        let old_path = ctx.path().cloned();
+        ctx.set_path(Some(path.clone()));
+
+        ctx.hoist_declarations(&content);
+        content.iter_mut().for_each(|instr| {
+            instr.type_of(ctx);
+        });
+
+        ctx.set_path(old_path);
+
+        // Executing the include reuses the typechecked instructions
+        self.content = Some((path, content));
+
+        CheckedType::Void
+    }
 }
@@ -0,0 +1,223 @@
+//! Indexing gives the element of an array at a given position, starting from zero.
+//! Indices outside of the array are an error.
+//!
+//! ```text
+//! values = [4, 5, 6];
+//! values[1] // 5
+//! ```
+
+use std::convert::TryFrom;
+
+use super::method_call::caller_instance;
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::value::JkInt;
+use crate::{
+    Context, ControlFlow, ErrKind, Error, FromObjectInstance, InstrKind, Instruction,
+    ObjectInstance,
+};
+
+#[derive(Clone)]
+pub struct Index {
+    array: Box<dyn Instruction>,
+    index: Box<dyn Instruction>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl Index {
+    /// Create a new access to the element of `array` at position `index`
+    pub fn new(array: Box<dyn Instruction>, index: Box<dyn Instruction>) -> Index {
+        Index {
+            array,
+            index,
+            cached_type: None,
+            location: None,
+        }
+    }
+}
+
+/// Execute `index`, an integer used as a position in an array
+pub(crate) fn execute_index(index: &dyn Instruction, ctx: &mut Context) -> Option<i64> {
+    Some(JkInt::from_instance(&index.execute_expression(ctx)?).0)
+}
+
+/// Check that `idx` is a valid position in `array`, whose instruction is printed as
+/// `name`. Indexing outside of the array, or into an instance which is not an array,
+/// is an error
+pub(crate) fn checked_index(array: &ObjectInstance, name: &str, idx: i64) -> Result<usize, Error> {
+    if !array.is_array() {
+        return Err(Error::new(ErrKind::Context).with_msg(format!(
+            "cannot index into `{}`, which is not an array",
+            name
+        )));
+    }
+
+    let len = array.elements().len();
+    match usize::try_from(idx) {
+        Ok(idx) if idx < len => Ok(idx),
+        _ => Err(Error::new(ErrKind::Context).with_msg(format!(
+            "index out of bounds: the length is {} but the index is {}",
+            len, idx
+        ))),
+    }
+}
+
+/// Typecheck the indexing of `array` by `index`, returning the type of the array's
+/// elements if it is known
+pub(crate) fn index_type(
+    array: &mut dyn Instruction,
+    index: &mut dyn Instruction,
+    ctx: &mut TypeCtx,
+    location: Option<ErrSpaceLocation>,
+) -> CheckedType {
+    match index.type_of(ctx) {
+        CheckedType::Resolved(ty) if ty.id() != "int" => ctx.error(
+            Error::new(ErrKind::TypeChecker)
+                .with_msg(format!(
+                    "arrays can only be indexed by `int`, not by `{}` of type `{}`",
+                    index.print(),
+                    ty
+                ))
+                .with_loc(location.clone()),
+        ),
+        CheckedType::Void => ctx.error(
+            Error::new(ErrKind::TypeChecker)
+                .with_msg(format!(
+                    "trying to use statement `{}` as an index",
+                    index.print()
+                ))
+                .with_loc(location.clone()),
+        ),
+        _ => {}
+    }
+
+    match array.type_of(ctx) {
+        CheckedType::Resolved(ty) => match ty.element_type() {
+            Some(element) => CheckedType::Resolved(element),
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "cannot index into `{}` of type `{}`, which is not an array",
+                            array.print(),
+                            ty
+                        ))
+                        .with_loc(location),
+                );
+                CheckedType::Unknown
+            }
+        },
+        CheckedType::Void => {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!("statement `{}` cannot be indexed", array.print()))
+                    .with_loc(location),
+            );
+            CheckedType::Unknown
+        }
+        CheckedType::Unknown => CheckedType::Unknown,
+    }
+}
+
+impl Instruction for Index {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Expression(None)
+    }
+
+    fn print(&self) -> String {
+        format!("{}[{}]", self.array.print(), self.index.print())
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("INDEX", &self.print());
+
+        // The index is executed first, so that arrays stored in variables can be
+        // borrowed and only the indexed element gets copied
+        let idx = execute_index(self.index.as_ref(), ctx)?;
+        let element = {
+            let array = caller_instance(self.array.as_ref(), ctx)?;
+            checked_index(&array, &self.array.print(), idx).map(|idx| array.elements()[idx].clone())
+        };
+
+        match element {
+            Ok(element) => Some(element),
+            Err(e) => {
+                ctx.error(e.with_loc(self.location.clone()));
+                ctx.set_control_flow(ControlFlow::Abort);
+
+                None
+            }
+        }
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Index {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        index_type(
+            self.array.as_mut(),
+            self.index.as_mut(),
+            ctx,
+            self.location.clone(),
+        )
+    }
+
+    cached_type!();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::ToObjectInstance;
+    use crate::jinko_ex;
+    use crate::parser::{Construct, Parser};
+    use crate::value::JkInt;
+
+    #[test]
+    fn t_index_pretty_print() {
+        let index = Construct::instruction("get(0)[1 + 1]").unwrap().1;
+
+        assert_eq!(index.print(), "get(0)[1 + 1]");
+    }
+
+    #[test]
+    fn t_index_gives_element() {
+        let res = jinko_ex! {
+            values = [4, 5, 6];
+            values[1] + values[2]
+        };
+
+        assert_eq!(res, Some(JkInt::from(11).to_instance()));
+    }
+
+    #[test]
+    fn t_index_nested() {
+        let res = jinko_ex! {
+            grid = [[1, 2], [3, 4]];
+            grid[1][0]
+        };
+
+        assert_eq!(res, Some(JkInt::from(3).to_instance()));
+    }
+
+    #[test]
+    fn t_index_out_of_bounds() {
+        let mut ctx = Parser::parse("values = [4, 5, 6]; values[3]", None);
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_index_negative() {
+        let mut ctx = Parser::parse("values = [4, 5, 6]; values[0 - 1]", None);
+
+        assert!(ctx.execute().is_err());
+    }
+}
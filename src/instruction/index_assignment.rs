@@ -0,0 +1,198 @@
+//! Assigning to an element of an array replaces the element at a given position.
+//! Like other assignments, it is only possible on mutable variables, and it is a
+//! statement.
+//!
+//! ```text
+//! mut values = [4, 5, 6];
+//! values[1] = 15; // [4, 15, 6]
+//! ```
+
+use super::index::{checked_index, execute_index, index_type};
+use crate::error::ErrSpaceLocation;
+use crate::instruction::Var;
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
+
+#[derive(Clone)]
+pub struct IndexAssign {
+    array: Var,
+    index: Box<dyn Instruction>,
+    value: Box<dyn Instruction>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl IndexAssign {
+    /// Create a new assignment of `value` to the element at position `index` of the
+    /// array contained in `array`
+    pub fn new(
+        array: Var,
+        index: Box<dyn Instruction>,
+        value: Box<dyn Instruction>,
+    ) -> IndexAssign {
+        IndexAssign {
+            array,
+            index,
+            value,
+            location: None,
+        }
+    }
+}
+
+impl Instruction for IndexAssign {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Statement
+    }
+
+    fn print(&self) -> String {
+        format!(
+            "{}[{}] = {}",
+            self.array.name(),
+            self.index.print(),
+            self.value.print()
+        )
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("INDEX ASSIGN", &self.print());
+
+        match ctx.get_variable(self.array.name()) {
+            Some(var) if var.mutable() => {}
+            Some(var) => {
+                let name = var.name().to_string();
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "trying to assign value to non mutable variable `{}`: `{}`",
+                            name,
+                            self.value.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
+            }
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "variable has not been declared: {}",
+                            self.array.name()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
+            }
+        };
+
+        let idx = execute_index(self.index.as_ref(), ctx)?;
+        let value = self.value.execute_expression(ctx)?;
+
+        // The element is replaced in place, without copying the rest of the array
+        let res = match ctx.get_variable_mut(self.array.name()) {
+            Some(var) => {
+                let array = var.instance_mut();
+                checked_index(array, self.array.name(), idx).map(|idx| {
+                    if let Some(elements) = array.elements_mut() {
+                        elements[idx] = value;
+                    }
+                })
+            }
+            None => Ok(()),
+        };
+
+        if let Err(e) = res {
+            ctx.error(e.with_loc(self.location.clone()));
+            ctx.set_control_flow(ControlFlow::Abort);
+        }
+
+        None
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for IndexAssign {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let element_ty = index_type(
+            &mut self.array,
+            self.index.as_mut(),
+            ctx,
+            self.location.clone(),
+        );
+
+        match (self.value.type_of(ctx), element_ty) {
+            (CheckedType::Void, _) => ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "trying to assign statement `{}` to an element of `{}`",
+                        self.value.print(),
+                        self.array.name()
+                    ))
+                    .with_loc(self.location.clone()),
+            ),
+            (CheckedType::Resolved(value_ty), CheckedType::Resolved(element_ty))
+                if value_ty != element_ty =>
+            {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to assign value of type `{}` to an element of `{}` of type `{}`",
+                            value_ty,
+                            self.array.name(),
+                            element_ty
+                        ))
+                        .with_loc(self.location.clone()),
+                )
+            }
+            _ => {}
+        }
+
+        // An assignment is always a statement
+        CheckedType::Void
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::ToObjectInstance;
+    use crate::jinko_ex;
+    use crate::parser::{Construct, Parser};
+    use crate::value::JkInt;
+
+    #[test]
+    fn t_index_assign_pretty_print() {
+        let assign = Construct::instruction("values[0] = 1").unwrap().1;
+
+        assert_eq!(assign.print(), "values[0] = 1");
+    }
+
+    #[test]
+    fn t_index_assign_replaces_element() {
+        let res = jinko_ex! {
+            mut values = [4, 5, 6];
+            values[1] = 15;
+            values[0] + values[1] + values[2]
+        };
+
+        assert_eq!(res, Some(JkInt::from(25).to_instance()));
+    }
+
+    #[test]
+    fn t_index_assign_immutable() {
+        let mut ctx = Parser::parse("values = [4, 5, 6]; values[1] = 15;", None);
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_index_assign_out_of_bounds() {
+        let mut ctx = Parser::parse("mut values = [4, 5, 6]; values[3] = 15;", None);
+
+        assert!(ctx.execute().is_err());
+    }
+}
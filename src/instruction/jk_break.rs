@@ -0,0 +1,108 @@
+//! The Break construct is used to stop the execution of the enclosing loop
+//! ```text
+//! break
+//! ```
+//!
+//! When used in a `loop`, it can give a value to the loop
+//!
+//! ```text
+//! x = loop {
+//!     break 42
+//! }
+//! ```
+
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{InstrKind, Instruction};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ObjectInstance};
+
+#[derive(Clone)]
+pub struct Break {
+    value: Option<Box<dyn Instruction>>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl Break {
+    /// Create a new Break instruction
+    pub fn new(value: Option<Box<dyn Instruction>>) -> Break {
+        Break {
+            value,
+            location: None,
+        }
+    }
+}
+
+impl Instruction for Break {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Statement
+    }
+
+    fn print(&self) -> String {
+        let base = "break".to_string();
+
+        match &self.value {
+            Some(val) => format!("{} {}", base, val.print()),
+            None => base,
+        }
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug_step("BREAK");
+
+        let value = match &self.value {
+            Some(val) => Some(val.execute_expression(ctx)?),
+            None => None,
+        };
+
+        ctx.set_control_flow(ControlFlow::Break(value));
+
+        None
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Break {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let value_ty = self.value.as_mut().map(|val| val.type_of(ctx));
+
+        ctx.check_break(value_ty);
+
+        CheckedType::Void
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::JkInt;
+    use crate::ToObjectInstance;
+
+    #[test]
+    fn t_break_pretty_print() {
+        assert_eq!(Break::new(None).print(), "break");
+        assert_eq!(
+            Break::new(Some(Box::new(JkInt::from(42)))).print(),
+            "break 42"
+        );
+    }
+
+    #[test]
+    fn t_break_raises_signal() {
+        let mut ctx = Context::new();
+
+        assert!(Break::new(Some(Box::new(JkInt::from(42))))
+            .execute(&mut ctx)
+            .is_none());
+        assert_eq!(
+            ctx.take_control_flow(),
+            Some(ControlFlow::Break(Some(JkInt::from(42).to_instance())))
+        );
+    }
+}
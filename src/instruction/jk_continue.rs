@@ -0,0 +1,44 @@
+//! The Continue construct is used to skip to the next iteration of the enclosing loop
+//! ```text
+//! continue
+//! ```
+
+use crate::instruction::{InstrKind, Instruction};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ObjectInstance};
+
+#[derive(Clone, Default)]
+pub struct Continue;
+
+impl Continue {
+    /// Create a new Continue instruction
+    pub fn new() -> Continue {
+        Continue
+    }
+}
+
+impl Instruction for Continue {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Statement
+    }
+
+    fn print(&self) -> String {
+        "continue".to_string()
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug_step("CONTINUE");
+
+        ctx.set_control_flow(ControlFlow::Continue);
+
+        None
+    }
+}
+
+impl TypeCheck for Continue {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        ctx.check_continue();
+
+        CheckedType::Void
+    }
+}
@@ -3,7 +3,9 @@
 //! really an `Instruction`, and therefore their implementation lives in the parser
 //! module. They are executed at "compile" time, when running through the code first.
 
+use crate::error::ErrSpaceLocation;
 use crate::instruction::{FunctionCall, InstrKind, Instruction};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
 use crate::{Context, ErrKind, Error, ObjectInstance};
 
 /// The potential ctx instructions
@@ -17,7 +19,10 @@ pub enum JkInstKind {
 #[derive(Clone)]
 pub struct JkInst {
     kind: JkInstKind,
+    // FIXME: No directive makes use of its arguments yet
+    #[allow(dead_code)]
     args: Vec<Box<dyn Instruction>>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl JkInst {
@@ -39,6 +44,7 @@ impl JkInst {
         Ok(Self {
             kind,
             args: fc.args().clone(),
+            location: None,
         })
     }
 
@@ -76,6 +82,22 @@ impl Instruction for JkInst {
         // on the context.
         None
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for JkInst {
+    fn resolve_type(&mut self, _ctx: &mut TypeCtx) -> CheckedType {
+        // Interpreter directives' arguments are never executed, so they are not
+        // typechecked
+        CheckedType::Void
+    }
 }
 
 #[cfg(test)]
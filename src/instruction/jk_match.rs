@@ -0,0 +1,460 @@
+//! `Match`es execute one of their arms depending on the variant of an enum instance.
+//! The fields of the variant are bound to the names given in the pattern of the arm,
+//! in a new scope. `_` ignores a field, and a pattern without names ignores all the
+//! fields of the variant.
+//!
+//! ```text
+//! match shape {
+//!     Circle(radius) => radius * radius * 3.14,
+//!     Rect(width, _) => width * width,
+//!     Empty => 0.0,
+//! }
+//! ```
+//!
+//! Matches must be exhaustive: Every variant of the enum has to be matched by an arm,
+//! unless the last arm of the match is the wildcard arm `_`, which matches any variant.
+//! Like blocks, matches can be used to return values.
+
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{InstrKind, Instruction, TypeId, Var};
+use crate::typechecker::generics::Bindings;
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ErrKind, Error, ObjectInstance};
+
+#[derive(Clone)]
+pub struct MatchArm {
+    /// Name of the matched variant, or `None` for the wildcard arm
+    variant: Option<String>,
+    /// Names given to the fields of the variant, or `None` for the ignored ones
+    bindings: Vec<Option<String>>,
+    body: Box<dyn Instruction>,
+}
+
+impl MatchArm {
+    /// Create a new arm matching a variant, or any variant if `variant` is `None`
+    pub fn new(
+        variant: Option<String>,
+        bindings: Vec<Option<String>>,
+        body: Box<dyn Instruction>,
+    ) -> MatchArm {
+        MatchArm {
+            variant,
+            bindings,
+            body,
+        }
+    }
+
+    /// Does the arm match the given variant. Wildcard arms match every variant
+    fn matches(&self, variant: &str) -> bool {
+        match self.variant.as_deref() {
+            Some(name) => name == variant,
+            None => true,
+        }
+    }
+
+    fn print(&self) -> String {
+        let mut pattern = self.variant.clone().unwrap_or_else(|| String::from("_"));
+
+        if !self.bindings.is_empty() {
+            let bindings: Vec<&str> = self
+                .bindings
+                .iter()
+                .map(|binding| binding.as_deref().unwrap_or("_"))
+                .collect();
+            pattern.push_str(&format!("({})", bindings.join(", ")));
+        }
+
+        format!("{} => {}", pattern, self.body.print())
+    }
+}
+
+#[derive(Clone)]
+pub struct Match {
+    value: Box<dyn Instruction>,
+    arms: Vec<MatchArm>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl Match {
+    /// Create a new match on a value, with its arms
+    pub fn new(value: Box<dyn Instruction>, arms: Vec<MatchArm>) -> Match {
+        Match {
+            value,
+            arms,
+            cached_type: None,
+            location: None,
+        }
+    }
+
+    /// Add the fields of a variant to the current scope, under the names the arm binds
+    /// them to
+    fn bind_fields(&self, arm: &MatchArm, instance: &ObjectInstance, ctx: &mut Context) {
+        let variant = instance
+            .ty()
+            .zip(instance.variant())
+            .and_then(|(ty, variant)| ty.variant(variant).cloned());
+
+        let fields = variant.iter().flat_map(|variant| variant.fields().iter());
+
+        for (binding, field) in arm.bindings.iter().zip(fields) {
+            let name = match binding {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let mut var = Var::new(name.clone());
+            match instance.get_field(field.name()) {
+                Ok(field_instance) => var.set_instance(field_instance),
+                Err(e) => {
+                    ctx.error(e.with_loc(self.location.clone()));
+                    continue;
+                }
+            }
+
+            if let Err(e) = ctx.add_variable(var) {
+                ctx.error(e.with_loc(self.location.clone()));
+            }
+        }
+    }
+
+    /// Check that an arm matches an existing variant of the enum, and get the types of
+    /// the fields it binds
+    fn arm_field_types(
+        &self,
+        arm: &MatchArm,
+        enum_ty: Option<&(TypeId, Bindings)>,
+        ctx: &mut TypeCtx,
+    ) -> Vec<CheckedType> {
+        let unknown = vec![CheckedType::Unknown; arm.bindings.len()];
+
+        let ((ty, bindings), name) = match (enum_ty, &arm.variant) {
+            (Some(enum_ty), Some(name)) => (enum_ty, name),
+            _ => return unknown,
+        };
+
+        // We can unwrap since only existing enums are matched on
+        let enum_dec = ctx.get_type(ty).unwrap();
+        let variant = match enum_dec.variant(name) {
+            Some(variant) => variant,
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "enum `{}` has no variant named `{}`",
+                            enum_dec.name(),
+                            name
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return unknown;
+            }
+        };
+
+        if !arm.bindings.is_empty() && arm.bindings.len() != variant.fields().len() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "wrong number of fields in pattern for variant `{}`: expected {}, got {}",
+                        name,
+                        variant.fields().len(),
+                        arm.bindings.len()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return unknown;
+        }
+
+        variant
+            .fields()
+            .iter()
+            .take(arm.bindings.len())
+            .map(|field| {
+                CheckedType::Resolved(ctx.normalize(field.get_type()).substitute(bindings))
+            })
+            .collect()
+    }
+
+    /// Check that every variant of the enum is matched by one arm exactly
+    fn check_arms(&self, enum_ty: &TypeId, ctx: &mut TypeCtx) {
+        // We can unwrap since only existing enums are matched on
+        let enum_dec = ctx.get_type(enum_ty).unwrap();
+
+        for (idx, arm) in self.arms.iter().enumerate() {
+            let previous = &self.arms[..idx];
+
+            let error = match &arm.variant {
+                _ if previous.iter().any(|previous| previous.variant.is_none()) => Some(
+                    String::from("the wildcard arm `_` must be the last arm of a match"),
+                ),
+                Some(name) if previous.iter().any(|previous| previous.matches(name)) => {
+                    Some(format!("variant `{}` is matched more than once", name))
+                }
+                _ => None,
+            };
+
+            if let Some(msg) = error {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(msg)
+                        .with_loc(self.location.clone()),
+                );
+                return;
+            }
+        }
+
+        let missing: Vec<String> = enum_dec
+            .variants()
+            .iter()
+            .filter(|variant| !self.arms.iter().any(|arm| arm.matches(variant.name())))
+            .map(|variant| format!("`{}`", variant.name()))
+            .collect();
+
+        if !missing.is_empty() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "non-exhaustive match on enum `{}`: missing {}",
+                        enum_ty,
+                        missing.join(", ")
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+        }
+    }
+}
+
+impl Instruction for Match {
+    fn kind(&self) -> InstrKind {
+        // The typechecker will have approved that all arms return the same thing
+        self.arms
+            .first()
+            .map_or(InstrKind::Statement, |arm| arm.body.kind())
+    }
+
+    fn print(&self) -> String {
+        let arms: Vec<String> = self.arms.iter().map(MatchArm::print).collect();
+
+        format!("match {} {{ {} }}", self.value.print(), arms.join(", "))
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("MATCH ENTER", &self.print());
+
+        let instance = self.value.execute_expression(ctx)?;
+
+        let variant = match instance.variant() {
+            Some(variant) => variant.to_string(),
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "cannot match on `{}`, which is not an enum instance",
+                            self.value.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
+            }
+        };
+
+        let arm = match self.arms.iter().find(|arm| arm.matches(&variant)) {
+            Some(arm) => arm,
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!("no arm matches variant `{}`", variant))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
+            }
+        };
+
+        ctx.debug("MATCHED", &variant);
+
+        ctx.scope_enter();
+        self.bind_fields(arm, &instance, ctx);
+        let result = arm.body.execute(ctx);
+        ctx.scope_exit();
+
+        ctx.debug_step("MATCH EXIT");
+
+        result
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Match {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let enum_ty = match self.value.type_of(ctx) {
+            CheckedType::Resolved(ty) => match ctx.get_type(&ty) {
+                Some(enum_dec) if enum_dec.is_enum() => {
+                    // The fields of the variants of generic enums are of the types the
+                    // instance was created with
+                    let bindings: Bindings = enum_dec
+                        .generics()
+                        .iter()
+                        .map(|param| param.name().to_string())
+                        .zip(ctx.normalize(&ty).generics().iter().cloned())
+                        .collect();
+
+                    Some((ty, bindings))
+                }
+                _ => {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "cannot match on `{}` of type `{}`, which is not an enum",
+                                self.value.print(),
+                                ty
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                    None
+                }
+            },
+            CheckedType::Void => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "statement `{}` cannot be matched on",
+                            self.value.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                None
+            }
+            CheckedType::Unknown => None,
+        };
+
+        if let Some((ty, _)) = &enum_ty {
+            self.check_arms(ty, ctx);
+        }
+
+        let returned = ctx.has_returned();
+        let mut all_return = !self.arms.is_empty();
+
+        let mut match_ty: Option<CheckedType> = None;
+        for idx in 0..self.arms.len() {
+            let field_types = self.arm_field_types(&self.arms[idx], enum_ty.as_ref(), ctx);
+            let arm = &mut self.arms[idx];
+
+            ctx.scope_enter();
+            for (binding, ty) in arm.bindings.iter().zip(field_types) {
+                if let Some(name) = binding {
+                    ctx.declare_variable(name, ty);
+                }
+            }
+            let arm_ty = arm.body.type_of(ctx);
+            ctx.scope_exit();
+
+            // An arm which returns from the function does not give its value to the match
+            let arm_ty = match ctx.has_returned() {
+                true => CheckedType::Unknown,
+                false => {
+                    all_return = false;
+                    arm_ty
+                }
+            };
+            ctx.set_returned(returned);
+
+            match (&match_ty, arm_ty) {
+                (_, CheckedType::Unknown) => {}
+                (Some(CheckedType::Resolved(expected)), CheckedType::Resolved(found))
+                    if expected != &found =>
+                {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "match arms have different types: `{}` and `{}`",
+                                expected, found
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                    return CheckedType::Unknown;
+                }
+                (None, arm_ty) => match_ty = Some(arm_ty),
+                _ => {}
+            }
+        }
+
+        ctx.set_returned(returned || all_return);
+
+        match_ty.unwrap_or(CheckedType::Unknown)
+    }
+
+    cached_type!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance::ToObjectInstance;
+    use crate::jinko;
+    use crate::parser::Construct;
+    use crate::JkInt;
+
+    fn setup() -> Context {
+        jinko! {
+            enum Shape { Square(side: int), Rect(width: int, height: int), Empty }
+            square = Square { side = 3 };
+            rect = Rect { width = 2, height = 5 };
+            empty = Empty;
+        }
+    }
+
+    fn area(ctx: &mut Context, shape: &str) -> Option<ObjectInstance> {
+        let input = format!(
+            "match {} {{ Square(s) => s * s, Rect(w, h) => w * h, Empty => 0 }}",
+            shape
+        );
+
+        Construct::instruction(&input).unwrap().1.execute(ctx)
+    }
+
+    #[test]
+    fn t_match_binds_fields() {
+        let mut ctx = setup();
+
+        assert_eq!(area(&mut ctx, "square"), Some(JkInt::from(9).to_instance()));
+        assert_eq!(area(&mut ctx, "rect"), Some(JkInt::from(10).to_instance()));
+        assert_eq!(area(&mut ctx, "empty"), Some(JkInt::from(0).to_instance()));
+    }
+
+    #[test]
+    fn t_match_bindings_are_scoped() {
+        let mut ctx = setup();
+
+        area(&mut ctx, "rect");
+        assert!(ctx.get_variable("w").is_none());
+        assert!(!ctx.error_handler.has_errors());
+    }
+
+    #[test]
+    fn t_match_wildcard() {
+        let mut ctx = setup();
+
+        let inst = Construct::instruction("match rect { Square => 1, _ => 2 }")
+            .unwrap()
+            .1;
+
+        assert_eq!(inst.execute(&mut ctx), Some(JkInt::from(2).to_instance()));
+    }
+
+    #[test]
+    fn t_match_on_non_enum() {
+        let mut ctx = setup();
+
+        let inst = Construct::instruction("match 1 { _ => 2 }").unwrap().1;
+
+        assert!(inst.execute(&mut ctx).is_none());
+        assert!(ctx.error_handler.has_errors());
+    }
+}
@@ -1,26 +1,35 @@
-//! Return construct is used to return early from a function
-//! ```
+//! Return construct is used to return early from a function. It stops the execution of
+//! the enclosing blocks and loops, up until the function it returns from
+//! ```text
 //! return
 //! ```
 //!
 //! It can be used to return values
 //!
-//! ```
+//! ```text
 //! return 42
 //! ```
 
+use crate::error::ErrSpaceLocation;
 use crate::instruction::{InstrKind, Instruction};
-use crate::{Context, ObjectInstance};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ErrKind, Error, ObjectInstance};
 
 #[derive(Clone)]
 pub struct Return {
     value: Option<Box<dyn Instruction>>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl Return {
     /// Create a new Return instruction
     pub fn new(value: Option<Box<dyn Instruction>>) -> Return {
-        Return { value }
+        Return {
+            value,
+            cached_type: None,
+            location: None,
+        }
     }
 }
 
@@ -42,11 +51,81 @@ impl Instruction for Return {
     }
 
     fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
-        match &self.value {
-            Some(val) => val.execute(ctx),
+        ctx.debug_step("RETURN");
+
+        let value = match &self.value {
+            Some(val) => Some(val.execute_expression(ctx)?),
             None => None,
+        };
+
+        // The value is given to the enclosing function, which stops executing
+        ctx.set_control_flow(ControlFlow::Return(value));
+
+        None
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Return {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let value_ty = match &mut self.value {
+            Some(val) => val.type_of(ctx),
+            None => CheckedType::Void,
+        };
+
+        ctx.mark_return();
+
+        // Any value can be returned from the entry point, but functions without a return
+        // type cannot return one
+        let expected = match ctx.return_type() {
+            Some(Some(ty)) => ty.clone(),
+            Some(None) => {
+                if let CheckedType::Resolved(found) = &value_ty {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "returning value of type `{}` from function without a return type",
+                                found
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                }
+                return value_ty;
+            }
+            None => return value_ty,
+        };
+
+        match &value_ty {
+            CheckedType::Resolved(found) if found != &expected => ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "returning value of type `{}` from function returning `{}`",
+                        found, expected
+                    ))
+                    .with_loc(self.location.clone()),
+            ),
+            CheckedType::Void => ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "returning nothing from function returning `{}`",
+                        expected
+                    ))
+                    .with_loc(self.location.clone()),
+            ),
+            _ => {}
         }
+
+        value_ty
     }
+
+    cached_type!();
 }
 
 #[cfg(test)]
@@ -105,4 +184,60 @@ mod tests {
 
         assert_eq!(res, Some(JkInt::from(42).to_instance()));
     }
+
+    #[test]
+    fn t_return_stops_function() {
+        use crate::instance::ToObjectInstance;
+        use crate::value::JkInt;
+
+        let res = jinko_ex! {
+            func f(early: bool) -> int {
+                if early {
+                    return 1
+                }
+                2
+            }
+            f(true) + f(false) * 10
+        };
+
+        assert_eq!(res, Some(JkInt::from(21).to_instance()));
+    }
+
+    #[test]
+    fn t_return_from_nested_loops() {
+        use crate::instance::ToObjectInstance;
+        use crate::value::JkInt;
+
+        let res = jinko_ex! {
+            func f() -> int {
+                for i in range(0, 10) {
+                    loop {
+                        if i == 4 {
+                            return i
+                        }
+                        break;
+                    }
+                }
+                0
+            }
+            f()
+        };
+
+        assert_eq!(res, Some(JkInt::from(4).to_instance()));
+    }
+
+    #[test]
+    fn t_return_from_entry_point() {
+        use crate::instance::ToObjectInstance;
+        use crate::value::JkInt;
+
+        let res = jinko_ex! {
+            if true {
+                return 3
+            }
+            5
+        };
+
+        assert_eq!(res, Some(JkInt::from(3).to_instance()));
+    }
 }
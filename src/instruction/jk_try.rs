@@ -0,0 +1,269 @@
+//! The `?` operator propagates missing values and errors to the caller of a function.
+//! Applied to an `Option`, it gives the value contained in `Some`, or returns `None`
+//! from the enclosing function. Applied to a `Result`, it gives the value contained in
+//! `Ok`, or returns the `Err` from the enclosing function.
+//!
+//! ```text
+//! func first_twice(values: Option<int>) -> Option<int> {
+//!     v = values?;
+//!     Some { value = v * 2 }
+//! }
+//! ```
+//!
+//! The enclosing function has to return an `Option` when `?` is applied to an
+//! `Option`, and a `Result` with the same error type when it is applied to a `Result`.
+//! `?` cannot be used outside of such a function, as there would be no caller to give
+//! the value back to.
+
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{InstrKind, Instruction, TypeDec};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ErrKind, Error, ObjectInstance};
+
+/// Name of the stdlib's `Option` enum
+const OPTION: &str = "Option";
+
+/// Name of the stdlib's `Result` enum
+const RESULT: &str = "Result";
+
+#[derive(Clone)]
+pub struct Try {
+    value: Box<dyn Instruction>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl Try {
+    /// Create a new `?` operation on a value
+    pub fn new(value: Box<dyn Instruction>) -> Try {
+        Try {
+            value,
+            cached_type: None,
+            location: None,
+        }
+    }
+}
+
+impl Instruction for Try {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Expression(None)
+    }
+
+    fn print(&self) -> String {
+        format!("{}?", self.value.print())
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("TRY", &self.print());
+
+        let instance = self.value.execute_expression(ctx)?;
+
+        let is_propagated = matches!(instance.ty().map(TypeDec::name), Some(OPTION | RESULT));
+
+        match instance.variant() {
+            Some("Some" | "Ok") if is_propagated => {
+                // Both variants contain a single field
+                let field = instance
+                    .fields()
+                    .iter()
+                    .flatten()
+                    .next()
+                    .map(|(_, field)| field.instance().clone());
+
+                if field.is_none() {
+                    ctx.error(
+                        Error::new(ErrKind::Context)
+                            .with_msg(format!("no value in `{}`", self.value.print()))
+                            .with_loc(self.location.clone()),
+                    );
+                }
+
+                field
+            }
+            Some("None" | "Err") if is_propagated => {
+                // The instance is given back to the enclosing function's caller
+                ctx.set_control_flow(ControlFlow::Return(Some(instance)));
+
+                None
+            }
+            _ => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "cannot use `?` on `{}`, which is not an `Option` or a `Result`",
+                            self.value.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+
+                None
+            }
+        }
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Try {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let ty = match self.value.type_of(ctx) {
+            CheckedType::Resolved(ty) => ctx.normalize(&ty),
+            CheckedType::Void => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "statement `{}` cannot be used with `?`",
+                            self.value.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                return CheckedType::Unknown;
+            }
+            CheckedType::Unknown => return CheckedType::Unknown,
+        };
+
+        if ty.id() != OPTION && ty.id() != RESULT {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "`?` can only be used on an `Option` or a `Result`, not on `{}` of type `{}`",
+                        self.value.print(),
+                        ty
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return CheckedType::Unknown;
+        }
+
+        // The value is given back to the caller of the enclosing function, which must be
+        // able to return it
+        match ctx.return_type() {
+            Some(Some(expected)) => {
+                let expected = ctx.normalize(&expected.clone());
+
+                // Results can only be propagated to functions returning the same errors
+                let compatible = expected.id() == ty.id()
+                    && (ty.id() == OPTION || expected.generics().get(1) == ty.generics().get(1));
+
+                if !compatible {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "cannot use `?` on `{}` of type `{}` in a function returning `{}`",
+                                self.value.print(),
+                                ty,
+                                expected
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                }
+            }
+            _ => ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "cannot use `?` on `{}` outside of a function returning an `{}` or a `{}`",
+                        self.value.print(),
+                        OPTION,
+                        RESULT
+                    ))
+                    .with_loc(self.location.clone()),
+            ),
+        }
+
+        ty.generics()
+            .first()
+            .cloned()
+            .map_or(CheckedType::Unknown, CheckedType::Resolved)
+    }
+
+    cached_type!();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::ToObjectInstance;
+    use crate::jinko_ex;
+    use crate::parser::{Construct, Parser};
+    use crate::value::{JkBool, JkInt};
+
+    #[test]
+    fn t_try_pretty_print() {
+        let try_inst = Construct::instruction("get(1)?").unwrap().1;
+
+        assert_eq!(try_inst.print(), "get(1)?");
+    }
+
+    #[test]
+    fn t_try_gives_value() {
+        let res = jinko_ex! {
+            func f(o: Option<int>) -> Option<int> {
+                x = o?;
+                Some { value = x + 1 }
+            }
+            unwrap(f(Some { value = 41 }))
+        };
+
+        assert_eq!(res, Some(JkInt::from(42).to_instance()));
+    }
+
+    #[test]
+    fn t_try_returns_none() {
+        let res = jinko_ex! {
+            func f(o: Option<int>) -> Option<int> {
+                x = o?;
+                Some { value = x + 1 }
+            }
+            is_none(f(None))
+        };
+
+        assert_eq!(res, Some(JkBool::from(true).to_instance()));
+    }
+
+    #[test]
+    fn t_try_returns_err() {
+        let res = jinko_ex! {
+            func check(x: int) -> Result<int, int> {
+                if x < 0 {
+                    return Err { error = x }
+                }
+                Ok { value = x }
+            }
+            func sum(a: int, b: int) -> Result<int, int> {
+                Ok { value = check(a)? + check(b)? }
+            }
+            unwrap(err(sum(1, -3))) + unwrap(ok(sum(4, 5)))
+        };
+
+        assert_eq!(res, Some(JkInt::from(6).to_instance()));
+    }
+
+    #[test]
+    fn t_try_on_type_instantiation() {
+        let res = jinko_ex! {
+            func check(x: int) -> Result<int, int> {
+                y = Ok { value = x }?;
+                if y < 0 {
+                    Err { error = y }?
+                }
+                Ok { value = y }
+            }
+            unwrap(err(check(-2))) + unwrap(ok(check(5)))
+        };
+
+        assert_eq!(res, Some(JkInt::from(3).to_instance()));
+    }
+
+    #[test]
+    fn t_try_outside_function() {
+        let mut ctx = Parser::parse("func none() -> Option<int> { None } x = none()?;", None);
+
+        assert!(ctx.execute().is_err());
+        assert!(ctx.get_variable("x").is_none());
+    }
+}
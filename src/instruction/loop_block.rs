@@ -1,15 +1,22 @@
 //! The Loop instruction is used for repeating instructions. They can be of three
 //! different kinds, `for`, `while` or `loop`.
+//! The execution of a loop can be controlled using `break` and `continue`. A `loop`
+//! can be broken out of with a value, which the loop then evaluates to.
 
+use crate::error::ErrSpaceLocation;
+use crate::instance::{iterated_entry_types, iterated_type};
 use crate::instruction::{Block, InstrKind, Instruction, Var};
-use crate::{Context, ErrKind, Error, ObjectInstance};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ControlFlow, ErrKind, Error, ObjectInstance};
 
 /// What kind of loop the loop block represents: Either a for Loop, with a variable and
-/// a range expression, a while loop with just an upper bound, or a loop with no bound
-/// at all
+/// a range expression, a for loop over the entries of a map, with a variable for the
+/// keys and one for the values, a while loop with just an upper bound, or a loop with
+/// no bound at all
 #[derive(Clone)]
 pub enum LoopKind {
-    For(Var, Box<dyn Instruction>),
+    For(Box<Var>, Box<dyn Instruction>),
+    ForEntries(Box<Var>, Box<Var>, Box<dyn Instruction>),
     While(Box<dyn Instruction>),
     Loop,
 }
@@ -20,11 +27,71 @@ pub enum LoopKind {
 pub struct Loop {
     kind: LoopKind,
     block: Block,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl Loop {
     pub fn new(kind: LoopKind, block: Block) -> Loop {
-        Loop { kind, block }
+        Loop {
+            kind,
+            block,
+            location: None,
+        }
+    }
+
+    /// Execute the body of the loop once, handling the `break` and `continue`
+    /// instructions it contains. Returns `Some` if the loop needs to stop, along with
+    /// the value the loop evaluates to
+    fn run_body(&self, ctx: &mut Context) -> Option<Option<ObjectInstance>> {
+        self.block.execute(ctx);
+
+        // Do not keep looping over erroneous code
+        if ctx.error_handler.has_errors() {
+            return Some(None);
+        }
+
+        match ctx.take_control_flow() {
+            Some(ControlFlow::Break(value)) => Some(value),
+            Some(ControlFlow::Continue) | None => None,
+            // Returns and aborts go through the loop and stop the enclosing function
+            Some(signal) => {
+                ctx.set_control_flow(signal);
+                Some(None)
+            }
+        }
+    }
+
+    /// Execute the body of the loop once, in a new scope where the loop variables are
+    /// bound to the given instances. Returns `Some` if the loop needs to stop, like
+    /// `run_body`
+    fn run_iteration(
+        &self,
+        ctx: &mut Context,
+        bindings: Vec<(&Var, ObjectInstance)>,
+    ) -> Option<Option<ObjectInstance>> {
+        // Each iteration gets its own scope, so that the loop variables are declared
+        // again on every iteration
+        ctx.scope_enter();
+
+        let mut declared = Ok(());
+        for (var, value) in bindings {
+            let mut var = var.clone();
+            var.set_instance(value);
+
+            declared = declared.and_then(|_| ctx.add_variable(var));
+        }
+
+        let stop = match declared {
+            Ok(_) => self.run_body(ctx),
+            Err(e) => {
+                ctx.error(e);
+                Some(None)
+            }
+        };
+
+        ctx.scope_exit();
+
+        stop
     }
 }
 
@@ -41,6 +108,13 @@ impl Instruction for Loop {
                 range.print(),
                 self.block.print()
             ),
+            LoopKind::ForEntries(key, value, map) => format!(
+                "for ({}, {}) in {} {}\n",
+                key.name(),
+                value.name(),
+                map.print(),
+                self.block.print()
+            ),
             LoopKind::While(condition) => {
                 format!("while {} {}\n", condition.print(), self.block.print())
             }
@@ -52,68 +126,192 @@ impl Instruction for Loop {
         match &self.kind {
             LoopKind::Loop => loop {
                 ctx.debug_step("LOOP ENTER");
-                self.block.execute(ctx)?;
+                if let Some(value) = self.run_body(ctx) {
+                    return value;
+                }
                 ctx.debug_step("LOOP EXIT");
             },
             LoopKind::While(cond) => {
                 ctx.debug_step("WHILE ENTER");
                 while cond.as_bool(ctx)? {
-                    self.block.execute(ctx)?;
+                    if let Some(value) = self.run_body(ctx) {
+                        return value;
+                    }
                 }
                 ctx.debug_step("WHILE EXIT");
             }
-            LoopKind::For(_var, _range) => {
-                // FIXME:
-                //
-                // In order to have a correct for implementation, jinko needs ranges,
-                // iterators, which implies the need for options, which implies the need
-                // for a standard library, which implies the need for code inclusion, etc
-                // etc etc... All of that is the goal of the 0.1.1 release.
-                //
-                // A possible implementation would be to execute the range, assign it
-                // to the var, and check that var is a valid iterator. For example,
-                // by calling `Iter::ok(var)` (whatever the API might be) and checking
-                // that that result, as a boolean, returns true. If it does, execute the
-                // body. If it does not, break from the for.
-
-                ctx.error(
-                    Error::new(ErrKind::Context)
-                        .with_msg("for loops are currently unimplemented".to_string()),
-                );
-                return None;
-
-                // FIXME: Rework that code
-                // ctx.debug_step("FOR ENTER");
-                // let var_name = var.name().to_owned();
-                // ctx.scope_enter();
-
-                // ctx.add_variable(var.clone())?;
-
-                // loop {
-                //     range.execute(ctx)?;
-
-                //     // We can unwrap since we added the variable right before
-                //     if !ctx.get_variable(&var_name).unwrap().as_bool() {
-                //         break;
-                //     }
-
-                //     self.block.execute(ctx)?;
-                // }
-
-                // ctx.scope_exit();
-                // ctx.debug_step("FOR EXIT");
+            LoopKind::For(var, range) => {
+                ctx.debug_step("FOR ENTER");
+
+                let iterable = range.execute_expression(ctx)?;
+                let values = match iterable.iter() {
+                    Ok(values) => values,
+                    Err(e) => {
+                        ctx.error(e.with_loc(self.location.clone()));
+                        return None;
+                    }
+                };
+
+                for value in values {
+                    if let Some(value) = self.run_iteration(ctx, vec![(var, value)]) {
+                        return value;
+                    }
+                }
+
+                ctx.debug_step("FOR EXIT");
+            }
+            LoopKind::ForEntries(key_var, value_var, map) => {
+                ctx.debug_step("FOR ENTRIES ENTER");
+
+                let map = map.execute_expression(ctx)?;
+                let entries = match map.iter_entries() {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        ctx.error(e.with_loc(self.location.clone()));
+                        return None;
+                    }
+                };
+
+                for (key, value) in entries {
+                    let bindings = vec![(&**key_var, key), (&**value_var, value)];
+
+                    if let Some(value) = self.run_iteration(ctx, bindings) {
+                        return value;
+                    }
+                }
+
+                ctx.debug_step("FOR ENTRIES EXIT");
             }
         }
 
-        // FIXME: Add logic. Right now they only return on error, not the actual value
         None
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Loop {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        // The body of a loop might not be executed, or be exited before returning
+        let returned = ctx.has_returned();
+
+        match &mut self.kind {
+            LoopKind::Loop => {
+                ctx.loop_enter(true);
+                self.block.type_of(ctx);
+                ctx.set_returned(returned);
+
+                // A `loop` can only be exited using `break`, so it evaluates to the
+                // values given to its `break` instructions
+                return ctx.loop_exit().unwrap_or(CheckedType::Void);
+            }
+            LoopKind::While(cond) => {
+                ctx.check_condition(&mut **cond);
+
+                ctx.loop_enter(false);
+                self.block.type_of(ctx);
+                ctx.loop_exit();
+            }
+            LoopKind::For(var, range) => {
+                let var_ty = match range.type_of(ctx) {
+                    CheckedType::Resolved(ty) => match iterated_type(&ty) {
+                        Some(iterated) => CheckedType::Resolved(iterated),
+                        None => {
+                            ctx.error(
+                                Error::new(ErrKind::TypeChecker)
+                                    .with_msg(format!(
+                                        "cannot iterate over an instance of type `{}`",
+                                        ty
+                                    ))
+                                    .with_loc(self.location.clone()),
+                            );
+                            CheckedType::Unknown
+                        }
+                    },
+                    CheckedType::Void => {
+                        ctx.error(
+                            Error::new(ErrKind::TypeChecker)
+                                .with_msg(format!(
+                                    "cannot iterate over statement `{}`",
+                                    range.print()
+                                ))
+                                .with_loc(self.location.clone()),
+                        );
+                        CheckedType::Unknown
+                    }
+                    CheckedType::Unknown => CheckedType::Unknown,
+                };
+
+                ctx.scope_enter();
+                ctx.declare_variable(var.name(), var_ty);
+
+                ctx.loop_enter(false);
+                self.block.type_of(ctx);
+                ctx.loop_exit();
+
+                ctx.scope_exit();
+            }
+            LoopKind::ForEntries(key_var, value_var, map) => {
+                let (key_ty, value_ty) = match map.type_of(ctx) {
+                    CheckedType::Resolved(ty) => match iterated_entry_types(&ty) {
+                        Some((key, value)) => {
+                            (CheckedType::Resolved(key), CheckedType::Resolved(value))
+                        }
+                        None => {
+                            ctx.error(
+                                Error::new(ErrKind::TypeChecker)
+                                    .with_msg(format!(
+                                        "cannot iterate over the entries of an instance of type `{}`",
+                                        ty
+                                    ))
+                                    .with_loc(self.location.clone()),
+                            );
+                            (CheckedType::Unknown, CheckedType::Unknown)
+                        }
+                    },
+                    CheckedType::Void => {
+                        ctx.error(
+                            Error::new(ErrKind::TypeChecker)
+                                .with_msg(format!(
+                                    "cannot iterate over statement `{}`",
+                                    map.print()
+                                ))
+                                .with_loc(self.location.clone()),
+                        );
+                        (CheckedType::Unknown, CheckedType::Unknown)
+                    }
+                    CheckedType::Unknown => (CheckedType::Unknown, CheckedType::Unknown),
+                };
+
+                ctx.scope_enter();
+                ctx.declare_variable(key_var.name(), key_ty);
+                ctx.declare_variable(value_var.name(), value_ty);
+
+                ctx.loop_enter(false);
+                self.block.type_of(ctx);
+                ctx.loop_exit();
+
+                ctx.scope_exit();
+            }
+        }
+
+        ctx.set_returned(returned);
+
+        CheckedType::Void
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::instruction::FunctionCall;
+    use crate::{jinko, JkInt, Parser, ToObjectInstance};
 
     #[test]
     fn pretty_print_loop() {
@@ -127,11 +325,88 @@ mod tests {
     fn pretty_print_for() {
         let r = Box::new(FunctionCall::new("iter".to_owned()));
         let b = Block::new();
-        let l = Loop::new(LoopKind::For(Var::new("i".to_owned()), r), b);
+        let l = Loop::new(LoopKind::For(Box::new(Var::new("i".to_owned())), r), b);
 
         assert_eq!(l.print().as_str(), "for i in iter() {\n}\n")
     }
 
+    #[test]
+    fn t_for_range() {
+        let ctx = jinko! {
+            mut sum = 0;
+            for i in range(0, 5) {
+                sum = sum + i;
+            }
+        };
+
+        assert!(!ctx.error_handler.has_errors());
+        assert_eq!(
+            ctx.get_variable("sum").unwrap().instance(),
+            JkInt::from(10).to_instance()
+        );
+    }
+
+    #[test]
+    fn t_for_variable_type() {
+        let mut ctx = Parser::parse("func f(b: bool) {} for i in range(0, 2) { f(i); }", None);
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_for_not_iterable() {
+        let ctx = jinko! {
+            for i in true {
+                i;
+            }
+        };
+
+        assert!(ctx.error_handler.has_errors());
+    }
+
+    #[test]
+    fn pretty_print_for_entries() {
+        let m = Box::new(FunctionCall::new("entries".to_owned()));
+        let b = Block::new();
+        let l = Loop::new(
+            LoopKind::ForEntries(
+                Box::new(Var::new("k".to_owned())),
+                Box::new(Var::new("v".to_owned())),
+                m,
+            ),
+            b,
+        );
+
+        assert_eq!(l.print().as_str(), "for (k, v) in entries() {\n}\n")
+    }
+
+    #[test]
+    fn t_for_entries() {
+        let ctx = jinko! {
+            mut sum = 0;
+            for (k, v) in [1: 10, 2: 20, 3: 30] {
+                sum = sum + k * v;
+            }
+        };
+
+        assert!(!ctx.error_handler.has_errors());
+        assert_eq!(
+            ctx.get_variable("sum").unwrap().instance(),
+            JkInt::from(140).to_instance()
+        );
+    }
+
+    #[test]
+    fn t_for_entries_not_a_map() {
+        let ctx = jinko! {
+            for (k, v) in [1, 2] {
+                k;
+            }
+        };
+
+        assert!(ctx.error_handler.has_errors());
+    }
+
     #[test]
     fn pretty_print_while() {
         let r = Box::new(Block::new());
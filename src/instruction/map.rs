@@ -0,0 +1,483 @@
+//! Maps associate keys to values. All the keys of a map have the same type, as do all
+//! its values. They are created from literals, and the type of a map from strings to
+//! integers is written `Map<string, int>`. Empty maps are written `[:]`.
+//!
+//! ```text
+//! mut ages = ["alice": 31, "bob": 27];
+//! ages.insert("carol", 45);
+//! for (name, age) in ages {
+//!     println("{name}: {age}");
+//! }
+//! ```
+//!
+//! Maps have a few built-in methods: `get(k)` returns the value associated to a key in
+//! an `Option`, `insert(k, v)` associates a value to a key, `remove(k)` removes a key
+//! and returns its value in an `Option`, `contains(k)` checks if a key is present, and
+//! `keys()` and `values()` give arrays of the keys and values, in insertion order.
+//! `insert` and `remove` modify the map in place, and can only be called on mutable
+//! variables.
+//!
+//! Keys are compared structurally, by their type and the value of each of their fields,
+//! so two keys are the same if they contain the same value. Looking up, inserting and
+//! removing a key does not go through the other entries.
+
+use super::method_call::{
+    caller_instance, caller_instance_mut, check_variable_caller, make_option, option_of,
+};
+use crate::error::ErrSpaceLocation;
+use crate::instance::ToObjectInstance;
+use crate::instruction::{TypeDec, TypeId};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::value::JkBool;
+use crate::{Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
+
+/// Key and value of an entry in a map literal
+pub type MapEntry = (Box<dyn Instruction>, Box<dyn Instruction>);
+
+#[derive(Clone)]
+pub struct Map {
+    entries: Vec<MapEntry>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl Map {
+    /// Create a new map literal from its entries
+    pub fn new(entries: Vec<MapEntry>) -> Map {
+        Map {
+            entries,
+            cached_type: None,
+            location: None,
+        }
+    }
+
+    /// Check that the key or value `inst` of an entry is of the type of the previous
+    /// keys or values, if there were any. Returns the type the next keys or values
+    /// should have
+    fn check_entry_part(
+        inst: &mut dyn Instruction,
+        part: &str,
+        expected: Option<TypeId>,
+        ctx: &mut TypeCtx,
+        location: Option<ErrSpaceLocation>,
+    ) -> Option<TypeId> {
+        match (inst.type_of(ctx), expected) {
+            (CheckedType::Resolved(ty), Some(expected)) if ty != expected => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "wrong type for {} `{}` of map: expected `{}`, got `{}`",
+                            part,
+                            inst.print(),
+                            expected,
+                            ty
+                        ))
+                        .with_loc(location),
+                );
+                Some(expected)
+            }
+            (CheckedType::Resolved(ty), None) => Some(ty),
+            (CheckedType::Void, expected) => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to use statement `{}` as a map {}",
+                            inst.print(),
+                            part
+                        ))
+                        .with_loc(location),
+                );
+                expected
+            }
+            (_, expected) => expected,
+        }
+    }
+}
+
+impl Instruction for Map {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Expression(None)
+    }
+
+    fn print(&self) -> String {
+        if self.entries.is_empty() {
+            return String::from("[:]");
+        }
+
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key.print(), value.print()))
+            .collect();
+
+        format!("[{}]", entries.join(", "))
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug("MAP", &self.print());
+
+        let mut entries = vec![];
+        for (key, value) in &self.entries {
+            entries.push((key.execute_expression(ctx)?, value.execute_expression(ctx)?));
+        }
+
+        // Like arrays, the entries carry their concrete types
+        let (key_ty, value_ty) = match (entries.first(), &self.cached_type) {
+            (Some((key, value)), _) => (instance_type(key), instance_type(value)),
+            (None, Some(CheckedType::Resolved(ty))) => match ty.map_types() {
+                Some((key, value)) => (key.clone(), value.clone()),
+                None => (TypeId::from(""), TypeId::from("")),
+            },
+            (None, _) => (TypeId::from(""), TypeId::from("")),
+        };
+
+        Some(ObjectInstance::map(&key_ty, &value_ty, entries))
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for Map {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let mut key_ty = None;
+        let mut value_ty = None;
+
+        for (key, value) in self.entries.iter_mut() {
+            key_ty = Map::check_entry_part(key.as_mut(), "key", key_ty, ctx, self.location.clone());
+            value_ty = Map::check_entry_part(
+                value.as_mut(),
+                "value",
+                value_ty,
+                ctx,
+                self.location.clone(),
+            );
+        }
+
+        // The type of an empty map cannot be known from its literal
+        match (key_ty, value_ty) {
+            (Some(key), Some(value)) => CheckedType::Resolved(TypeId::map_of(&key, &value)),
+            _ => CheckedType::Unknown,
+        }
+    }
+
+    cached_type!();
+}
+
+/// Type of an instance stored in a map
+fn instance_type(instance: &ObjectInstance) -> TypeId {
+    instance
+        .ty()
+        .map_or_else(|| TypeId::from(""), |ty| TypeId::from(ty.name()))
+}
+
+/// Methods built into every map
+#[derive(Clone, Copy, PartialEq)]
+pub enum MapMethod {
+    Get,
+    Insert,
+    Remove,
+    Contains,
+    Keys,
+    Values,
+}
+
+impl MapMethod {
+    /// Get the map method with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<MapMethod> {
+        match name {
+            "get" => Some(MapMethod::Get),
+            "insert" => Some(MapMethod::Insert),
+            "remove" => Some(MapMethod::Remove),
+            "contains" => Some(MapMethod::Contains),
+            "keys" => Some(MapMethod::Keys),
+            "values" => Some(MapMethod::Values),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            MapMethod::Get => "get",
+            MapMethod::Insert => "insert",
+            MapMethod::Remove => "remove",
+            MapMethod::Contains => "contains",
+            MapMethod::Keys => "keys",
+            MapMethod::Values => "values",
+        }
+    }
+
+    /// Does the method modify the map it is called on
+    fn is_mutating(self) -> bool {
+        matches!(self, MapMethod::Insert | MapMethod::Remove)
+    }
+
+    /// Typecheck a call to the method on `map`, a map from `key_ty` to `value_ty`
+    pub fn resolve_type(
+        self,
+        map: &dyn Instruction,
+        (key_ty, value_ty): (&TypeId, &TypeId),
+        args: &mut [Box<dyn Instruction>],
+        ctx: &mut TypeCtx,
+        location: Option<ErrSpaceLocation>,
+    ) -> CheckedType {
+        let expected = match self {
+            MapMethod::Insert => vec![key_ty, value_ty],
+            MapMethod::Get | MapMethod::Remove | MapMethod::Contains => vec![key_ty],
+            MapMethod::Keys | MapMethod::Values => vec![],
+        };
+
+        if args.len() != expected.len() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "wrong number of arguments for map method `{}`: expected {}, got {}",
+                        self.name(),
+                        expected.len(),
+                        args.len()
+                    ))
+                    .with_loc(location.clone()),
+            );
+        }
+
+        if self.is_mutating() {
+            check_variable_caller(map, self.name(), ctx, location.clone());
+        }
+
+        for (idx, (arg, expected)) in args.iter_mut().zip(expected).enumerate() {
+            match arg.type_of(ctx) {
+                CheckedType::Resolved(ty) if &ty != expected => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "wrong type for argument {} in call to map method `{}`: expected `{}`, got `{}`",
+                            idx + 1,
+                            self.name(),
+                            expected,
+                            ty
+                        ))
+                        .with_loc(location.clone()),
+                ),
+                CheckedType::Void => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to use statement `{}` as argument to map method `{}`",
+                            arg.print(),
+                            self.name()
+                        ))
+                        .with_loc(location.clone()),
+                ),
+                _ => {}
+            }
+        }
+
+        match self {
+            MapMethod::Get | MapMethod::Remove => CheckedType::Resolved(option_of(value_ty)),
+            MapMethod::Insert => CheckedType::Void,
+            MapMethod::Contains => CheckedType::Resolved(TypeId::from("bool")),
+            MapMethod::Keys => CheckedType::Resolved(TypeId::array_of(key_ty)),
+            MapMethod::Values => CheckedType::Resolved(TypeId::array_of(value_ty)),
+        }
+    }
+
+    /// Call the method on `map`. Methods modifying the map modify the instance of the
+    /// variable it is stored in, without copying its entries
+    pub fn execute(
+        self,
+        map: &dyn Instruction,
+        args: &[Box<dyn Instruction>],
+        ctx: &mut Context,
+        location: Option<ErrSpaceLocation>,
+    ) -> Option<ObjectInstance> {
+        let mut values = vec![];
+        for arg in args {
+            values.push(arg.execute_expression(ctx)?);
+        }
+
+        match self {
+            MapMethod::Get => {
+                let value = {
+                    let instance = caller_instance(map, ctx)?;
+                    values
+                        .first()
+                        .and_then(|key| instance.map_entries()?.get(key).cloned())
+                };
+
+                make_option(value, ctx, location)
+            }
+            MapMethod::Contains => {
+                let instance = caller_instance(map, ctx)?;
+                let contained = values
+                    .first()
+                    .zip(instance.map_entries())
+                    .is_some_and(|(key, entries)| entries.contains(key));
+
+                Some(JkBool::from(contained).to_instance())
+            }
+            MapMethod::Keys => {
+                let instance = caller_instance(map, ctx)?;
+                let key_ty = instance
+                    .map_types()
+                    .map_or_else(|| TypeId::from(""), |(key, _)| key);
+                let keys = instance
+                    .map_entries()
+                    .map(|entries| entries.iter().map(|(key, _)| key.clone()).collect())
+                    .unwrap_or_default();
+
+                Some(ObjectInstance::array(&key_ty, keys))
+            }
+            MapMethod::Values => {
+                let instance = caller_instance(map, ctx)?;
+                let value_ty = instance
+                    .map_types()
+                    .map_or_else(|| TypeId::from(""), |(_, value)| value);
+                let values = instance
+                    .map_entries()
+                    .map(|entries| entries.iter().map(|(_, value)| value.clone()).collect())
+                    .unwrap_or_default();
+
+                Some(ObjectInstance::array(&value_ty, values))
+            }
+            MapMethod::Insert => {
+                let instance = caller_instance_mut(map, ctx, location)?;
+                let value = values.pop();
+                let key = values.pop();
+
+                if let (Some(key), Some(value)) = (key, value) {
+                    // Empty maps get the types of the first entry inserted in them
+                    let untyped = instance
+                        .map_types()
+                        .is_some_and(|(key_ty, _)| key_ty.id().is_empty());
+                    if untyped {
+                        let ty = TypeId::map_of(&instance_type(&key), &instance_type(&value));
+                        instance.set_ty(Some(TypeDec::from(ty.to_string())));
+                    }
+
+                    if let Some(entries) = instance.map_entries_mut() {
+                        entries.insert(key, value);
+                    }
+                }
+
+                None
+            }
+            MapMethod::Remove => {
+                let instance = caller_instance_mut(map, ctx, location.clone())?;
+                let removed = values
+                    .first()
+                    .zip(instance.map_entries_mut())
+                    .and_then(|(key, entries)| entries.remove(key));
+
+                make_option(removed, ctx, location)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance::MapEntries;
+    use crate::jinko_ex;
+    use crate::parser::{Construct, Parser};
+    use crate::value::JkInt;
+
+    #[test]
+    fn t_map_pretty_print() {
+        let map = Construct::instruction("[1: 'a', 2: 'b']").unwrap().1;
+        assert_eq!(map.print(), "[1: 'a', 2: 'b']");
+
+        let empty = Construct::instruction("[ : ]").unwrap().1;
+        assert_eq!(empty.print(), "[:]");
+    }
+
+    #[test]
+    fn t_map_get() {
+        let res = jinko_ex! {
+            ages = ["alice": 31, "bob": 27];
+            unwrap(ages.get("bob")) + unwrap_or(ages.get("carol"), 100)
+        };
+
+        assert_eq!(res, Some(JkInt::from(127).to_instance()));
+    }
+
+    #[test]
+    fn t_map_duplicate_keys() {
+        let res = jinko_ex! {
+            values = [1: 10, 2: 20, 1: 30];
+            keys = values.keys();
+            unwrap(values.get(1)) + keys.len()
+        };
+
+        assert_eq!(res, Some(JkInt::from(32).to_instance()));
+    }
+
+    #[test]
+    fn t_map_insert_and_remove() {
+        let res = jinko_ex! {
+            mut ages = ["alice": 31];
+            ages.insert("bob", 27);
+            ages.insert("alice", 32);
+            removed = unwrap(ages.remove("bob"));
+            values = ages.values();
+            removed + unwrap(ages.get("alice")) + values.len()
+        };
+
+        assert_eq!(res, Some(JkInt::from(60).to_instance()));
+    }
+
+    #[test]
+    fn t_map_insert_in_empty() {
+        let res = jinko_ex! {
+            mut counts = [:];
+            counts.insert('a', 1);
+            counts.contains('a')
+        };
+
+        assert_eq!(res, Some(JkBool::from(true).to_instance()));
+    }
+
+    #[test]
+    fn t_map_keys_hashed_by_type() {
+        let res = jinko_ex! {
+            type Id(value: int);
+            ids = [Id { value = 1 }: "one"];
+            is_some(ids.get(Id { value = 1 }))
+        };
+
+        assert_eq!(res, Some(JkBool::from(true).to_instance()));
+
+        let int_key = JkInt::from(15).to_instance();
+        let mut float_key = int_key.clone();
+        float_key.set_ty(Some(TypeDec::from("float")));
+
+        let entries: MapEntries = vec![(int_key, JkInt::from(1).to_instance())]
+            .into_iter()
+            .collect();
+
+        assert!(!entries.contains(&float_key));
+    }
+
+    #[test]
+    fn t_map_keys_hashed_by_field() {
+        let res = jinko_ex! {
+            type P(a: string, b: string);
+            mut pairs = [P { a = "ab", b = "c" }: 1];
+            pairs.insert(P { a = "a", b = "bc" }, 2);
+            keys = pairs.keys();
+            keys.len()
+        };
+
+        assert_eq!(res, Some(JkInt::from(2).to_instance()));
+    }
+
+    #[test]
+    fn t_map_remove_immutable() {
+        let mut ctx = Parser::parse("ages = [\"alice\": 31]; ages.remove(\"alice\");", None);
+
+        assert!(ctx.execute().is_err());
+    }
+}
@@ -1,20 +1,174 @@
 //! A method like call is syntactic sugar over regular function calls. During executions,
 //! they get desugared into a normal function call.
+//! Calls to the built-in methods of arrays and maps, such as `values.push(4)`, are not
+//! desugared and are handled by these methods instead.
 
-use crate::instruction::FunctionCall;
-use crate::{Context, InstrKind, Instruction, ObjectInstance};
+use std::borrow::Cow;
+
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{ArrayMethod, FunctionCall, MapMethod, TypeId, Var};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
 
 #[derive(Clone)]
 pub struct MethodCall {
     var: Box<dyn Instruction>,
     method: FunctionCall,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl MethodCall {
     /// Create a new MethodCall from a variable and an associated function
     pub fn new(var: Box<dyn Instruction>, method: FunctionCall) -> MethodCall {
-        MethodCall { var, method }
+        MethodCall {
+            var,
+            method,
+            cached_type: None,
+            location: None,
+        }
+    }
+
+    /// Is the caller an array or a map, as checked by `is_instance` on its instance or
+    /// `is_type` on its type. Variables are looked up directly, since their types are
+    /// not always known to the typechecker
+    fn caller_is(
+        &self,
+        ctx: &Context,
+        is_instance: fn(&ObjectInstance) -> bool,
+        is_type: fn(&TypeId) -> bool,
+    ) -> bool {
+        match self.var.downcast_ref::<Var>() {
+            Some(var) => ctx
+                .get_variable(var.name())
+                .is_some_and(|var| is_instance(var.instance_ref())),
+            None => {
+                matches!(self.var.cached_type(), Some(CheckedType::Resolved(ty)) if is_type(ty))
+            }
+        }
+    }
+
+    /// Get the array method called, if the caller is an array
+    fn array_method(&self, ctx: &Context) -> Option<ArrayMethod> {
+        ArrayMethod::from_name(self.method.name()).filter(|_| {
+            self.caller_is(ctx, ObjectInstance::is_array, |ty| {
+                ty.element_type().is_some()
+            })
+        })
+    }
+
+    /// Get the map method called, if the caller is a map
+    fn map_method(&self, ctx: &Context) -> Option<MapMethod> {
+        MapMethod::from_name(self.method.name())
+            .filter(|_| self.caller_is(ctx, ObjectInstance::is_map, TypeId::is_map))
+    }
+
+    /// Is the method one of the built-in methods of arrays or maps
+    fn is_builtin_method(&self) -> bool {
+        ArrayMethod::from_name(self.method.name()).is_some()
+            || MapMethod::from_name(self.method.name()).is_some()
+    }
+}
+
+/// Name of the stdlib's `Option` enum, returned by built-in methods which may not
+/// have a value to give back
+const OPTION: &str = "Option";
+
+/// Type of the options containing values of type `ty`
+pub(crate) fn option_of(ty: &TypeId) -> TypeId {
+    TypeId::with_generics(String::from(OPTION), vec![ty.clone()])
+}
+
+/// Create a `Some` instance containing `value`, or a `None` instance if there is no
+/// value
+pub(crate) fn make_option(
+    value: Option<ObjectInstance>,
+    ctx: &mut Context,
+    location: Option<ErrSpaceLocation>,
+) -> Option<ObjectInstance> {
+    let option = match ctx.get_type(&TypeId::from(OPTION)) {
+        Some(option) => (**option).clone(),
+        None => {
+            ctx.error(
+                Error::new(ErrKind::Context)
+                    .with_msg(format!("type not found: {}", OPTION))
+                    .with_loc(location),
+            );
+            return None;
+        }
+    };
+
+    Some(match value {
+        Some(value) => {
+            ObjectInstance::enum_variant(option, "Some", vec![(String::from("value"), value)])
+        }
+        None => ObjectInstance::enum_variant(option, "None", vec![]),
+    })
+}
+
+/// Check that a built-in method modifying its caller, such as `values.push(4)`, is
+/// called on a variable
+pub(crate) fn check_variable_caller(
+    caller: &dyn Instruction,
+    method: &str,
+    ctx: &mut TypeCtx,
+    location: Option<ErrSpaceLocation>,
+) {
+    if caller.downcast_ref::<Var>().is_none() {
+        ctx.error(
+            Error::new(ErrKind::TypeChecker)
+                .with_msg(format!(
+                    "method `{}` can only be called on a variable, not on `{}`",
+                    method,
+                    caller.print()
+                ))
+                .with_loc(location),
+        );
+    }
+}
+
+/// Get the instance a built-in method is called on. The instance of a variable is
+/// borrowed instead of copied, so that methods like `len` do not copy whole arrays
+pub(crate) fn caller_instance<'ctx>(
+    caller: &dyn Instruction,
+    ctx: &'ctx mut Context,
+) -> Option<Cow<'ctx, ObjectInstance>> {
+    if let Some(name) = caller.downcast_ref::<Var>().map(Var::name) {
+        if ctx.get_variable(name).is_some() {
+            return ctx
+                .get_variable(name)
+                .map(|var| Cow::Borrowed(var.instance_ref()));
+        }
+    }
+
+    caller.execute_expression(ctx).map(Cow::Owned)
+}
+
+/// Get the instance of the variable a built-in method modifying its caller was called
+/// on, so that it can be modified in place. The variable has to be mutable
+pub(crate) fn caller_instance_mut<'ctx>(
+    caller: &dyn Instruction,
+    ctx: &'ctx mut Context,
+    location: Option<ErrSpaceLocation>,
+) -> Option<&'ctx mut ObjectInstance> {
+    let name = caller.downcast_ref::<Var>().map(Var::name);
+
+    match name.and_then(|name| ctx.get_variable(name)) {
+        Some(var) if var.mutable() => {}
+        _ => {
+            ctx.error(
+                Error::new(ErrKind::Context)
+                    .with_msg(format!(
+                        "trying to modify non mutable variable `{}`",
+                        name.map_or_else(|| caller.print(), String::from)
+                    ))
+                    .with_loc(location),
+            );
+            return None;
+        }
     }
+
+    ctx.get_variable_mut(name?).map(Var::instance_mut)
 }
 
 impl Instruction for MethodCall {
@@ -30,10 +184,35 @@ impl Instruction for MethodCall {
     fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
         ctx.debug("METHOD CALL ENTER", &self.print());
 
+        if let Some(method) = self.array_method(ctx) {
+            ctx.debug("METHOD CALL EXIT", &self.print());
+
+            return method.execute(
+                self.var.as_ref(),
+                self.method.args(),
+                ctx,
+                self.location.clone(),
+            );
+        }
+
+        if let Some(method) = self.map_method(ctx) {
+            ctx.debug("METHOD CALL EXIT", &self.print());
+
+            return method.execute(
+                self.var.as_ref(),
+                self.method.args(),
+                ctx,
+                self.location.clone(),
+            );
+        }
+
         // FIXME: No clone here
         let mut call = self.method.clone();
 
         call.add_arg_front(self.var.clone());
+        if let Some(loc) = &self.location {
+            call.set_location(loc.clone());
+        }
 
         ctx.debug("DESUGARING TO", &call.print());
 
@@ -41,6 +220,69 @@ impl Instruction for MethodCall {
 
         call.execute(ctx)
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for MethodCall {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let caller_ty = self.var.type_of(ctx);
+
+        let name = self.method.name().to_string();
+
+        match &caller_ty {
+            CheckedType::Resolved(ty) => {
+                if let (Some(method), Some(element_ty)) =
+                    (ArrayMethod::from_name(&name), ty.element_type())
+                {
+                    return method.resolve_type(
+                        self.var.as_ref(),
+                        &element_ty,
+                        self.method.args_mut(),
+                        ctx,
+                        self.location.clone(),
+                    );
+                }
+
+                if let (Some(method), Some(types)) = (MapMethod::from_name(&name), ty.map_types()) {
+                    return method.resolve_type(
+                        self.var.as_ref(),
+                        types,
+                        self.method.args_mut(),
+                        ctx,
+                        self.location.clone(),
+                    );
+                }
+            }
+            // Empty arrays and maps have an unknown type, but can still be modified. Their
+            // methods take precedence over functions with the same name, such as the
+            // stdlib's `contains`
+            CheckedType::Unknown if self.is_builtin_method() => {
+                self.method.args_mut().iter_mut().for_each(|arg| {
+                    arg.type_of(ctx);
+                });
+
+                return CheckedType::Unknown;
+            }
+            _ => {}
+        }
+
+        // Typecheck the desugared function call in place, so that the types of the
+        // arguments are recorded on them
+        self.method.add_arg_front(self.var.clone());
+        let ty = self.method.type_of(ctx);
+        self.var = self.method.remove_arg_front();
+
+        ty
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
@@ -75,4 +317,25 @@ mod tests {
 
         assert_eq!(mc.execute(&mut ctx).unwrap(), JkInt::from(1).to_instance());
     }
+
+    #[test]
+    fn t_arg_types_are_recorded() {
+        let mut ctx = Parser::parse(
+            "func first(a: int, b: float) -> int { a } x = 1; x.first(1.5 + 1.0)",
+            None,
+        );
+        ctx.type_check();
+
+        let last = ctx.entry_point.block().unwrap().last().unwrap();
+        let mc = last.downcast_ref::<MethodCall>().unwrap();
+
+        assert_eq!(
+            mc.method.args()[0].cached_type(),
+            Some(&CheckedType::Resolved(TypeId::from("float")))
+        );
+        assert_eq!(
+            mc.var.cached_type(),
+            Some(&CheckedType::Resolved(TypeId::from("int")))
+        );
+    }
 }
@@ -2,51 +2,77 @@
 //! When using nested instructions, such as `foo = bar();`, you're actually using
 //! two instructions: A function call expression, and a variable assignment statement
 
-use crate::{Context, ErrKind, Error, ObjectInstance};
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::TypeCheck;
+use crate::{Context, ErrKind, Error, FromObjectInstance, JkBool, ObjectInstance};
 
 use colored::Colorize;
 use downcast_rs::{impl_downcast, Downcast};
 
+mod array;
 mod binary_op;
 mod block;
 mod dec_arg;
 mod extra_content;
 mod field_access;
+mod format_string;
 mod function_call;
 mod function_declaration;
 mod if_else;
 mod incl;
+mod index;
+mod index_assignment;
+mod jk_break;
+mod jk_continue;
 mod jk_inst;
+mod jk_match;
 mod jk_return;
+mod jk_try;
 mod loop_block;
+mod map;
 mod method_call;
 mod operator;
 mod rename;
 mod type_declaration;
 mod type_id;
 mod type_instantiation;
+mod type_param;
+mod unary_op;
 mod var;
 mod var_assignment;
+mod variant;
 
+pub use array::{Array, ArrayMethod};
 pub use binary_op::BinaryOp;
 pub use block::Block;
 pub use dec_arg::DecArg;
 pub use extra_content::{CommentKind, ExtraContent, ExtraKind};
 pub use field_access::FieldAccess;
+pub use format_string::{FormatPart, FormatString};
 pub use function_call::FunctionCall;
 pub use function_declaration::{FunctionDec, FunctionKind};
 pub use if_else::IfElse;
 pub use incl::Incl;
+pub use index::Index;
+pub use index_assignment::IndexAssign;
+pub use jk_break::Break;
+pub use jk_continue::Continue;
 pub use jk_inst::{JkInst, JkInstKind};
+pub use jk_match::{Match, MatchArm};
 pub use jk_return::Return;
+pub use jk_try::Try;
 pub use loop_block::{Loop, LoopKind};
+pub use map::{Map, MapEntry, MapMethod};
 pub use method_call::MethodCall;
 pub use operator::Operator;
 pub use type_declaration::TypeDec;
 pub use type_id::{TypeId, PRIMITIVE_TYPES};
 pub use type_instantiation::TypeInstantiation;
+pub use type_param::TypeParam;
+pub use unary_op::UnaryOp;
 pub use var::Var;
 pub use var_assignment::VarAssign;
+pub use variant::Variant;
 
 /// The type of instructions available. An Instruction either is a statement, or an
 /// expression. An expression contains an instance of a result. For example,
@@ -56,14 +82,14 @@ pub use var_assignment::VarAssign;
 #[derive(Debug, PartialEq, Clone)]
 pub enum InstrKind {
     Statement,
-    Expression(Option<ObjectInstance>),
+    Expression(Option<Box<ObjectInstance>>),
 }
 
 // FIXME: Fix documentation for execute_*()
 
 /// The `Instruction` trait is the basic trait for all of Jinko's execution nodes. Each
 /// node that can be executed needs to implement it
-pub trait Instruction: InstructionClone + Downcast {
+pub trait Instruction: InstructionClone + Downcast + TypeCheck {
     // FIXME: Add Rename here
     /// Execute the instruction, altering the state of the context. Executing
     /// this method may return an object instance
@@ -76,17 +102,23 @@ pub trait Instruction: InstructionClone + Downcast {
     }
 
     /// Execute the instruction, hoping for an instance to be returned. If no instance is
-    /// returned, error out.
+    /// returned, error out. Expressions interrupted by a control flow signal, such as
+    /// `opt?`, do not return an instance and are not an error.
     fn execute_expression(&self, ctx: &mut Context) -> Option<ObjectInstance> {
         let instance = self.execute(ctx);
 
         match instance {
             Some(obj) => Some(obj),
+            None if ctx.control_flow().is_some() => None,
             None => {
-                ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                    "statement found when expression was expected: {}",
-                    self.print()
-                )));
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "statement found when expression was expected: {}",
+                            self.print()
+                        ))
+                        .with_loc(self.location().cloned()),
+                );
                 None
             }
         }
@@ -101,10 +133,12 @@ pub trait Instruction: InstructionClone + Downcast {
         match instance {
             None => Ok(()),
             Some(_) => {
-                let e = Error::new(ErrKind::Context).with_msg(format!(
-                    "expression found when statement was expected: {}",
-                    self.print()
-                ));
+                let e = Error::new(ErrKind::Context)
+                    .with_msg(format!(
+                        "expression found when statement was expected: {}",
+                        self.print()
+                    ))
+                    .with_loc(self.location().cloned());
                 ctx.error(e.clone());
                 Err(e)
             }
@@ -112,15 +146,23 @@ pub trait Instruction: InstructionClone + Downcast {
     }
 
     /// Maybe execute the instruction, transforming it in a Rust bool if possible. It is
-    /// only possible to execute as_bool on boolean variables, boolean constants, blocks
-    /// returning a boolean and functions returning a boolean.
+    /// only possible to execute as_bool on instructions returning a boolean. Since
+    /// conditions are typechecked, the type of the returned instance can be trusted.
     fn as_bool(&self, ctx: &mut Context) -> Option<bool> {
-        ctx.error(
-            Error::new(ErrKind::Context)
-                .with_msg(format!("cannot be used as a boolean: {}", self.print())),
-        );
+        let instance = self.execute_expression(ctx)?;
 
-        None
+        match instance.ty().map(TypeDec::name) {
+            Some("bool") => Some(JkBool::from_instance(&instance).0),
+            _ => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!("cannot be used as a boolean: {}", self.print()))
+                        .with_loc(self.location().cloned()),
+                );
+
+                None
+            }
+        }
     }
 
     /// What is the type of the instruction: a Statement or an Expression.
@@ -131,6 +173,16 @@ pub trait Instruction: InstructionClone + Downcast {
 
     /// Pretty-print the instruction to valid jinko code
     fn print(&self) -> String;
+
+    /// Location of the instruction in its source file, if it was parsed from one.
+    /// Instructions which cannot be responsible for an error, such as constants, do
+    /// not keep their location
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        None
+    }
+
+    /// Record the location of the instruction in its source file
+    fn set_location(&mut self, _loc: ErrSpaceLocation) {}
 }
 
 impl_downcast!(Instruction);
@@ -1,7 +1,7 @@
-//! Operators used by jinko's BinaryOp struct. This module is not public, and is only
-//! used by the BinaryOp structure.
+//! Operators used by jinko's BinaryOp and UnaryOp structs. This module is not public,
+//! and is only used by these structures.
 
-/// All the binary operators available
+/// All the binary and unary operators available
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operator {
@@ -9,18 +9,40 @@ pub enum Operator {
     Sub,
     Mul,
     Div,
+    Mod,
+    Equals,
+    NotEquals,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    Not,
+    Neg,
     LeftParenthesis,
     RightParenthesis,
 }
 
 impl Operator {
-    /// Create a new operator from a given character
+    /// Create a new operator from a given character. A `-` always creates a
+    /// substraction: Whether it is a negation instead depends on its position
     pub fn new(op_str: &str) -> Operator {
         match op_str {
             "+" => Operator::Add,
             "-" => Operator::Sub,
             "*" => Operator::Mul,
             "/" => Operator::Div,
+            "%" => Operator::Mod,
+            "==" => Operator::Equals,
+            "!=" => Operator::NotEquals,
+            "<" => Operator::Lt,
+            "<=" => Operator::LtEq,
+            ">" => Operator::Gt,
+            ">=" => Operator::GtEq,
+            "&&" => Operator::And,
+            "||" => Operator::Or,
+            "!" => Operator::Not,
             "(" => Operator::LeftParenthesis,
             ")" => Operator::RightParenthesis,
             _ => unreachable!("Invalid operator: {}", op_str),
@@ -34,6 +56,17 @@ impl Operator {
             Operator::Sub => "-",
             Operator::Mul => "*",
             Operator::Div => "/",
+            Operator::Mod => "%",
+            Operator::Equals => "==",
+            Operator::NotEquals => "!=",
+            Operator::Lt => "<",
+            Operator::LtEq => "<=",
+            Operator::Gt => ">",
+            Operator::GtEq => ">=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Not => "!",
+            Operator::Neg => "-",
             Operator::LeftParenthesis => "(",
             Operator::RightParenthesis => ")",
         }
@@ -42,9 +75,16 @@ impl Operator {
     /// Return the operator's precedence according to the Shunting Yard algorithm
     pub fn precedence(&self) -> u8 {
         match self {
+            // Unary operators bind tighter than any binary operator
+            Operator::Not | Operator::Neg => 7,
+
             // Classic SY operator precedence
-            Operator::Mul | Operator::Div => 3,
-            Operator::Add | Operator::Sub => 2,
+            Operator::Mul | Operator::Div | Operator::Mod => 6,
+            Operator::Add | Operator::Sub => 5,
+            Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => 4,
+            Operator::Equals | Operator::NotEquals => 3,
+            Operator::And => 2,
+            Operator::Or => 1,
 
             // Special operators. They don't really have a precedence value, and it's
             // never used
@@ -52,6 +92,29 @@ impl Operator {
         }
     }
 
+    /// Is the operator a comparison operator, returning a boolean
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::Equals
+                | Operator::NotEquals
+                | Operator::Lt
+                | Operator::LtEq
+                | Operator::Gt
+                | Operator::GtEq
+        )
+    }
+
+    /// Is the operator a short-circuiting logical operator
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Operator::And | Operator::Or)
+    }
+
+    /// Is the operator a unary one, applied to a single operand
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Operator::Not | Operator::Neg)
+    }
+
     /// Is the operator a left associative one
     pub fn is_left_associative(&self) -> bool {
         // FIXME: Not entirely true
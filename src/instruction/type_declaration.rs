@@ -1,17 +1,50 @@
-use super::{DecArg, InstrKind, Instruction};
+use super::{DecArg, InstrKind, Instruction, TypeParam, Variant};
 
-use crate::{Context, ObjectInstance};
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ErrKind, Error, ObjectInstance};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct TypeDec {
     name: String,
+    generics: Vec<TypeParam>,
     fields: Vec<DecArg>,
+    variants: Vec<Variant>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl TypeDec {
     /// Create a new type
     pub fn new(name: String, fields: Vec<DecArg>) -> TypeDec {
-        TypeDec { name, fields }
+        TypeDec {
+            name,
+            generics: vec![],
+            fields,
+            variants: vec![],
+            location: None,
+        }
+    }
+
+    /// Create a new enum, whose instances are one of its variants
+    pub fn new_enum(name: String, variants: Vec<Variant>) -> TypeDec {
+        TypeDec {
+            name,
+            generics: vec![],
+            fields: vec![],
+            variants,
+            location: None,
+        }
+    }
+
+    /// Set the type parameters of a generic type. This is done after creating the type,
+    /// since most types are not generic
+    pub fn set_generics(&mut self, generics: Vec<TypeParam>) {
+        self.generics = generics
+    }
+
+    /// Get a reference to the type's type parameters
+    pub fn generics(&self) -> &Vec<TypeParam> {
+        &self.generics
     }
 
     /// Get a reference to the name of the type
@@ -23,6 +56,32 @@ impl TypeDec {
     pub fn fields(&self) -> &Vec<DecArg> {
         &self.fields
     }
+
+    /// Get a reference to the variants of an enum
+    pub fn variants(&self) -> &Vec<Variant> {
+        &self.variants
+    }
+
+    /// Get one of the variants of an enum by its name
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|variant| variant.name() == name)
+    }
+
+    /// Is the type an enum
+    pub fn is_enum(&self) -> bool {
+        !self.variants.is_empty()
+    }
+}
+
+/// Two types are the same if they have the same name, type parameters, fields and
+/// variants, regardless of where they were declared
+impl PartialEq for TypeDec {
+    fn eq(&self, other: &TypeDec) -> bool {
+        self.name == other.name
+            && self.generics == other.generics
+            && self.fields == other.fields
+            && self.variants == other.variants
+    }
 }
 
 impl Instruction for TypeDec {
@@ -46,11 +105,27 @@ impl Instruction for TypeDec {
 
     // FIXME: Really unefficient
     fn print(&self) -> String {
-        let mut base = format!("type {} (", self.name);
+        let keyword = match self.is_enum() {
+            true => "enum",
+            false => "type",
+        };
+        let mut base = format!("{} {}", keyword, self.name);
+
+        if !self.generics.is_empty() {
+            let generics: Vec<String> = self.generics.iter().map(TypeParam::to_string).collect();
+            base.push_str(&format!("<{}>", generics.join(", ")));
+        }
+
+        if self.is_enum() {
+            let variants: Vec<String> = self.variants.iter().map(Variant::to_string).collect();
+            return format!("{} {{ {} }}", base, variants.join(", "));
+        }
+
+        base.push_str(" (");
 
         base.push_str(
             self.fields
-                .get(0)
+                .first()
                 .map_or(String::new(), |f| format!("{}", f))
                 .as_str(),
         );
@@ -61,6 +136,14 @@ impl Instruction for TypeDec {
             .for_each(|field| base.push_str(format!(", {}", field).as_str()));
         format!("{});", base)
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
 }
 
 impl From<&str> for TypeDec {
@@ -79,7 +162,10 @@ impl From<String> for TypeDec {
     fn from(type_name: String) -> TypeDec {
         TypeDec {
             name: type_name,
+            generics: vec![],
             fields: vec![],
+            variants: vec![],
+            location: None,
         }
     }
 }
@@ -89,3 +175,48 @@ impl std::fmt::Display for TypeDec {
         write!(f, "{}", self.name)
     }
 }
+
+impl TypeCheck for TypeDec {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        // Declare the type first, so that it can contain fields of its own type
+        ctx.declare_type(self.clone());
+
+        // The type parameters are only visible in the declaration
+        ctx.scope_enter();
+        ctx.declare_type_params(&self.generics);
+
+        for default in self.generics.iter().filter_map(TypeParam::default) {
+            ctx.check_type_exists(default);
+        }
+
+        for field in &self.fields {
+            ctx.check_arg_type_exists(field);
+        }
+
+        for (idx, variant) in self.variants.iter().enumerate() {
+            if self.variants[..idx]
+                .iter()
+                .any(|previous| previous.name() == variant.name())
+            {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "variant `{}` is declared more than once in enum `{}`",
+                            variant.name(),
+                            self.name
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+            }
+
+            for field in variant.fields() {
+                ctx.check_arg_type_exists(field);
+            }
+        }
+
+        ctx.scope_exit();
+
+        // Declaring a type is always a statement (for now)
+        CheckedType::Void
+    }
+}
@@ -1,29 +1,283 @@
 //! A TypeId refers to a type's identifier. For example, the TypeId of `int` is "int".
-//! The TypeId of `type Custom(a: int, b: OtherCustom)` is `Custom`.
+//! The TypeId of `type Custom(a: int, b: OtherCustom)` is `Custom`. Generic types
+//! carry the types they are instantiated with: The TypeId of `Pair<int, float>` is
+//! `Pair`, with `int` and `float` as generic arguments. The types of functions, such as
+//! `func(int, char) -> string`, keep the types of their arguments followed by their
+//! return type as generic arguments.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result};
 
 pub const PRIMITIVE_TYPES: [&str; 5] = ["bool", "int", "float", "char", "string"];
 
+/// Identifier of array types, whose only generic argument is the type of their elements
+const ARRAY_ID: &str = "[]";
+
+/// Identifier of function types
+const FUNCTION_ID: &str = "func";
+
+/// Identifier of map types, whose generic arguments are the types of their keys and
+/// values
+const MAP_ID: &str = "Map";
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct TypeId {
     id: String,
+    generics: Vec<TypeId>,
 }
 
 impl TypeId {
     pub fn new(id: String) -> TypeId {
-        TypeId { id }
+        TypeId {
+            id,
+            generics: vec![],
+        }
+    }
+
+    /// Create the identifier of a generic type instantiated with the given types
+    pub fn with_generics(id: String, generics: Vec<TypeId>) -> TypeId {
+        TypeId { id, generics }
     }
 
     pub fn id(&self) -> &str {
         &self.id
     }
 
+    /// Return a reference to the types the type is instantiated with
+    pub fn generics(&self) -> &Vec<TypeId> {
+        &self.generics
+    }
+
     pub fn is_primitive(&self) -> bool {
         PRIMITIVE_TYPES.contains(&self.id.as_str())
     }
+
+    /// Create the type of the arrays containing elements of the given type, such as
+    /// `[int]`
+    pub fn array_of(element: &TypeId) -> TypeId {
+        TypeId::with_generics(String::from(ARRAY_ID), vec![element.clone()])
+    }
+
+    /// Type of the elements of an array type, if the type is an array type
+    pub fn element_type(&self) -> Option<TypeId> {
+        match self.id == ARRAY_ID {
+            true => self.generics.first().cloned(),
+            false => None,
+        }
+    }
+
+    /// Create the type of the maps associating keys of type `key` to values of type
+    /// `value`, such as `Map<string, int>`
+    pub fn map_of(key: &TypeId, value: &TypeId) -> TypeId {
+        TypeId::with_generics(String::from(MAP_ID), vec![key.clone(), value.clone()])
+    }
+
+    /// Is the type a map type, regardless of its generic arguments
+    pub fn is_map(&self) -> bool {
+        self.id == MAP_ID
+    }
+
+    /// Types of the keys and values of a map type, if the type is a map type
+    pub fn map_types(&self) -> Option<(&TypeId, &TypeId)> {
+        match (self.is_map(), self.generics.as_slice()) {
+            (true, [key, value]) => Some((key, value)),
+            _ => None,
+        }
+    }
+
+    /// Create the type of the functions taking arguments of the given types and
+    /// returning an instance of type `ret`, such as `func(int) -> bool`
+    pub fn function(args: Vec<TypeId>, ret: TypeId) -> TypeId {
+        let mut generics = args;
+        generics.push(ret);
+
+        TypeId::with_generics(String::from(FUNCTION_ID), generics)
+    }
+
+    /// Types of the arguments and return type of a function type, if the type is a
+    /// function type
+    pub fn signature(&self) -> Option<(&[TypeId], &TypeId)> {
+        match self.id == FUNCTION_ID {
+            true => self.generics.split_last().map(|(ret, args)| (args, ret)),
+            false => None,
+        }
+    }
+
+    /// Replace the type parameters contained in the type with the types they are bound
+    /// to. `Pair<T, int>` becomes `Pair<float, int>` if `T` is bound to `float`
+    pub fn substitute(&self, bindings: &HashMap<String, TypeId>) -> TypeId {
+        match bindings.get(&self.id) {
+            Some(ty) if self.generics.is_empty() => ty.clone(),
+            _ => TypeId::with_generics(
+                self.id.clone(),
+                self.generics
+                    .iter()
+                    .map(|generic| generic.substitute(bindings))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Parse a type written in jinko's syntax, such as `Pair<int, [float]>`
+    fn parse(s: &str) -> Option<(TypeId, &str)> {
+        let s = s.trim_start();
+
+        if let Some(rest) = s.strip_prefix('[') {
+            let (element, rest) = TypeId::parse(rest)?;
+            let rest = rest.trim_start().strip_prefix(']')?;
+
+            return Some((TypeId::array_of(&element), rest));
+        }
+
+        if let Some(mut list) = s
+            .strip_prefix(FUNCTION_ID)
+            .and_then(|rest| rest.trim_start().strip_prefix('('))
+        {
+            let mut args = vec![];
+
+            if let Some(rest) = list.trim_start().strip_prefix(')') {
+                list = rest;
+            } else {
+                loop {
+                    let (arg, next) = TypeId::parse(list)?;
+                    args.push(arg);
+
+                    let next = next.trim_start();
+                    match next.strip_prefix(',') {
+                        Some(next) => list = next,
+                        None => {
+                            list = next.strip_prefix(')')?;
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let rest = list.trim_start().strip_prefix("->")?;
+            let (ret, rest) = TypeId::parse(rest)?;
+
+            return Some((TypeId::function(args, ret), rest));
+        }
+
+        let end = s
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(s.len());
+        let (id, mut rest) = s.split_at(end);
+        let mut generics = vec![];
+
+        if let Some(mut list) = rest.trim_start().strip_prefix('<') {
+            loop {
+                let (generic, next) = TypeId::parse(list)?;
+                generics.push(generic);
+
+                let next = next.trim_start();
+                match next.strip_prefix(',') {
+                    Some(next) => list = next,
+                    None => {
+                        rest = next.strip_prefix('>')?;
+                        break;
+                    }
+                }
+            }
+        }
+
+        Some((TypeId::with_generics(id.to_string(), generics), rest))
+    }
 }
 
+impl Display for TypeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(element) = self.element_type() {
+            return write!(f, "[{}]", element);
+        }
+
+        if let Some((args, ret)) = self.signature() {
+            let args: Vec<String> = args.iter().map(TypeId::to_string).collect();
+            return write!(f, "func({}) -> {}", args.join(", "), ret);
+        }
+
+        write!(f, "{}", self.id)?;
+
+        if let Some((first, rest)) = self.generics.split_first() {
+            write!(f, "<{}", first)?;
+            rest.iter()
+                .try_for_each(|generic| write!(f, ", {}", generic))?;
+            write!(f, ">")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a TypeId from its representation, such as `Pair<int, float>`. Invalid
+/// representations are kept as they are, as the identifier of a type with no generics
 impl From<&str> for TypeId {
     fn from(s: &str) -> Self {
-        TypeId::new(s.to_string())
+        match TypeId::parse(s) {
+            Some((ty, "")) => ty,
+            _ => TypeId::new(s.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_display_round_trips() {
+        for repr in [
+            "int",
+            "Pair<int, float>",
+            "[Pair<[int], Node<char>>]",
+            "ns::Ty<T>",
+            "func() -> int",
+            "func(Pair<T>, [char]) -> func(int) -> bool",
+            "Map<string, [int]>",
+        ] {
+            assert_eq!(TypeId::from(repr).to_string(), repr);
+        }
+    }
+
+    #[test]
+    fn t_parse_generics() {
+        let ty = TypeId::from("Pair< int ,[float]>");
+
+        assert_eq!(ty.id(), "Pair");
+        assert_eq!(
+            ty.generics(),
+            &vec![
+                TypeId::from("int"),
+                TypeId::array_of(&TypeId::from("float"))
+            ]
+        );
+    }
+
+    #[test]
+    fn t_map_types() {
+        let ty = TypeId::map_of(&TypeId::from("char"), &TypeId::from("[int]"));
+
+        assert_eq!(ty, TypeId::from("Map<char, [int]>"));
+        assert_eq!(
+            ty.map_types(),
+            Some((&TypeId::from("char"), &TypeId::from("[int]")))
+        );
+        assert!(TypeId::from("Map<int>").is_map());
+        assert!(TypeId::from("Map<int>").map_types().is_none());
+    }
+
+    #[test]
+    fn t_substitute() {
+        let bindings: HashMap<String, TypeId> = vec![
+            (String::from("T"), TypeId::from("int")),
+            (String::from("U"), TypeId::from("Pair<char>")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            TypeId::from("Node<T, [U]>").substitute(&bindings),
+            TypeId::from("Node<int, [Pair<char>]>")
+        );
     }
 }
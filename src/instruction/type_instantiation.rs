@@ -1,10 +1,15 @@
 //! TypeInstantiations are used when instantiating a type. The argument list is given to the
-//! type on execution.
+//! type on execution. The variants of enums are instantiated the same way, using the
+//! name of the variant: `Circle { radius = 2.0 }`.
 
 use super::{
-    Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance, TypeDec, TypeId, VarAssign,
+    Context, DecArg, ErrKind, Error, InstrKind, Instruction, ObjectInstance, TypeDec, TypeId,
+    VarAssign, Variant,
 };
+use crate::error::ErrSpaceLocation;
 use crate::instance::Name;
+use crate::typechecker::generics::{self, Bindings};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
 
 use std::rc::Rc;
 
@@ -12,6 +17,8 @@ use std::rc::Rc;
 pub struct TypeInstantiation {
     type_name: TypeId,
     fields: Vec<VarAssign>,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl TypeInstantiation {
@@ -20,6 +27,8 @@ impl TypeInstantiation {
         TypeInstantiation {
             type_name,
             fields: Vec::new(),
+            cached_type: None,
+            location: None,
         }
     }
 
@@ -38,33 +47,73 @@ impl TypeInstantiation {
         &self.fields
     }
 
-    /// Get the corresponding type declaration from a context
-    fn get_declaration(&self, ctx: &mut Context) -> Option<Rc<TypeDec>> {
+    /// Get the corresponding type declaration from a context. When instantiating the
+    /// variant of an enum, the declaration of the enum is returned along with the variant
+    fn get_declaration(&self, ctx: &mut Context) -> Option<(Rc<TypeDec>, Option<Variant>)> {
+        let variant_enum = ctx.get_variant(self.name().id()).cloned();
+
         match ctx.get_type(self.name()) {
             // get_type() return a Rc, so this clones the Rc, not the TypeId
-            Some(t) => Some(t.clone()),
-            // FIXME: Fix Location and input
+            Some(t) => Some((t.clone(), None)),
+            None if variant_enum.is_some() => variant_enum.map(|t| {
+                let variant = t.variant(self.name().id()).cloned();
+                (t, variant)
+            }),
             None => {
                 ctx.error(
                     Error::new(ErrKind::Context)
-                        .with_msg(format!("Cannot find type {}", self.name().id())),
+                        .with_msg(format!("Cannot find type {}", self.name().id()))
+                        .with_loc(self.location.clone()),
                 );
                 None
             }
         }
     }
 
+    /// Bind the type parameters of the instantiated type to the types given explicitly,
+    /// or to the types inferred from the values of the fields. Inferred bindings might
+    /// not contain all the type parameters
+    fn bind_generics(
+        &self,
+        type_dec: &TypeDec,
+        fields: &[DecArg],
+        value_types: &[CheckedType],
+        ctx: &TypeCtx,
+    ) -> Result<Bindings, Error> {
+        let params = type_dec.generics();
+
+        if !self.type_name.generics().is_empty() {
+            return generics::bind(self.type_name.id(), params, self.type_name.generics());
+        }
+
+        let mut bindings = Bindings::new();
+        for (named_arg, value_ty) in self.fields.iter().zip(value_types) {
+            let field = fields
+                .iter()
+                .find(|field| field.name() == named_arg.symbol());
+
+            if let (Some(field), CheckedType::Resolved(ty)) = (field, value_ty) {
+                let declared = ctx.normalize(field.get_type());
+                generics::infer(params, &declared, ty, &mut bindings);
+            }
+        }
+
+        Ok(bindings)
+    }
+
     /// Check if the fields received and the fields expected match
-    fn check_fields_count(&self, type_dec: &TypeDec) -> Result<(), Error> {
-        match self.fields().len() == type_dec.fields().len() {
+    fn check_fields_count(&self, fields: &[DecArg]) -> Result<(), Error> {
+        match self.fields().len() == fields.len() {
             true => Ok(()),
-            false => Err(Error::new(ErrKind::Context).with_msg(format!(
-                "Wrong number of arguments \
+            false => Err(Error::new(ErrKind::Context)
+                .with_msg(format!(
+                    "Wrong number of arguments \
                     for type instantiation `{}`: Expected {}, got {}",
-                self.name().id(),
-                type_dec.fields().len(),
-                self.fields().len()
-            ))),
+                    self.name().id(),
+                    fields.len(),
+                    self.fields().len()
+                ))
+                .with_loc(self.location.clone())),
         }
     }
 
@@ -72,10 +121,12 @@ impl TypeInstantiation {
     // FIXME: Remove later, as it should not be needed once typechecking is implemented
     fn check_primitive(&self) -> Result<(), Error> {
         match self.type_name.is_primitive() {
-            true => Err(Error::new(ErrKind::Context).with_msg(format!(
-                "cannot instantiate primitive type `{}`",
-                self.type_name.id()
-            ))),
+            true => Err(Error::new(ErrKind::Context)
+                .with_msg(format!(
+                    "cannot instantiate primitive type `{}`",
+                    self.type_name.id()
+                ))
+                .with_loc(self.location.clone())),
             false => Ok(()),
         }
     }
@@ -87,7 +138,7 @@ impl Instruction for TypeInstantiation {
     }
 
     fn print(&self) -> String {
-        let mut base = format!("{}(", self.type_name.id());
+        let mut base = format!("{}(", self.type_name);
         let mut first_arg = true;
         for arg in &self.fields {
             if !first_arg {
@@ -108,9 +159,22 @@ impl Instruction for TypeInstantiation {
             return None;
         }
 
-        let type_dec = self.get_declaration(ctx)?;
+        let (type_dec, variant) = self.get_declaration(ctx)?;
+
+        if type_dec.is_enum() && variant.is_none() {
+            ctx.error(
+                Error::new(ErrKind::Context)
+                    .with_msg(format!(
+                        "cannot instantiate enum `{}` directly",
+                        type_dec.name()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return None;
+        }
 
-        if let Err(e) = self.check_fields_count(&type_dec) {
+        let expected_fields = variant.as_ref().map_or(type_dec.fields(), Variant::fields);
+        if let Err(e) = self.check_fields_count(expected_fields) {
             ctx.error(e);
             return None;
         }
@@ -118,7 +182,7 @@ impl Instruction for TypeInstantiation {
         let mut size: usize = 0;
         let mut data: Vec<u8> = Vec::new();
         let mut fields: Vec<(Name, ObjectInstance)> = Vec::new();
-        for (_, named_arg) in self.fields.iter().enumerate() {
+        for named_arg in self.fields.iter() {
             // FIXME: Need to assign the correct field to the field that corresponds
             // in the typedec
             let field_instr = named_arg.value();
@@ -131,6 +195,14 @@ impl Instruction for TypeInstantiation {
             fields.push((field_name.to_string(), instance));
         }
 
+        if let Some(variant) = variant {
+            return Some(ObjectInstance::enum_variant(
+                (*type_dec).clone(),
+                variant.name(),
+                fields,
+            ));
+        }
+
         Some(ObjectInstance::new(
             // FIXME: Disgusting, maybe do not use Rc for TypeId?
             Some((*type_dec).clone()),
@@ -139,6 +211,172 @@ impl Instruction for TypeInstantiation {
             Some(fields),
         ))
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for TypeInstantiation {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        if self.type_name.is_primitive() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "cannot instantiate primitive type `{}`",
+                        self.type_name.id()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return CheckedType::Unknown;
+        }
+
+        let (type_dec, variant) = match ctx.get_type(self.name()) {
+            Some(t) => (t, None),
+            None => match ctx.get_variant(self.name().id()) {
+                Some(t) => {
+                    let variant = t.variant(self.name().id()).cloned();
+                    (t, variant)
+                }
+                None => {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!("Cannot find type {}", self.name().id()))
+                            .with_loc(self.location.clone()),
+                    );
+                    return CheckedType::Unknown;
+                }
+            },
+        };
+
+        if type_dec.is_enum() && variant.is_none() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "cannot instantiate enum `{}` directly: instantiate one of its variants",
+                        type_dec.name()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return CheckedType::Unknown;
+        }
+
+        let (kind, expected_fields) = match &variant {
+            Some(variant) => ("variant", variant.fields()),
+            None => ("type", type_dec.fields()),
+        };
+
+        for generic in self.type_name.generics() {
+            ctx.check_type_exists(generic);
+        }
+
+        if self.fields().len() != expected_fields.len() {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "Wrong number of arguments \
+                    for type instantiation `{}`: Expected {}, got {}",
+                        self.type_name.id(),
+                        expected_fields.len(),
+                        self.fields().len()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+        }
+
+        let value_types: Vec<CheckedType> = self
+            .fields
+            .iter_mut()
+            .map(|named_arg| named_arg.value_mut().type_of(ctx))
+            .collect();
+
+        let params = type_dec.generics();
+        let mut bindings = match self.bind_generics(&type_dec, expected_fields, &value_types, ctx) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                ctx.error(e.with_loc(self.location.clone()));
+                return CheckedType::Unknown;
+            }
+        };
+
+        // The type parameters of an enum which do not appear in the fields of the
+        // instantiated variant cannot be inferred: The instance is of an unknown type,
+        // but its fields are still checked
+        let is_complete = match generics::complete(self.type_name.id(), params, &mut bindings) {
+            Ok(()) => true,
+            // Type parameters cannot be inferred from values of unknown types
+            Err(_) if value_types.contains(&CheckedType::Unknown) => return CheckedType::Unknown,
+            Err(_) if variant.is_some() => false,
+            Err(e) => {
+                ctx.error(e.with_loc(self.location.clone()));
+                return CheckedType::Unknown;
+            }
+        };
+
+        for (named_arg, value_ty) in self.fields.iter().zip(value_types) {
+            let field = match expected_fields
+                .iter()
+                .find(|field| field.name() == named_arg.symbol())
+            {
+                Some(field) => field,
+                None => {
+                    ctx.error(
+                        Error::new(ErrKind::TypeChecker)
+                            .with_msg(format!(
+                                "{} `{}` has no field named `{}`",
+                                kind,
+                                self.type_name.id(),
+                                named_arg.symbol()
+                            ))
+                            .with_loc(self.location.clone()),
+                    );
+                    continue;
+                }
+            };
+
+            let expected = ctx.normalize(field.get_type()).substitute(&bindings);
+
+            match value_ty {
+                CheckedType::Resolved(ty) if ty != expected => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "wrong type for field `{}` of {} `{}`: expected `{}`, got `{}`",
+                            field.name(),
+                            kind,
+                            self.type_name.id(),
+                            expected,
+                            ty
+                        ))
+                        .with_loc(self.location.clone()),
+                ),
+                CheckedType::Void => ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "trying to assign statement `{}` to field `{}`",
+                            named_arg.value().print(),
+                            field.name()
+                        ))
+                        .with_loc(self.location.clone()),
+                ),
+                _ => {}
+            }
+        }
+
+        if !is_complete {
+            return CheckedType::Unknown;
+        }
+
+        CheckedType::Resolved(TypeId::with_generics(
+            type_dec.name().to_string(),
+            generics::bound_types(params, &bindings),
+        ))
+    }
+
+    cached_type!();
 }
 
 #[cfg(test)]
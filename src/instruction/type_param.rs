@@ -0,0 +1,38 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::instruction::TypeId;
+
+/// Type parameter of a generic type or function declaration, such as the `T` in
+/// `type Wrapper<T>(inner: T)`. A parameter with a default type can be omitted when
+/// instantiating the declaration: `type Pair<T, U = T>(f: T, s: U)` allows `Pair<int>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeParam {
+    name: String,
+    default: Option<TypeId>,
+}
+
+impl TypeParam {
+    /// Create a new type parameter with a name and an optional default type
+    pub fn new(name: String, default: Option<TypeId>) -> TypeParam {
+        TypeParam { name, default }
+    }
+
+    /// Return a reference to the parameter's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return a reference to the parameter's default type
+    pub fn default(&self) -> Option<&TypeId> {
+        self.default.as_ref()
+    }
+}
+
+impl Display for TypeParam {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.default {
+            Some(default) => write!(f, "{} = {}", self.name, default),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Unary operations apply an operation on a single Instruction. When writing `!a`,
+//! a UnaryOp will be created containing "a" as its operand and "!" as the operator.
+//!
+//! The available unary operators are the logical negation `!`, which takes a boolean
+//! and returns a boolean, and the arithmetic negation `-`, which takes and returns an
+//! int or a float. Negating the smallest int overflows, and is a runtime error.
+
+use crate::error::ErrSpaceLocation;
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{
+    instruction::{Operator, TypeDec, TypeId},
+    Context, ControlFlow, ErrKind, Error, FromObjectInstance, InstrKind, Instruction, JkBool,
+    JkFloat, JkInt, ObjectInstance, ToObjectInstance,
+};
+
+/// The `UnaryOp` struct contains an expression and the operator to apply to it
+#[derive(Clone)]
+pub struct UnaryOp {
+    operand: Box<dyn Instruction>,
+    op: Operator,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
+}
+
+impl UnaryOp {
+    /// Create a new `UnaryOp` from an instruction and an operator
+    pub fn new(operand: Box<dyn Instruction>, op: Operator) -> Self {
+        UnaryOp {
+            operand,
+            op,
+            cached_type: None,
+            location: None,
+        }
+    }
+
+    /// Return the operator used by the UnaryOp
+    #[cfg(test)]
+    pub fn operator(&self) -> Operator {
+        self.op
+    }
+
+    /// Primitive types on which the operator is available
+    fn available_types(&self) -> &'static [&'static str] {
+        match self.op {
+            Operator::Not => &["bool"],
+            _ => &["int", "float"],
+        }
+    }
+
+    /// Execute an arithmetic negation
+    fn negate(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        let value = self.operand.execute_expression(ctx)?;
+
+        let res = match value.ty().map(TypeDec::name) {
+            Some("int") => JkInt::from_instance(&value)
+                .0
+                .checked_neg()
+                .map(|n| JkInt::from(n).to_instance())
+                .ok_or_else(|| format!("integer overflow: `-({})`", value)),
+            Some("float") => Ok(JkFloat::from(-JkFloat::from_instance(&value).0).to_instance()),
+            ty => Err(format!(
+                "unary operation `{}` is not available for type `{}`",
+                self.op.as_str(),
+                ty.unwrap_or("")
+            )),
+        };
+
+        match res {
+            Ok(instance) => Some(instance),
+            Err(msg) => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(msg)
+                        .with_loc(self.location.clone()),
+                );
+                ctx.set_control_flow(ControlFlow::Abort);
+                None
+            }
+        }
+    }
+}
+
+impl Instruction for UnaryOp {
+    fn kind(&self) -> InstrKind {
+        InstrKind::Expression(None)
+    }
+
+    fn print(&self) -> String {
+        format!("{}{}", self.op.as_str(), self.operand.print())
+    }
+
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        ctx.debug_step("UNOP ENTER");
+
+        ctx.debug("OP", self.op.as_str());
+
+        let value = match self.op {
+            Operator::Not => JkBool::from(!self.operand.as_bool(ctx)?).to_instance(),
+            Operator::Neg => self.negate(ctx)?,
+            op => {
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!("invalid unary operator: {}", op.as_str()))
+                        .with_loc(self.location.clone()),
+                );
+                return None;
+            }
+        };
+
+        ctx.debug_step("UNOP EXIT");
+
+        Some(value)
+    }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for UnaryOp {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        match self.operand.type_of(ctx) {
+            CheckedType::Resolved(ty) if !self.available_types().contains(&ty.id()) => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "unary operation `{}` is not available for type `{}`",
+                            self.op.as_str(),
+                            ty
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                CheckedType::Unknown
+            }
+            CheckedType::Void => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!(
+                            "invalid use of statement in unary operation: {}",
+                            self.operand.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
+                CheckedType::Unknown
+            }
+            CheckedType::Resolved(ty) => CheckedType::Resolved(ty),
+            _ if self.op == Operator::Not => CheckedType::Resolved(TypeId::from("bool")),
+            _ => CheckedType::Unknown,
+        }
+    }
+
+    cached_type!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Construct;
+    use crate::Parser;
+
+    #[test]
+    fn t_unop_not() {
+        let unop = UnaryOp::new(Box::new(JkBool::from(true)), Operator::Not);
+
+        let mut ctx = Context::new();
+
+        assert_eq!(unop.operator(), Operator::Not);
+        assert_eq!(
+            unop.execute(&mut ctx).unwrap(),
+            JkBool::from(false).to_instance()
+        );
+        assert!(!ctx.error_handler.has_errors());
+    }
+
+    #[test]
+    fn t_unop_not_on_int() {
+        let mut ctx = Parser::parse("a = !4;", None);
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_unop_neg() {
+        let mut ctx = Context::new();
+
+        let neg = UnaryOp::new(Box::new(JkInt::from(3)), Operator::Neg);
+        assert_eq!(neg.execute(&mut ctx), Some(JkInt::from(-3).to_instance()));
+
+        let neg = UnaryOp::new(Box::new(JkFloat::from(1.5)), Operator::Neg);
+        assert_eq!(
+            neg.execute(&mut ctx),
+            Some(JkFloat::from(-1.5).to_instance())
+        );
+
+        let neg = UnaryOp::new(Box::new(JkInt::from(i64::MIN)), Operator::Neg);
+        assert_eq!(neg.execute(&mut ctx), None);
+        assert!(ctx.error_handler.has_errors());
+        assert_eq!(ctx.take_control_flow(), Some(ControlFlow::Abort));
+    }
+
+    #[test]
+    fn t_unop_neg_on_bool() {
+        let mut ctx = Parser::parse("a = -true;", None);
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_unop_print() {
+        let (_, unop) = Construct::binary_op("!true").unwrap();
+
+        assert_eq!(unop.print(), "!true");
+    }
+}
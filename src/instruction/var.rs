@@ -2,15 +2,24 @@
 //! a block. In jinko, variables cannot be uninitialized. Therefore, there is no
 //! need to keep an option of an instance. A variable is either there, fully initialized,
 //! or it's not.
+//! The variants of enums which do not have any fields are used like variables, by their
+//! name. Variables shadow variants of the same name.
+//! Functions returning a value are used as values by their name as well, such as
+//! `double` in `map(opt, double)`. Variables and variants shadow functions.
 
-use crate::instruction::TypeDec;
-use crate::{Context, ErrKind, Error, InstrKind, Instruction, JkBool, ObjectInstance};
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{TypeDec, TypeId};
+use crate::typechecker::generics::{self, Bindings};
+use crate::typechecker::{cached_type, CheckedType, TypeCheck, TypeCtx};
+use crate::{Context, ErrKind, Error, InstrKind, Instruction, ObjectInstance};
 
 #[derive(Clone)]
 pub struct Var {
     name: String,
     mutable: bool,
     instance: ObjectInstance,
+    cached_type: Option<CheckedType>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl Var {
@@ -20,6 +29,8 @@ impl Var {
             name,
             mutable: false,
             instance: ObjectInstance::empty(),
+            cached_type: None,
+            location: None,
         }
     }
 
@@ -33,6 +44,17 @@ impl Var {
         self.instance.clone()
     }
 
+    /// Return a reference to the variable's instance, without copying it
+    pub fn instance_ref(&self) -> &ObjectInstance {
+        &self.instance
+    }
+
+    /// Return a mutable reference to the variable's instance, in order to modify it
+    /// in place
+    pub fn instance_mut(&mut self) -> &mut ObjectInstance {
+        &mut self.instance
+    }
+
     /// Is a variable mutable or not
     pub fn mutable(&self) -> bool {
         self.mutable
@@ -47,6 +69,41 @@ impl Var {
     pub fn set_mutable(&mut self, mutable: bool) {
         self.mutable = mutable;
     }
+
+    /// Type of the function named like the variable, when the function is used as a
+    /// value
+    fn function_type(&self, ctx: &mut TypeCtx) -> CheckedType {
+        let function = match ctx.get_function(self.name()) {
+            Some(function) => function,
+            None => {
+                ctx.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!("variable has not been declared: `{}`", self.name))
+                        .with_loc(self.location.clone()),
+                );
+                return CheckedType::Unknown;
+            }
+        };
+
+        let error = match function.value_type() {
+            Some(_) if !function.generics().is_empty() => {
+                format!("generic function `{}` cannot be used as a value", self.name)
+            }
+            Some(ty) => return CheckedType::Resolved(ctx.normalize(&ty)),
+            None => format!(
+                "function `{}` does not return anything and cannot be used as a value",
+                self.name
+            ),
+        };
+
+        ctx.error(
+            Error::new(ErrKind::TypeChecker)
+                .with_msg(error)
+                .with_loc(self.location.clone()),
+        );
+
+        CheckedType::Unknown
+    }
 }
 
 impl Instruction for Var {
@@ -63,47 +120,39 @@ impl Instruction for Var {
         )
     }
 
-    fn as_bool(&self, ctx: &mut Context) -> Option<bool> {
-        use crate::FromObjectInstance;
+    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
+        let var = match ctx.get_variable(self.name()) {
+            Some(v) => v,
+            None if ctx.get_variant(self.name()).is_some() => {
+                // We can unwrap since we just checked that the variant exists
+                let enum_dec = (**ctx.get_variant(self.name()).unwrap()).clone();
+
+                return Some(ObjectInstance::enum_variant(enum_dec, self.name(), vec![]));
+            }
+            None if ctx.get_function(self.name()).is_some() => {
+                // We can unwrap since we just checked that the function exists
+                let function = ctx.get_function(self.name()).unwrap().clone();
 
-        // FIXME: Cleanup
+                if let Some(ty) = function.value_type() {
+                    return Some(ObjectInstance::function(self.name(), &ty));
+                }
 
-        match self.execute(ctx) {
-            Some(instance) => match instance.ty() {
-                Some(ty) => match ty.name() {
-                    // FIXME:
-                    "bool" => Some(JkBool::from_instance(&instance).as_bool(ctx).unwrap()),
-                    // We can safely unwrap since we checked the type of the variable
-                    _ => {
-                        ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                            "var {} cannot be interpreted as boolean",
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "function `{}` does not return anything and cannot be used as a value",
                             self.name
-                        )));
-                        None
-                    }
-                },
-                None => todo!(
-                    "If the type of the variable hasn't been determined yet,
-                    typecheck it and call self.as_bool() again"
-                ),
-            },
-            _ => {
-                ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                    "var {} cannot be interpreted as boolean",
-                    self.name
-                )));
-                None
-            }
-        }
-    }
+                        ))
+                        .with_loc(self.location.clone()),
+                );
 
-    fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
-        let var = match ctx.get_variable(self.name()) {
-            Some(v) => v,
+                return None;
+            }
             None => {
                 ctx.error(
                     Error::new(ErrKind::Context)
-                        .with_msg(format!("variable has not been declared: {}", self.name)),
+                        .with_msg(format!("variable has not been declared: {}", self.name))
+                        .with_loc(self.location.clone()),
                 );
 
                 return None;
@@ -114,6 +163,14 @@ impl Instruction for Var {
 
         Some(var.instance())
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
 }
 
 impl Default for Var {
@@ -122,6 +179,50 @@ impl Default for Var {
     }
 }
 
+impl TypeCheck for Var {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        if let Some(ty) = ctx.get_variable(self.name()) {
+            return ty;
+        }
+
+        // Names which are neither variables nor variants refer to functions
+        let enum_dec = match ctx.get_variant(self.name()) {
+            Some(enum_dec) => enum_dec,
+            None => return self.function_type(ctx),
+        };
+
+        let has_fields = enum_dec
+            .variant(self.name())
+            .is_some_and(|variant| !variant.fields().is_empty());
+        if has_fields {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "variant `{}` of enum `{}` has fields, which must be given to it: \
+                        `{} {{ ... }}`",
+                        self.name,
+                        enum_dec.name(),
+                        self.name
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return CheckedType::Unknown;
+        }
+
+        // The type parameters of the enum can only be bound to their default types
+        let mut bindings = Bindings::new();
+        match generics::complete(enum_dec.name(), enum_dec.generics(), &mut bindings) {
+            Ok(()) => CheckedType::Resolved(TypeId::with_generics(
+                enum_dec.name().to_string(),
+                generics::bound_types(enum_dec.generics(), &bindings),
+            )),
+            Err(_) => CheckedType::Unknown,
+        }
+    }
+
+    cached_type!();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,8 @@
 //! The VarAssign struct is used when assigning values to variables.
 
+use crate::error::ErrSpaceLocation;
 use crate::instruction::{InstrKind, Var};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
 use crate::{Context, ErrKind, Error, Instruction, ObjectInstance};
 
 #[derive(Clone)]
@@ -12,6 +14,7 @@ pub struct VarAssign {
     symbol: String,
 
     value: Box<dyn Instruction>,
+    location: Option<ErrSpaceLocation>,
 }
 
 impl VarAssign {
@@ -20,6 +23,7 @@ impl VarAssign {
             mutable,
             symbol,
             value,
+            location: None,
         }
     }
 
@@ -37,6 +41,11 @@ impl VarAssign {
     pub fn value(&self) -> &dyn Instruction {
         &*self.value
     }
+
+    /// Get a mutable reference to the value used to initialize the variable
+    pub fn value_mut(&mut self) -> &mut dyn Instruction {
+        &mut *self.value
+    }
 }
 
 impl Instruction for VarAssign {
@@ -67,7 +76,11 @@ impl Instruction for VarAssign {
                 if self.mutable() {
                     let err_msg =
                         format!("trying to redefine already defined variable: {}", v.name());
-                    ctx.error(Error::new(ErrKind::Context).with_msg(err_msg));
+                    ctx.error(
+                        Error::new(ErrKind::Context)
+                            .with_msg(err_msg)
+                            .with_loc(self.location.clone()),
+                    );
                     return None;
                 }
 
@@ -87,14 +100,30 @@ impl Instruction for VarAssign {
             (false, false) => {
                 // The variable already exists. So we need to error out if it isn't
                 // mutable
-                ctx.error(Error::new(ErrKind::Context).with_msg(format!(
-                    "trying to assign value to non mutable variable `{}`: `{}`",
-                    var.name(),
-                    self.value.print()
-                )));
+                ctx.error(
+                    Error::new(ErrKind::Context)
+                        .with_msg(format!(
+                            "trying to assign value to non mutable variable `{}`: `{}`",
+                            var.name(),
+                            self.value.print()
+                        ))
+                        .with_loc(self.location.clone()),
+                );
                 return None;
             }
-            (true, _) | (_, true) => var.set_instance(self.value.execute_expression(ctx)?),
+            (true, _) | (_, true) => {
+                let mut instance = self.value.execute_expression(ctx)?;
+
+                // Give the instance the type inferred by the typechecker if it does
+                // not carry one already
+                if instance.ty().is_none() {
+                    if let Some(CheckedType::Resolved(ty)) = self.value.cached_type() {
+                        instance.set_ty(ctx.get_type(ty).map(|t| (**t).clone()));
+                    }
+                }
+
+                var.set_instance(instance)
+            }
         }
 
         // We can unwrap safely since we checked that the variable does not
@@ -104,6 +133,56 @@ impl Instruction for VarAssign {
         // A variable assignment is always a statement
         None
     }
+
+    fn location(&self) -> Option<&ErrSpaceLocation> {
+        self.location.as_ref()
+    }
+
+    fn set_location(&mut self, loc: ErrSpaceLocation) {
+        self.location = Some(loc)
+    }
+}
+
+impl TypeCheck for VarAssign {
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        let value_ty = self.value.type_of(ctx);
+
+        if value_ty == CheckedType::Void {
+            ctx.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(format!(
+                        "trying to assign statement `{}` to variable `{}`",
+                        self.value.print(),
+                        self.symbol()
+                    ))
+                    .with_loc(self.location.clone()),
+            );
+            return CheckedType::Void;
+        }
+
+        match ctx.get_variable(self.symbol()) {
+            Some(CheckedType::Resolved(var_ty)) => {
+                if let CheckedType::Resolved(value_ty) = value_ty {
+                    if value_ty != var_ty {
+                        ctx.error(
+                            Error::new(ErrKind::TypeChecker)
+                                .with_msg(format!(
+                            "trying to assign value of type `{}` to variable `{}` of type `{}`",
+                            value_ty,
+                            self.symbol(),
+                            var_ty
+                        ))
+                                .with_loc(self.location.clone()),
+                        );
+                    }
+                }
+            }
+            Some(_) | None => ctx.declare_variable(self.symbol(), value_ty),
+        }
+
+        // A variable assignment is always a statement
+        CheckedType::Void
+    }
 }
 
 #[cfg(test)]
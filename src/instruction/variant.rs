@@ -0,0 +1,44 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::instruction::DecArg;
+
+/// Variant of an enum declaration, such as the `Circle(radius: float)` in
+/// `enum Shape { Circle(radius: float), Empty }`. Variants can carry fields, which are
+/// declared like the fields of a type. Variants without fields, such as `Empty`, are
+/// instantiated by their name only
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    name: String,
+    fields: Vec<DecArg>,
+}
+
+impl Variant {
+    /// Create a new variant with a name and its fields
+    pub fn new(name: String, fields: Vec<DecArg>) -> Variant {
+        Variant { name, fields }
+    }
+
+    /// Return a reference to the variant's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return a reference to the variant's fields
+    pub fn fields(&self) -> &Vec<DecArg> {
+        &self.fields
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.name)?;
+
+        if let Some((first, rest)) = self.fields.split_first() {
+            write!(f, "({}", first)?;
+            rest.iter().try_for_each(|field| write!(f, ", {}", field))?;
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+//! jinko is a small and safe interpreted language. This crate contains the whole
+//! interpreter, and can be used to embed jinko in a Rust program: Parse a program into
+//! a `Context`, register the Rust functions it can call, and execute it.
+//!
+//! ```
+//! use jinko::{Context, FromObjectInstance, JkInt, Parser, Signature, ToObjectInstance, TypeId};
+//!
+//! let mut ctx = Parser::parse("double(21)", None);
+//!
+//! ctx.register_native(
+//!     "double",
+//!     Signature::new(vec![TypeId::from("int")], Some(TypeId::from("int"))),
+//!     |_, args| Some(JkInt::from(JkInt::from_instance(&args[0]).0 * 2).to_instance()),
+//! );
+//!
+//! let result = ctx.execute().unwrap().unwrap();
+//! assert_eq!(JkInt::from_instance(&result).0, 42);
+//! ```
+
+// Allows the code generated by `jinko_derive` to refer to `::jinko` from this crate
+extern crate self as jinko;
+
+pub mod builtins;
+pub mod context;
+pub mod error;
+pub mod ffi;
+pub mod indent;
+pub mod instance;
+pub mod instruction;
+pub mod parser;
+pub mod typechecker;
+mod utils;
+pub mod value;
+
+pub use builtins::{NativeFn, Signature};
+pub use context::{Context, ControlFlow};
+pub use error::{ErrKind, Error};
+pub use indent::Indent;
+pub use instance::{FromObjectInstance, JinkoType, ObjectInstance, ToObjectInstance};
+pub use instruction::{InstrKind, Instruction, TypeId};
+pub use jinko_derive::JinkoType;
+pub use parser::Parser;
+pub use typechecker::{CheckedType, TypeCheck, TypeCtx};
+pub use value::{JkBool, JkChar, JkConstant, JkFloat, JkInt, JkString, Value};
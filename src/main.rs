@@ -1,29 +1,17 @@
 #[warn(missing_docs)]
 mod args;
-mod context;
-mod error;
-mod indent;
-mod instance;
-mod instruction;
-mod parser;
 mod repl;
-mod utils;
-mod value;
 
 use args::Args;
-use parser::Parser;
 use repl::Repl;
 use std::{fs, path::Path};
 
-pub use context::Context;
-pub use error::{ErrKind, Error};
-pub use indent::Indent;
-pub use instance::{FromObjectInstance, ObjectInstance, ToObjectInstance};
-pub use instruction::{InstrKind, Instruction};
-pub use value::{JkBool, JkChar, JkConstant, JkFloat, JkInt, JkString, Value};
+use jinko::{
+    Context, ErrKind, Error, FromObjectInstance, JkBool, JkFloat, JkInt, ObjectInstance, Parser,
+};
 
 // FIXME: Add documentation
-pub type InteractResult = Result<(Option<ObjectInstance>, Context), Error>;
+type InteractResult = Result<(Option<ObjectInstance>, Context), Error>;
 
 fn handle_exit_code(result: Option<ObjectInstance>) -> ! {
     use std::process::exit;
@@ -56,12 +44,30 @@ fn handle_exit_code(result: Option<ObjectInstance>) -> ! {
 fn handle_input(args: &Args, file: &Path) -> InteractResult {
     let input = fs::read_to_string(file)?;
 
-    let mut ctx = Parser::parse(&input)?;
+    let mut ctx = Parser::parse(&input, Some(file));
     ctx.set_path(Some(file.to_owned()));
     ctx.set_debug(args.debug());
 
-    ctx.emit_errors();
-    ctx.clear_errors();
+    for library in args.libraries() {
+        ctx.load_library(library)?;
+    }
+
+    // A program containing syntax errors is not executed, but the user can still
+    // interact with what was parsed properly
+    if ctx.has_errors() {
+        ctx.emit_errors();
+        ctx.clear_errors();
+
+        if !args.interactive() {
+            return Err(Error::new(ErrKind::Parsing));
+        }
+    }
+
+    if args.test() {
+        ctx.run_tests(args.filter())?;
+
+        return Ok((None, ctx));
+    }
 
     match args.interactive() {
         true => Repl::new(args)?.with_context(ctx).launch(),
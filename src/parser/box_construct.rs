@@ -13,7 +13,7 @@
 //! allowing to use them simultaneously when parsing multiple types of constructs.
 
 use crate::{
-    parser::{Construct, ParseResult},
+    parser::{location::location, Construct, ParseResult},
     Instruction,
 };
 
@@ -25,20 +25,28 @@ macro_rules! box_construct {
     };
 }
 
+/// Parser function wrapped by `BoxConstruct::wrap`
+type ConstructFn<T> = Box<dyn FnOnce(&str) -> ParseResult<&str, T>>;
+
 pub struct BoxConstruct;
 
 impl BoxConstruct {
     /// Call a `Construct` and box the return value
     fn wrap<T: 'static + Instruction>(
         input: &str,
-        construct: Box<dyn FnOnce(&str) -> ParseResult<&str, T>>,
+        construct: ConstructFn<T>,
     ) -> ParseResult<&str, Box<dyn Instruction>> {
-        let (input, value) = construct(input)?;
+        let (remaining, mut value) = construct(input)?;
+
+        if let Some(loc) = location(input, remaining) {
+            value.set_location(loc);
+        }
 
-        Ok((input, Box::new(value)))
+        Ok((remaining, Box::new(value)))
     }
 
     box_construct! {type_instantiation}
+    box_construct! {format_string}
     box_construct! {function_call}
     box_construct! {function_declaration}
     box_construct! {ext_declaration}
@@ -47,8 +55,13 @@ impl BoxConstruct {
     box_construct! {jinko_inst}
     box_construct! {any_loop}
     box_construct! {var_assignment}
+    box_construct! {index_assignment}
+    box_construct! {array}
+    box_construct! {map}
     box_construct! {if_else}
     box_construct! {type_declaration}
+    box_construct! {enum_declaration}
+    box_construct! {jk_match}
     box_construct! {test_declaration}
     box_construct! {mock_declaration}
     box_construct! {incl}
@@ -56,4 +69,6 @@ impl BoxConstruct {
     box_construct! {field_access}
     box_construct! {extra}
     box_construct! {jk_return}
+    box_construct! {jk_break}
+    box_construct! {jk_continue}
 }
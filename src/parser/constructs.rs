@@ -14,15 +14,22 @@
 //! is the grammar for a variable assignment.
 
 use nom::Err::Error as NomError;
-use nom::{branch::alt, combinator::opt, multi::many0};
+use nom::{
+    branch::alt,
+    combinator::{not, opt},
+    multi::many0,
+};
 
 use crate::error::{ErrKind, Error};
 use crate::instruction::{
-    Block, DecArg, ExtraContent, FieldAccess, FunctionCall, FunctionDec, FunctionKind, IfElse,
-    Incl, Instruction, JkInst, Loop, LoopKind, MethodCall, Return, TypeDec, TypeId,
-    TypeInstantiation, Var, VarAssign,
+    Array, Block, Break, Continue, DecArg, ExtraContent, FieldAccess, FormatPart, FormatString,
+    FunctionCall, FunctionDec, FunctionKind, IfElse, Incl, IndexAssign, Instruction, JkInst, Loop,
+    LoopKind, Map, MapEntry, Match, MatchArm, MethodCall, Return, TypeDec, TypeId,
+    TypeInstantiation, TypeParam, Var, VarAssign, Variant,
+};
+use crate::parser::{
+    location, BoxConstruct, ConstantConstruct, ParseResult, ShuntingYard, StringFragment, Token,
 };
-use crate::parser::{BoxConstruct, ConstantConstruct, ParseResult, ShuntingYard, Token};
 
 type Instructions = Vec<Box<dyn Instruction>>;
 type MaybeInstruction = Option<Box<dyn Instruction>>;
@@ -37,26 +44,36 @@ impl Construct {
         // FIXME: We need to parse the remaining input after a correct instruction
         // has been parsed
         let (input, value) = alt((
-            Construct::binary_op,
+            alt((Construct::generic_type_instantiation, Construct::binary_op)),
             BoxConstruct::method_call,
             BoxConstruct::field_access,
             BoxConstruct::function_declaration,
-            BoxConstruct::type_declaration,
+            alt((
+                BoxConstruct::type_declaration,
+                BoxConstruct::enum_declaration,
+            )),
             BoxConstruct::ext_declaration,
             BoxConstruct::test_declaration,
             BoxConstruct::mock_declaration,
             BoxConstruct::type_instantiation,
             BoxConstruct::function_call,
             BoxConstruct::incl,
-            BoxConstruct::if_else,
+            alt((BoxConstruct::if_else, BoxConstruct::jk_match)),
             BoxConstruct::any_loop,
+            BoxConstruct::jk_break,
+            BoxConstruct::jk_continue,
             BoxConstruct::jinko_inst,
             BoxConstruct::block,
-            BoxConstruct::var_assignment,
+            alt((BoxConstruct::index_assignment, BoxConstruct::var_assignment)),
             BoxConstruct::variable,
-            Construct::constant,
+            // Literals followed by a block, such as the range of a for loop, are not
+            // binary expressions
+            alt((Construct::constant, BoxConstruct::map, BoxConstruct::array)),
             BoxConstruct::extra,
-        ))(input)?;
+        ))(input)
+        // Errors which do not know where they happened are located at the start of the
+        // innermost instruction containing them
+        .map_err(|e| e.map(|e| e.with_remaining(input)))?;
 
         Ok((input, value))
     }
@@ -77,21 +94,15 @@ impl Construct {
         Ok((input, expr))
     }
 
-    /// Parse as many instructions as possible
-    pub fn many_instructions(input: &str) -> ParseResult<&str, Vec<Box<dyn Instruction>>> {
-        let instrs = many0(Construct::instruction_maybe_semicolon)(input)?;
-
-        Ok(instrs)
-    }
-
     /// Constants are raw values in the source code. For example, `"string"`, `12` and
-    /// `0.5`.
+    /// `0.5`. Strings containing interpolations are parsed as format strings.
     ///
     /// `'<any_char>' | "<any_char>*" | <num>? | <num>?.<num>?`
     pub(crate) fn constant(input: &str) -> ParseResult<&str, Box<dyn Instruction>> {
         let constant = alt((
             ConstantConstruct::char_constant,
             ConstantConstruct::string_constant,
+            BoxConstruct::format_string,
             ConstantConstruct::float_constant,
             ConstantConstruct::int_constant,
             ConstantConstruct::bool_constant,
@@ -141,11 +152,15 @@ impl Construct {
     /// `<identifier> ( )`
     fn function_call_no_args(input: &str) -> ParseResult<&str, FunctionCall> {
         let (input, fn_id) = Token::identifier(input)?;
+        let (input, generics) = opt(Construct::generic_args)(input)?;
         let (input, _) = Token::left_parenthesis(input)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::right_parenthesis(input)?;
 
-        Ok((input, FunctionCall::new(fn_id)))
+        let mut fn_call = FunctionCall::new(fn_id);
+        fn_call.set_generics(generics.unwrap_or_default());
+
+        Ok((input, fn_call))
     }
 
     /// Parse an argument given to a function. Consumes the whitespaces before and after
@@ -188,9 +203,11 @@ impl Construct {
     /// Parse a function call with arguments
     fn function_call_args(input: &str) -> ParseResult<&str, FunctionCall> {
         let (input, fn_id) = Token::identifier(input)?;
+        let (input, generics) = opt(Construct::generic_args)(input)?;
         let (input, _) = Token::left_parenthesis(input)?;
 
         let mut fn_call = FunctionCall::new(fn_id);
+        fn_call.set_generics(generics.unwrap_or_default());
 
         let (input, mut arg_vec) = Construct::args_list(input)?;
         let (input, _) = Token::right_parenthesis(input)?;
@@ -238,15 +255,14 @@ impl Construct {
 
     /// When a type is instantiated in the source code.
     ///
-    /// ```
+    /// ```text
     /// type A(n: int); // Declare type A
     /// val = A(1); // Instantiate a new A type variable
     /// ```
     /// `<arg_list> := [(<constant> | <variable> | <expression>)*]`
-    /// `<identifier> ( <arg_list> )`
+    /// `<type> { <arg_list> }`
     pub fn type_instantiation(input: &str) -> ParseResult<&str, TypeInstantiation> {
-        let (input, type_name) = Token::identifier(input)?;
-        let type_id = TypeId::new(type_name);
+        let (input, type_id) = Construct::type_id(input)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::left_curly_bracket(input)?;
 
@@ -262,9 +278,80 @@ impl Construct {
         Ok((input, type_instantiation))
     }
 
+    /// Parse the instantiation of a type given explicit generic arguments, which is not
+    /// the instance of a field access. This needs to be tried before binary operations,
+    /// as the generic arguments of `Pair<int, float> { ... }` would otherwise be parsed
+    /// as comparisons
+    fn generic_type_instantiation(input: &str) -> ParseResult<&str, Box<dyn Instruction>> {
+        let (remaining, value) = Construct::type_instantiation(input)?;
+        if value.name().generics().is_empty() {
+            return Err(NomError(Error::new(ErrKind::Parsing)));
+        }
+
+        let (remaining, _) = Token::maybe_consume_extra(remaining)?;
+        let (_, _) = not(Token::dot)(remaining)?;
+
+        BoxConstruct::type_instantiation(input)
+    }
+
+    /// Parse the instantiation of a type which is followed by the `?` operator, such as
+    /// `Err { error = e }?`. Other instantiations are not operands of binary operations,
+    /// as the block of `if a == b { ... }` would otherwise be parsed as the fields of `b`
+    pub(crate) fn propagated_type_instantiation(
+        input: &str,
+    ) -> ParseResult<&str, Box<dyn Instruction>> {
+        let (remaining, value) = BoxConstruct::type_instantiation(input)?;
+
+        let (next, _) = Token::maybe_consume_extra(remaining)?;
+        let (_, _) = Token::question_mark(next)?;
+
+        Ok((remaining, value))
+    }
+
+    /// Parse a string containing expressions to interpolate. Braces are escaped by
+    /// doubling them
+    ///
+    /// `"<any_char>* '{' <expression> '}' <any_char>*"`
+    pub(crate) fn format_string(input: &str) -> ParseResult<&str, FormatString> {
+        let (mut input, _) = Token::double_quote(input)?;
+        let mut parts = vec![];
+        let mut text = String::new();
+
+        loop {
+            let (next, fragment) = Token::string_fragment(input)?;
+
+            input = match fragment {
+                StringFragment::Char(c) => {
+                    text.push(c);
+                    next
+                }
+                StringFragment::Interpolation => {
+                    if !text.is_empty() {
+                        parts.push(FormatPart::Text(std::mem::take(&mut text)));
+                    }
+
+                    let (next, _) = Token::maybe_consume_extra(next)?;
+                    let (next, expr) = Construct::instruction(next)?;
+                    let (next, _) = Token::maybe_consume_extra(next)?;
+                    let (next, _) = Token::right_curly_bracket(next)?;
+
+                    parts.push(FormatPart::Expr(expr));
+                    next
+                }
+                StringFragment::End => {
+                    if !text.is_empty() {
+                        parts.push(FormatPart::Text(text));
+                    }
+
+                    return Ok((next, FormatString::new(parts)));
+                }
+            };
+        }
+    }
+
     /// When a function is called in the source code.
     ///
-    /// ```
+    /// ```text
     /// fn(); // Function call
     /// fn() // Call the function `fn` and use the return result as an instruction
     /// x = fn(); // Assign the result of the function call to the variable x
@@ -284,7 +371,7 @@ impl Construct {
     /// When a variable is assigned a value. Ideally, a variable cannot be assigned the
     /// `void` type.
     ///
-    /// ```
+    /// ```text
     /// x = 12; // Store 12 into the variable `x`
     /// x = 456; // Forbidden, `x` is immutable
     /// mut n = 12; // Store 12 into `n`, a mutable variable
@@ -293,7 +380,7 @@ impl Construct {
     ///
     /// A variable assignment is a Statement. It cannot be used as an Expression
     ///
-    /// ```
+    /// ```text
     /// {
     ///     x = 12; // Block returns void
     /// }
@@ -326,6 +413,109 @@ impl Construct {
         }
     }
 
+    /// Assign a value to the element of an array contained in a variable
+    ///
+    /// `<identifier> <index> = <instruction>`
+    pub(crate) fn index_assignment(input: &str) -> ParseResult<&str, IndexAssign> {
+        let (input, id) = Token::identifier(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, index) = Construct::index(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::equal(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, value) = Construct::instruction(input)?;
+
+        Ok((input, IndexAssign::new(Var::new(id), index, value)))
+    }
+
+    /// Parse the position of an element in an array
+    ///
+    /// `[ <instruction> ]`
+    pub(crate) fn index(input: &str) -> ParseResult<&str, Box<dyn Instruction>> {
+        let (input, _) = Token::left_bracket(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, index) = Construct::instruction(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::right_bracket(input)?;
+
+        Ok((input, index))
+    }
+
+    /// Parse an array literal. The last element can be followed by a comma
+    ///
+    /// `[ [ <instruction> [ , <instruction> ]* [ , ] ] ]`
+    pub(crate) fn array(input: &str) -> ParseResult<&str, Array> {
+        let (input, _) = Token::left_bracket(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, elements) = opt(|input| {
+            let (input, first) = Construct::instruction(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            let (input, mut rest) = many0(|input| {
+                let (input, _) = Token::comma(input)?;
+                let (input, _) = Token::maybe_consume_extra(input)?;
+                let (input, next) = Construct::instruction(input)?;
+                let (input, _) = Token::maybe_consume_extra(input)?;
+
+                Ok((input, next))
+            })(input)?;
+
+            let (input, _) = opt(Token::comma)(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            rest.insert(0, first);
+
+            Ok((input, rest))
+        })(input)?;
+
+        let (input, _) = Token::right_bracket(input)?;
+
+        Ok((input, Array::new(elements.unwrap_or_default())))
+    }
+
+    /// Parse a map literal. The last entry can be followed by a comma
+    ///
+    /// `[ : ] | [ <instruction> : <instruction> [ , <instruction> : <instruction> ]* [ , ] ]`
+    pub(crate) fn map(input: &str) -> ParseResult<&str, Map> {
+        fn entry(input: &str) -> ParseResult<&str, MapEntry> {
+            let (input, key) = Construct::instruction(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, _) = Token::colon(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, value) = Construct::instruction(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            Ok((input, (key, value)))
+        }
+
+        let (input, _) = Token::left_bracket(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        if let Ok((input, _)) = Token::colon(input) {
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, _) = Token::right_bracket(input)?;
+
+            return Ok((input, Map::new(vec![])));
+        }
+
+        let (input, first) = entry(input)?;
+        let (input, mut entries) = many0(|input| {
+            let (input, _) = Token::comma(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            entry(input)
+        })(input)?;
+
+        let (input, _) = opt(Token::comma)(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::right_bracket(input)?;
+
+        entries.insert(0, first);
+
+        Ok((input, Map::new(entries)))
+    }
+
     /// Parse a valid variable name
     ///
     /// `<identifier>`
@@ -348,9 +538,25 @@ impl Construct {
         Ok((input, expr))
     }
 
+    /// Parse a statement ending with a block, such as an if/else or a loop. It does not
+    /// need to be followed by a semicolon, unless it is the last instruction of a block
+    ///
+    /// `<if_else> | <loop>`
+    fn stmt_block(input: &str) -> ParseResult<&str, Box<dyn Instruction>> {
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, expr) = alt((BoxConstruct::if_else, BoxConstruct::any_loop))(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        // The last instruction of a block is its return value, not a statement
+        not(Token::right_curly_bracket)(input)?;
+
+        Ok((input, expr))
+    }
+
     /// Parse multiple statements and a possible return Instruction
     fn stmts_and_maybe_last(input: &str) -> ParseResult<&str, (Instructions, MaybeInstruction)> {
-        let (input, instructions) = many0(Construct::stmt_semicolon)(input)?;
+        let (input, instructions) =
+            many0(alt((Construct::stmt_semicolon, Construct::stmt_block)))(input)?;
         let (input, last_expr) =
             opt(alt((Construct::early_return, Construct::instruction)))(input)?;
 
@@ -381,7 +587,7 @@ impl Construct {
     /// A block of code is a new inner scope that contains instructions. You can use
     /// them in If/Else blocks, in function declarations, or just as is.
     ///
-    /// ```
+    /// ```text
     /// func return_nothing() {
     ///     compute_stuff();
     /// } // Block returns void, so does the function
@@ -422,17 +628,153 @@ impl Construct {
         Ok((input, vec![]))
     }
 
+    /// Parse a list of at least one element, separated by commas and surrounded by
+    /// angle brackets
+    fn angle_bracket_list<'i, T>(
+        input: &'i str,
+        element: fn(&'i str) -> ParseResult<&'i str, T>,
+    ) -> ParseResult<&'i str, Vec<T>> {
+        let (input, _) = Token::lt(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, first) = element(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, mut rest) = many0(|input| {
+            let (input, _) = Token::comma(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, next) = element(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            Ok((input, next))
+        })(input)?;
+
+        let (input, _) = Token::gt(input)?;
+
+        rest.insert(0, first);
+
+        Ok((input, rest))
+    }
+
+    /// Parse the types given to the type parameters of a generic type or function
+    ///
+    /// `< <type> [ , <type> ]* >`
+    fn generic_args(input: &str) -> ParseResult<&str, Vec<TypeId>> {
+        Construct::angle_bracket_list(input, Construct::type_id)
+    }
+
+    /// Parse the type of a function, which has to return a value
+    ///
+    /// `func ( [ <type> [ , <type> ]* ] ) -> <type>`
+    fn function_type(input: &str) -> ParseResult<&str, TypeId> {
+        let (input, _) = Token::func_type_tok(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::left_parenthesis(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, first) = opt(Construct::type_id)(input)?;
+        let (input, mut args) = many0(|input| {
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, _) = Token::comma(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            Construct::type_id(input)
+        })(input)?;
+
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::right_parenthesis(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::arrow(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, ret) = Construct::type_id(input)?;
+
+        if let Some(first) = first {
+            args.insert(0, first);
+        }
+
+        Ok((input, TypeId::function(args, ret)))
+    }
+
+    /// Parse a type, which can be a generic type instantiated with other types, the
+    /// type of a function or the type of an array
+    ///
+    /// `<identifier> [ <generic_args> ] | <function_type> | <array_type>`
+    pub(crate) fn type_id(input: &str) -> ParseResult<&str, TypeId> {
+        alt((
+            Construct::function_type,
+            Construct::array_type,
+            Construct::generic_type,
+        ))(input)
+    }
+
+    /// Parse the type of an array, from the type of its elements
+    ///
+    /// `[ <type> ]`
+    fn array_type(input: &str) -> ParseResult<&str, TypeId> {
+        let (input, _) = Token::left_bracket(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, element) = Construct::type_id(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::right_bracket(input)?;
+
+        Ok((input, TypeId::array_of(&element)))
+    }
+
+    /// Parse a type with its generic arguments, if any
+    ///
+    /// `<identifier> [ <generic_args> ]`
+    fn generic_type(input: &str) -> ParseResult<&str, TypeId> {
+        let (input, id) = Token::identifier(input)?;
+        let (input, generics) = opt(Construct::generic_args)(input)?;
+
+        Ok((
+            input,
+            TypeId::with_generics(id, generics.unwrap_or_default()),
+        ))
+    }
+
+    /// Parse a type parameter and its optional default type
+    ///
+    /// `<identifier> [ = <type> ]`
+    fn type_param(input: &str) -> ParseResult<&str, TypeParam> {
+        let (input, name) = Token::identifier(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, default) = opt(|input| {
+            let (input, _) = Token::equal(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            Construct::type_id(input)
+        })(input)?;
+
+        Ok((input, TypeParam::new(name, default)))
+    }
+
+    /// Parse the type parameters of a generic declaration
+    ///
+    /// `< <type_param> [ , <type_param> ]* >`
+    fn type_params(input: &str) -> ParseResult<&str, Vec<TypeParam>> {
+        let (input, params) = Construct::angle_bracket_list(input, Construct::type_param)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        Ok((input, params))
+    }
+
     /// Parse an identifier then its type
     ///
     /// `<identifier> : <type>`
-    fn identifier_type(input: &str) -> ParseResult<&str, DecArg> {
-        let (input, id) = Token::identifier(input)?;
+    fn identifier_type(start: &str) -> ParseResult<&str, DecArg> {
+        let (input, id) = Token::identifier(start)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::colon(input)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
-        let (input, ty) = Token::identifier(input)?;
+        let (input, ty) = Construct::type_id(input)?;
 
-        Ok((input, DecArg::new(id, TypeId::new(ty))))
+        let mut arg = DecArg::new(id, ty);
+        if let Some(loc) = location::location(start, input) {
+            arg.set_location(loc);
+        }
+
+        Ok((input, arg))
     }
 
     /// Parse an identifer as well as the type and comma that follows
@@ -491,10 +833,10 @@ impl Construct {
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::arrow(input)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
-        let (input, ty) = Token::identifier(input)?;
+        let (input, ty) = Construct::type_id(input)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
 
-        Ok((input, Some(TypeId::from(ty.as_str()))))
+        Ok((input, Some(ty)))
     }
 
     /// Parse the return type of a function. Can be void
@@ -506,11 +848,12 @@ impl Construct {
 
     /// Parses the content of a function declaration
     ///
-    /// `<identifier> <args_dec> <return_type> <block>`
+    /// `<identifier> [ <type_params> ] <args_dec> <return_type> <block>`
     fn function_content(input: &str) -> ParseResult<&str, FunctionDec> {
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, fn_name) = Token::identifier(input)?;
         let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, generics) = opt(Construct::type_params)(input)?;
 
         let (input, args) = Construct::args_dec(input)?;
         let (input, ty) = Construct::return_type(input)?;
@@ -518,6 +861,7 @@ impl Construct {
 
         let mut function = FunctionDec::new(fn_name, ty);
 
+        function.set_generics(generics.unwrap_or_default());
         function.set_args(args);
         function.set_block(block);
 
@@ -527,7 +871,7 @@ impl Construct {
     /// Parse a function declaration. This includes the function's signature and the
     /// associated code block
     ///
-    /// ```
+    /// ```text
     /// func fn_name(arg0: int) -> int {
     ///     do_something(arg0);
     ///
@@ -536,7 +880,7 @@ impl Construct {
     /// ```
     ///
     /// `<typed_arg_list> := [ (<identifier> : <type>)* ]
-    /// `<func> <identifier> ( <typed_arg_list> ) [ -> <type> ] <block>`
+    /// `<func> <identifier> [ <type_params> ] ( <typed_arg_list> ) [ -> <type> ] <block>`
     pub(crate) fn function_declaration(input: &str) -> ParseResult<&str, FunctionDec> {
         let (input, _) = Token::func_tok(input)?;
 
@@ -552,7 +896,7 @@ impl Construct {
     /// invoke them. Therefore, naming the test the same as the tested function is fine
     /// and is not any form of overloading whatsoever.
     ///
-    /// ```
+    /// ```text
     /// test add() {
     ///     assert_eq(12 + 2, add(12, 2));
     /// }
@@ -583,7 +927,7 @@ impl Construct {
     /// kind `FunctionDec::Mock`.
     ///
     ///
-    /// ```
+    /// ```text
     /// mock add(lhs: int, rhs: int) -> int {
     ///     mock_stuff()
     /// }
@@ -673,6 +1017,29 @@ impl Construct {
         Ok((input, Return::new(val)))
     }
 
+    /// Parse break construct. Consumes a break with its potential value
+    ///
+    /// `<break> [ <xxx> ]`
+    pub(crate) fn jk_break(input: &str) -> ParseResult<&str, Break> {
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::break_tok(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, val) = opt(Construct::instruction)(input)?;
+
+        Ok((input, Break::new(val)))
+    }
+
+    /// Parse continue construct
+    ///
+    /// `<continue>`
+    pub(crate) fn jk_continue(input: &str) -> ParseResult<&str, Continue> {
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::continue_tok(input)?;
+
+        Ok((input, Continue::new()))
+    }
+
     /// Parse a loop block, meaning the `loop` keyword and a corresponding block
     ///
     /// `<loop> <block>`
@@ -700,30 +1067,75 @@ impl Construct {
         Ok((input, Loop::new(LoopKind::While(condition), block)))
     }
 
+    /// Parse the expression iterated on by a for loop. Since the body of the loop
+    /// follows it, an identifier followed by a block is not a type instantiation here:
+    /// `for e in values { ... }` iterates over the `values` variable
+    ///
+    /// `<binary_op> | <method_call> | <field_access> | <function_call> | <variable> | <constant> | <map> | <array>`
+    fn iterable(input: &str) -> ParseResult<&str, Box<dyn Instruction>> {
+        alt((
+            Construct::binary_op,
+            BoxConstruct::method_call,
+            BoxConstruct::field_access,
+            BoxConstruct::function_call,
+            BoxConstruct::variable,
+            Construct::constant,
+            BoxConstruct::map,
+            BoxConstruct::array,
+        ))(input)
+    }
+
     /// Construct a for block, which consists of a variable, a range instruction, and
-    /// a block to execute
+    /// a block to execute. Loops over the entries of a map have a variable for the keys
+    /// and one for the values
     ///
-    /// `<for> <variable> <in> <instruction> <block>`
+    /// `<for> <variable> <in> <iterable> <block>`
+    /// `<for> ( <variable> , <variable> ) <in> <iterable> <block>`
     fn for_block(input: &str) -> ParseResult<&str, Loop> {
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::for_tok(input)?;
 
         let (input, _) = Token::maybe_consume_extra(input)?;
-        let (input, variable) = Construct::variable(input)?;
+        let (input, entry) = opt(|input| {
+            let (input, _) = Token::left_parenthesis(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, key) = Construct::variable(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, _) = Token::comma(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, value) = Construct::variable(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, _) = Token::right_parenthesis(input)?;
+
+            Ok((input, (key, value)))
+        })(input)?;
+        let (input, variable) = match entry {
+            Some(_) => (input, None),
+            None => {
+                let (input, variable) = Construct::variable(input)?;
+                (input, Some(variable))
+            }
+        };
 
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::in_tok(input)?;
 
         let (input, _) = Token::maybe_consume_extra(input)?;
-        let (input, instruction) = Construct::instruction(input)?;
+        let (input, instruction) = Construct::iterable(input)?;
 
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, block) = Construct::block(input)?;
 
-        Ok((
-            input,
-            Loop::new(LoopKind::For(variable, instruction), block),
-        ))
+        let kind = match (entry, variable) {
+            (Some((key, value)), _) => {
+                LoopKind::ForEntries(Box::new(key), Box::new(value), instruction)
+            }
+            (None, Some(variable)) => LoopKind::For(Box::new(variable), instruction),
+            // We parsed either an entry or a variable
+            (None, None) => unreachable!(),
+        };
+
+        Ok((input, Loop::new(kind, block)))
     }
 
     /// Parse any loop construct: For, While or Loop
@@ -758,18 +1170,24 @@ impl Construct {
     ///
     /// `<expr> <op> <expr>`
     ///
-    /// ```
+    /// ```text
     /// x + y; // Add x and y together
     /// a << 2; // Shift a by 2 bits
     /// a > 2; // Is a greater than 2?
     /// ```
     pub(crate) fn binary_op(input: &str) -> ParseResult<&str, Box<dyn Instruction>> {
-        ShuntingYard::parse(input)
+        let (remaining, mut expr) = ShuntingYard::parse(input)?;
+
+        if let Some(loc) = location::location(input, remaining) {
+            expr.set_location(loc);
+        }
+
+        Ok((remaining, expr))
     }
 
     /// Parse a user-defined custom type
     ///
-    /// `<type> <TypeName> ( <typed_arg_list> ) ;`
+    /// `<type> <TypeName> [ <type_params> ] ( <typed_arg_list> ) ;`
     pub(crate) fn type_declaration(input: &str) -> ParseResult<&str, TypeDec> {
         let (input, _) = Token::maybe_consume_extra(input)?;
         let (input, _) = Token::_type_tok(input)?;
@@ -778,14 +1196,149 @@ impl Construct {
         let (input, type_name) = Token::identifier(input)?;
 
         let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, generics) = opt(Construct::type_params)(input)?;
 
         let (input, fields) = Construct::args_dec_non_empty(input)?;
 
-        let type_declaration = TypeDec::new(type_name, fields);
+        let mut type_declaration = TypeDec::new(type_name, fields);
+        type_declaration.set_generics(generics.unwrap_or_default());
 
         Ok((input, type_declaration))
     }
 
+    /// Parse a list of elements between curly brackets, separated by commas. The last
+    /// element can be followed by a comma
+    ///
+    /// `{ <element> [ , <element> ]* [ , ] }`
+    fn curly_bracket_list<'i, T>(
+        input: &'i str,
+        element: fn(&'i str) -> ParseResult<&'i str, T>,
+    ) -> ParseResult<&'i str, Vec<T>> {
+        let (input, _) = Token::left_curly_bracket(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, first) = element(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, mut rest) = many0(|input| {
+            let (input, _) = Token::comma(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+            let (input, next) = element(input)?;
+            let (input, _) = Token::maybe_consume_extra(input)?;
+
+            Ok((input, next))
+        })(input)?;
+
+        let (input, _) = opt(Token::comma)(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::right_curly_bracket(input)?;
+
+        rest.insert(0, first);
+
+        Ok((input, rest))
+    }
+
+    /// Parse the variant of an enum, with its optional fields
+    ///
+    /// `<identifier> [ ( <typed_arg_list> ) ]`
+    fn variant(input: &str) -> ParseResult<&str, Variant> {
+        let (input, name) = Token::identifier(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, fields) = opt(Construct::args_dec_non_empty)(input)?;
+
+        Ok((input, Variant::new(name, fields.unwrap_or_default())))
+    }
+
+    /// Parse a user-defined enum, whose instances are one of its variants
+    ///
+    /// `<enum> <EnumName> [ <type_params> ] { <variant> [ , <variant> ]* [ , ] }`
+    pub(crate) fn enum_declaration(input: &str) -> ParseResult<&str, TypeDec> {
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::enum_tok(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, enum_name) = Token::identifier(input)?;
+
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, generics) = opt(Construct::type_params)(input)?;
+
+        let (input, variants) = Construct::curly_bracket_list(input, Construct::variant)?;
+
+        let mut enum_declaration = TypeDec::new_enum(enum_name, variants);
+        enum_declaration.set_generics(generics.unwrap_or_default());
+
+        Ok((input, enum_declaration))
+    }
+
+    /// Parse a name bound in the pattern of a match arm, or the wildcard `_` to ignore
+    /// the field
+    ///
+    /// `<identifier> | _`
+    fn binding(input: &str) -> ParseResult<&str, Option<String>> {
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, binding) = alt((
+            |input| Token::identifier(input).map(|(input, name)| (input, Some(name))),
+            |input| Token::wildcard(input).map(|(input, _)| (input, None)),
+        ))(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        Ok((input, binding))
+    }
+
+    /// Parse an arm of a match expression. The wildcard pattern `_` matches any variant
+    ///
+    /// `<pattern> := _ | <identifier> [ ( <binding> [ , <binding> ]* ) ]`
+    /// `<pattern> => <instruction>`
+    fn match_arm(input: &str) -> ParseResult<&str, MatchArm> {
+        let (input, variant) = alt((
+            |input| Token::identifier(input).map(|(input, name)| (input, Some(name))),
+            |input| Token::wildcard(input).map(|(input, _)| (input, None)),
+        ))(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, bindings) = match variant {
+            Some(_) => opt(|input| {
+                let (input, _) = Token::left_parenthesis(input)?;
+                let (input, first) = Construct::binding(input)?;
+                let (input, mut rest) = many0(|input| {
+                    let (input, _) = Token::comma(input)?;
+                    Construct::binding(input)
+                })(input)?;
+                let (input, _) = Token::right_parenthesis(input)?;
+                let (input, _) = Token::maybe_consume_extra(input)?;
+
+                rest.insert(0, first);
+
+                Ok((input, rest))
+            })(input)?,
+            None => (input, None),
+        };
+
+        let (input, _) = Token::fat_arrow(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, body) = Construct::instruction(input)?;
+
+        Ok((
+            input,
+            MatchArm::new(variant, bindings.unwrap_or_default(), body),
+        ))
+    }
+
+    /// Parse a match expression on the variant of an enum instance
+    ///
+    /// `<match> <instruction> { <match_arm> [ , <match_arm> ]* [ , ] }`
+    pub(crate) fn jk_match(input: &str) -> ParseResult<&str, Match> {
+        let (input, _) = Token::maybe_consume_extra(input)?;
+        let (input, _) = Token::match_tok(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, value) = Construct::instruction(input)?;
+        let (input, _) = Token::maybe_consume_extra(input)?;
+
+        let (input, arms) = Construct::curly_bracket_list(input, Construct::match_arm)?;
+
+        Ok((input, Match::new(value, arms)))
+    }
+
     /// Parses a path for code inclusion
     fn path(input: &str) -> ParseResult<&str, String> {
         let (input, _) = Token::maybe_consume_extra(input)?;
@@ -843,6 +1396,8 @@ impl Construct {
             BoxConstruct::function_call,
             BoxConstruct::variable,
             Construct::constant,
+            BoxConstruct::map,
+            BoxConstruct::array,
             BoxConstruct::if_else,
             BoxConstruct::block,
             BoxConstruct::any_loop,
@@ -925,22 +1480,16 @@ mod tests {
 
     #[test]
     fn t_var_assign_valid() {
-        assert_eq!(
-            Construct::var_assignment("x = 12;").unwrap().1.mutable(),
-            false
-        );
+        assert!(!Construct::var_assignment("x = 12;").unwrap().1.mutable());
         assert_eq!(
             Construct::var_assignment("x = 12;").unwrap().1.symbol(),
             "x"
         );
 
-        assert_eq!(
-            Construct::var_assignment("mut x_99 = 129;")
-                .unwrap()
-                .1
-                .mutable(),
-            true
-        );
+        assert!(Construct::var_assignment("mut x_99 = 129;")
+            .unwrap()
+            .1
+            .mutable());
         assert_eq!(
             Construct::var_assignment("mut x_99 = 129;")
                 .unwrap()
@@ -949,13 +1498,10 @@ mod tests {
             "x_99"
         );
 
-        assert_eq!(
-            Construct::var_assignment("mut_x_99 = 129;")
-                .unwrap()
-                .1
-                .mutable(),
-            false
-        );
+        assert!(!Construct::var_assignment("mut_x_99 = 129;")
+            .unwrap()
+            .1
+            .mutable());
         assert_eq!(
             Construct::var_assignment("mut_x_99 = 129;")
                 .unwrap()
@@ -964,13 +1510,10 @@ mod tests {
             "mut_x_99"
         );
 
-        assert_eq!(
-            Construct::var_assignment("mut mut_x_99 = 129;")
-                .unwrap()
-                .1
-                .mutable(),
-            true
-        );
+        assert!(Construct::var_assignment("mut mut_x_99 = 129;")
+            .unwrap()
+            .1
+            .mutable());
         assert_eq!(
             Construct::var_assignment("mut mut_x_99 = 129;")
                 .unwrap()
@@ -979,13 +1522,10 @@ mod tests {
             "mut_x_99"
         );
 
-        assert_eq!(
-            Construct::var_assignment("mut\nname = 129;")
-                .unwrap()
-                .1
-                .mutable(),
-            true
-        );
+        assert!(Construct::var_assignment("mut\nname = 129;")
+            .unwrap()
+            .1
+            .mutable());
 
         assert!(Construct::var_assignment("mut x=12;").is_ok());
         assert!(Construct::var_assignment("mut x= 12;").is_ok());
@@ -1322,6 +1862,15 @@ mod tests {
     #[test]
     fn t_for_valid() {
         assert!(Construct::for_block("for x_99 in x_99 {}").is_ok());
+        assert!(Construct::for_block("for ( k,v ) in map {}").is_ok());
+        assert!(Construct::instruction("for x in [1, 2] {}").is_ok());
+        assert!(Construct::instruction("for (k, v) in [1: 2] {}").is_ok());
+    }
+
+    #[test]
+    fn t_for_variable_followed_by_body() {
+        let (input, _) = Construct::for_block("for x in values { y = x }").unwrap();
+        assert_eq!(input, "");
     }
 
     #[test]
@@ -1331,6 +1880,10 @@ mod tests {
         assert!(Construct::for_block("for x99 in {}").is_err());
 
         assert!(Construct::for_block("for x99 in { { { inner_block() } } }").is_err());
+
+        assert!(Construct::for_block("for (k) in map {}").is_err());
+
+        assert!(Construct::for_block("for (k, v in map {}").is_err());
     }
 
     #[test]
@@ -1374,6 +1927,172 @@ mod tests {
         assert!(Construct::type_declaration("type ExtraComma(a: int, b: int,);").is_err());
     }
 
+    #[test]
+    fn t_type_declaration_generic() {
+        let dec = Construct::type_declaration("type Pair<T, U = Node<T>>(f: T, s: U);")
+            .unwrap()
+            .1;
+
+        assert_eq!(dec.generics().len(), 2);
+        assert_eq!(dec.generics()[1].to_string(), "U = Node<T>");
+
+        assert!(Construct::type_declaration("type Empty<>(a: int);").is_err());
+        assert!(Construct::type_declaration("type Comma<T,>(a: T);").is_err());
+    }
+
+    #[test]
+    fn t_generic_type_id() {
+        assert_eq!(
+            Construct::type_id("Pair<int, Node< float >>").unwrap().1,
+            TypeId::from("Pair<int, Node<float>>")
+        );
+        assert_eq!(
+            Construct::type_instantiation("Pair<int> { f = 1, s = 2 }")
+                .unwrap()
+                .1
+                .name(),
+            &TypeId::from("Pair<int>")
+        );
+    }
+
+    #[test]
+    fn t_function_type_id() {
+        assert_eq!(
+            Construct::type_id("func( int,Pair<T> ) -> func() -> bool")
+                .unwrap()
+                .1,
+            TypeId::function(
+                vec![TypeId::from("int"), TypeId::from("Pair<T>")],
+                TypeId::function(vec![], TypeId::from("bool"))
+            )
+        );
+        assert!(Construct::type_id("func(int)").is_err());
+        assert_eq!(
+            Construct::type_id("funcs<int>").unwrap().1,
+            TypeId::from("funcs<int>")
+        );
+    }
+
+    #[test]
+    fn t_array_type_id() {
+        assert_eq!(
+            Construct::type_id("[ [Pair<int, T>] ]").unwrap().1,
+            TypeId::array_of(&TypeId::array_of(&TypeId::from("Pair<int, T>")))
+        );
+        assert_eq!(
+            Construct::type_id("func([int]) -> [int]").unwrap().1,
+            TypeId::function(
+                vec![TypeId::from("[int]")],
+                TypeId::array_of(&TypeId::from("int"))
+            )
+        );
+        assert!(Construct::type_id("[int").is_err());
+    }
+
+    #[test]
+    fn t_array_literal() {
+        assert_eq!(Construct::array("[]").unwrap().1.print(), "[]");
+        assert_eq!(
+            Construct::array("[ 1,2 + 3 , ]").unwrap().1.print(),
+            "[1, 2 + 3]"
+        );
+        assert!(Construct::array("[1 2]").is_err());
+    }
+
+    #[test]
+    fn t_map_literal() {
+        assert_eq!(Construct::map("[ : ]").unwrap().1.print(), "[:]");
+        assert_eq!(
+            Construct::map("[ \"a\":1,\"b\" : 2 + 3 , ]")
+                .unwrap()
+                .1
+                .print(),
+            "[\"a\": 1, \"b\": 2 + 3]"
+        );
+        assert!(Construct::map("[]").is_err());
+        assert!(Construct::map("[1: 2, 3]").is_err());
+
+        let (input, inst) = Construct::instruction("[1: 2].get(1)").unwrap();
+        assert_eq!(input, "");
+        assert_eq!(inst.print(), "[1: 2].get(1)");
+    }
+
+    #[test]
+    fn t_index() {
+        let (input, inst) = Construct::instruction("get()[0][1 + 1]").unwrap();
+
+        assert_eq!(input, "");
+        assert_eq!(inst.print(), "get()[0][1 + 1]");
+
+        let (input, _) = Construct::instruction("values[1 - 1] = [2]").unwrap();
+        assert_eq!(input, "");
+    }
+
+    #[test]
+    fn t_try_operator() {
+        let (input, inst) = Construct::instruction("a.get(1)? + b?").unwrap();
+
+        assert_eq!(input, "");
+        assert!(inst.print().contains(")? + b"));
+        assert!(inst.print().ends_with("?"));
+    }
+
+    #[test]
+    fn t_generic_function() {
+        let dec = Construct::function_declaration("func id<T>(a: T) -> T { a }")
+            .unwrap()
+            .1;
+        assert_eq!(dec.generics().len(), 1);
+
+        let (input, call) = Construct::function_call("id<Pair<int, float>>(a)").unwrap();
+        assert_eq!(input, "");
+        assert!(call.print().starts_with("id<Pair<int, float>>("));
+    }
+
+    #[test]
+    fn t_generic_instantiation_instruction() {
+        let (input, _) = Construct::instruction("Pair<int, float> { f = 1, s = 2.0 }").unwrap();
+        assert_eq!(input, "");
+
+        let (input, _) = Construct::instruction("x { a = 1 }").unwrap();
+        assert_eq!(input, "");
+    }
+
+    #[test]
+    fn t_enum_declaration() {
+        let dec = Construct::enum_declaration(
+            "enum Shape<T> {\n    Circle(radius: T),\n    Rect(w: T, h: T),\n    Empty,\n}",
+        )
+        .unwrap()
+        .1;
+
+        assert!(dec.is_enum());
+        assert_eq!(dec.generics().len(), 1);
+        assert_eq!(dec.variants().len(), 3);
+        assert_eq!(dec.variants()[1].to_string(), "Rect(w: T, h: T)");
+        assert!(dec.variant("Empty").unwrap().fields().is_empty());
+
+        assert!(Construct::enum_declaration("enum Empty {}").is_err());
+        assert!(Construct::enum_declaration("enum NoFields { A() }").is_err());
+    }
+
+    #[test]
+    fn t_match() {
+        let (input, m) = Construct::jk_match(
+            "match s { Circle(r) => r, Rect(_, h) => { h }, Empty => 0.0, _ => 1.0 }",
+        )
+        .unwrap();
+
+        assert_eq!(input, "");
+        assert!(m.print().contains("Rect(_, h) => {"));
+        assert!(Construct::jk_match("match s { Empty => 0 }").is_ok());
+        assert!(Construct::jk_match("match s { Empty => 0, }").is_ok());
+
+        assert!(Construct::jk_match("match s {}").is_err());
+        assert!(Construct::jk_match("match s { Circle() => 0 }").is_err());
+        assert!(Construct::jk_match("match s { _(a) => 0 }").is_err());
+    }
+
     #[test]
     fn t_type_instantiation_valid() {
         assert!(Construct::type_instantiation("Custom { a = 1 }").is_ok());
@@ -1679,4 +2398,43 @@ func void() { }"##;
         // This should fail, ``5;`` is dead code because of the ``return``
         assert!(&ie.is_err());
     }
+
+    #[test]
+    fn t_break_valid() {
+        assert_eq!(Construct::jk_break("break").unwrap().1.print(), "break");
+        assert_eq!(
+            Construct::jk_break("break 4 + 2").unwrap().1.print(),
+            "break 4 + 2"
+        );
+    }
+
+    #[test]
+    fn t_continue_valid() {
+        assert!(Construct::jk_continue("continue").is_ok());
+        assert!(Construct::jk_continue("contin").is_err());
+    }
+
+    #[test]
+    fn t_break_continue_are_keywords() {
+        assert!(Construct::variable("break").is_err());
+        assert!(Construct::variable("continue").is_err());
+    }
+
+    #[test]
+    fn t_block_stmt_without_semicolon() {
+        let (input, block) =
+            Construct::block("{ if a { break; } loop { continue; } x = 1; x }").unwrap();
+
+        assert_eq!(input, "");
+        assert_eq!(block.instructions().len(), 3);
+        assert!(block.last().is_some());
+    }
+
+    #[test]
+    fn t_block_last_if_is_not_stmt() {
+        let (_, block) = Construct::block("{ if a { 1 } else { 2 } }").unwrap();
+
+        assert!(block.instructions().is_empty());
+        assert!(block.last().is_some());
+    }
 }
@@ -0,0 +1,140 @@
+//! Keeps track of the source currently being parsed, in order to compute the location
+//! of the parsed instructions. The parser works on slices of the source: The location
+//! of a slice is found by comparing its address to the address of the whole source.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::ErrSpaceLocation;
+
+/// Source code being parsed, and the information needed to compute locations in it
+struct Source {
+    /// Address of the first byte of the source
+    address: usize,
+    content: Arc<str>,
+    path: Option<Arc<PathBuf>>,
+    /// Offset of the first byte of each line
+    line_starts: Vec<usize>,
+}
+
+impl Source {
+    fn new(input: &str, path: Option<&Path>) -> Source {
+        let line_starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Source {
+            address: input.as_ptr() as usize,
+            content: Arc::from(input),
+            path: path.map(|p| Arc::new(p.to_owned())),
+            line_starts,
+        }
+    }
+
+    /// Offset of a slice in the source, if the slice is part of it
+    fn offset(&self, slice: &str) -> Option<usize> {
+        let offset = (slice.as_ptr() as usize).checked_sub(self.address)?;
+
+        match offset + slice.len() <= self.content.len() {
+            true => Some(offset),
+            false => None,
+        }
+    }
+
+    /// Line and column of an offset in the source, both starting at 1
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        let column = self.content[line_start..offset].chars().count();
+
+        (line + 1, column + 1)
+    }
+}
+
+thread_local! {
+    static SOURCE: RefCell<Option<Source>> = const { RefCell::new(None) };
+}
+
+/// Run a parsing function on an input, allowing the location of parsed instructions
+/// to be computed
+pub(crate) fn with_source<'i, T>(
+    input: &'i str,
+    path: Option<&Path>,
+    parse: impl FnOnce(&'i str) -> T,
+) -> T {
+    let previous = SOURCE.with(|s| s.replace(Some(Source::new(input, path))));
+
+    let result = parse(input);
+
+    SOURCE.with(|s| s.replace(previous));
+
+    result
+}
+
+/// Location of the text between the beginning of `input` and the beginning of
+/// `remaining`, which must both be slices of the source being parsed. Whitespaces
+/// surrounding the text are not part of the location
+pub(crate) fn location(input: &str, remaining: &str) -> Option<ErrSpaceLocation> {
+    SOURCE.with(|s| {
+        let source = s.borrow();
+        let source = source.as_ref()?;
+
+        let start = source.offset(input)?;
+        let end = source.offset(remaining)?;
+        let text = source.content.get(start..end)?;
+
+        let start = start + (text.len() - text.trim_start().len());
+        let end = start + text.trim().len();
+
+        Some(
+            ErrSpaceLocation::new(
+                source.path.clone(),
+                source.position(start),
+                source.position(end),
+            )
+            .with_source(source.content.clone()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_location_lines_and_columns() {
+        let input = "a = 1;\n  b = 2;\n";
+
+        let loc = with_source(input, None, |input| location(&input[9..], &input[15..]));
+
+        assert_eq!(
+            loc,
+            Some(ErrSpaceLocation::new(None, (2, 3), (2, 9)).with_source(Arc::from(input)))
+        );
+    }
+
+    #[test]
+    fn t_location_trims_whitespaces() {
+        let input = "   x   ";
+
+        let loc = with_source(input, None, |input| location(input, &input[7..]));
+
+        assert_eq!(
+            loc,
+            Some(ErrSpaceLocation::new(None, (1, 4), (1, 5)).with_source(Arc::from(input)))
+        );
+    }
+
+    #[test]
+    fn t_location_outside_of_source() {
+        let other = String::from("x");
+
+        let loc = with_source("x", None, |_| location(&other, &other[1..]));
+
+        assert_eq!(loc, None);
+    }
+}
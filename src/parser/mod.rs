@@ -2,19 +2,25 @@
 //! entry is created for the "main" function of the program. Including modules adds
 //! instructions to that main entry.
 
-use crate::{Context, Error, InstrKind};
+use std::path::Path;
+
+use nom::Err::{Error as NomError, Failure as NomFailure};
+
+use crate::{Context, ErrKind, Error, InstrKind, Instruction};
 
 mod box_construct;
 mod constant_construct;
 mod constructs;
+mod location;
 mod shunting_yard;
 mod tokens;
 
 pub use box_construct::BoxConstruct;
 pub use constant_construct::ConstantConstruct;
 pub use constructs::Construct;
+pub(crate) use location::with_source;
 pub use shunting_yard::ShuntingYard;
-pub use tokens::Token;
+pub use tokens::{StringFragment, Token};
 
 pub type ParseResult<T, I> = nom::IResult<T, I, Error>;
 
@@ -23,7 +29,7 @@ pub struct Parser;
 #[macro_export]
 macro_rules! jinko_ex {
     ($($t:tt) *) => {
-        $crate::Parser::parse(stringify!( $( $t ) * )).unwrap().execute().unwrap();
+        $crate::Parser::parse(stringify!( $( $t ) * ), None).execute().unwrap()
     }
 }
 
@@ -32,7 +38,7 @@ macro_rules! jinko {
     ($($t:tt) *) => {
         {
             let mut ctx = Context::new();
-            let (_, insts) = $crate::parser::Construct::many_instructions(stringify!( $( $t ) * )).unwrap();
+            let insts = $crate::parser::Parser::parse_instructions(stringify!( $( $t ) * ), None).unwrap();
             for inst in insts {
                 inst.execute(&mut ctx);
             }
@@ -44,13 +50,20 @@ macro_rules! jinko {
 
 impl Parser {
     /// Parses the entire user input and returns a hashmap corresponding to the user
-    /// program
-    pub fn parse(input: &str) -> Result<Context, Error> {
+    /// program. If the input is the content of a source file, the location of the
+    /// parsed instructions refers to that file. Syntax errors are kept in the context
+    pub fn parse(input: &str, path: Option<&Path>) -> Context {
         let mut ctx = Context::new();
 
-        let entry_block = ctx.entry_point.block_mut().unwrap();
+        let instructions = match Parser::parse_instructions(input, path) {
+            Ok(instructions) => instructions,
+            Err(errors) => {
+                errors.into_iter().for_each(|e| ctx.error(e));
+                return ctx;
+            }
+        };
 
-        let (_, instructions) = Construct::many_instructions(input)?;
+        let entry_block = ctx.entry_point.block_mut().unwrap();
 
         entry_block.set_instructions(instructions);
 
@@ -64,6 +77,197 @@ impl Parser {
             }
         }
 
-        Ok(ctx)
+        ctx
+    }
+
+    /// Parse all the instructions contained in the input. Parsing does not stop at
+    /// the first syntax error: The parser skips to the end of the erroneous statement
+    /// and keeps going, so that all the syntax errors are reported at once
+    pub(crate) fn parse_instructions(
+        input: &str,
+        path: Option<&Path>,
+    ) -> Result<Vec<Box<dyn Instruction>>, Vec<Error>> {
+        with_source(input, path, |mut input| {
+            let mut instructions = vec![];
+            let mut errors = vec![];
+
+            loop {
+                // Whitespaces and comments are not instructions, and should not be
+                // part of the location of syntax errors
+                input = Token::maybe_consume_extra(input).map_or(input, |(input, _)| input);
+                if input.is_empty() {
+                    break;
+                }
+
+                match Construct::instruction_maybe_semicolon(input) {
+                    Ok((remaining, instruction)) if remaining.len() < input.len() => {
+                        instructions.push(instruction);
+                        input = remaining;
+                    }
+                    result => {
+                        let remaining = Parser::skip_statement(input);
+
+                        let error = match result {
+                            Err(NomError(e)) | Err(NomFailure(e)) => e,
+                            _ => Error::new(ErrKind::Parsing)
+                                .with_msg(String::from("unexpected input")),
+                        };
+
+                        // The error goes from the position where parsing failed to the
+                        // end of the statement, or covers the whole statement if it
+                        // happened elsewhere, such as at the end of the input
+                        let failure = error
+                            .remaining()
+                            .filter(|&len| len > remaining.len())
+                            .and_then(|len| input.get(input.len().checked_sub(len)?..))
+                            .filter(|failure| {
+                                !failure[..failure.len() - remaining.len()].trim().is_empty()
+                            })
+                            .unwrap_or(input);
+
+                        errors.push(error.with_loc(location::location(failure, remaining)));
+                        input = remaining;
+                    }
+                }
+            }
+
+            match errors.is_empty() {
+                true => Ok(instructions),
+                false => Err(errors),
+            }
+        })
+    }
+
+    /// Skip the input until the end of the current statement: The next `;`, or the
+    /// `}` closing a block opened in the statement. Strings, characters and comments
+    /// are skipped entirely, as they might contain these delimiters
+    fn skip_statement(input: &str) -> &str {
+        let mut depth = 0usize;
+        let mut idx = 0;
+
+        while let Some(c) = input[idx..].chars().next() {
+            let rest = &input[idx..];
+
+            idx += match c {
+                '"' | '\'' => Parser::literal_len(rest, c),
+                '#' => rest.find('\n').unwrap_or(rest.len()),
+                '/' if rest.starts_with("//") => rest.find('\n').unwrap_or(rest.len()),
+                '/' if rest.starts_with("/*") => rest.find("*/").map_or(rest.len(), |end| end + 2),
+                '{' => {
+                    depth += 1;
+                    1
+                }
+                // A `}` which does not match any `{` is skipped as well
+                '}' if depth <= 1 => return &rest[1..],
+                '}' => {
+                    depth -= 1;
+                    1
+                }
+                ';' if depth == 0 => return &rest[1..],
+                c => c.len_utf8(),
+            };
+        }
+
+        &input[input.len()..]
+    }
+
+    /// Length of the string or character literal at the beginning of the input,
+    /// delimited by `quote`
+    fn literal_len(input: &str, quote: char) -> usize {
+        let mut escaped = false;
+
+        for (idx, c) in input.char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                c if c == quote => return idx + 1,
+                _ => {}
+            }
+        }
+
+        input.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsing_errors(input: &str) -> Vec<Error> {
+        match Parser::parse_instructions(input, None) {
+            Ok(_) => vec![],
+            Err(errors) => errors,
+        }
+    }
+
+    #[test]
+    fn t_parse_whole_input() {
+        let instructions = Parser::parse_instructions("a = 1; b = 2; // end\n", None);
+
+        assert_eq!(instructions.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn t_leftover_input_is_an_error() {
+        let errors = parsing_errors("a = 1;\nb = 2);\n");
+
+        assert_eq!(errors.len(), 1);
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (2, 6));
+        assert_eq!((loc.end_line, loc.end_column), (2, 8));
+    }
+
+    #[test]
+    fn t_error_until_end_of_input() {
+        let errors = parsing_errors("a = 1;\nb = 2)");
+
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.column), (2, 6));
+        assert_eq!((loc.end_line, loc.end_column), (2, 7));
+    }
+
+    #[test]
+    fn t_report_all_syntax_errors() {
+        let errors = parsing_errors("a = ;\nb = 2;\nfunc f() {\n  c = ;\n}\n d = ;");
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.loc().unwrap().line).collect();
+        assert_eq!(lines, vec![1, 4, 6]);
+    }
+
+    #[test]
+    fn t_error_inside_function_body() {
+        let errors = parsing_errors("func f() {\n  a = 1;\n  b = 2 +;\n}\n");
+
+        assert_eq!(errors.len(), 1);
+        let loc = errors[0].loc().unwrap();
+        assert_eq!((loc.line, loc.end_line), (3, 4));
+    }
+
+    #[test]
+    fn t_error_keeps_expected_token() {
+        let errors = parsing_errors("type Point(x: int, y: int;");
+
+        assert_eq!(errors[0].msg(), Some("expected `)`"));
+    }
+
+    #[test]
+    fn t_error_inside_string() {
+        let errors = parsing_errors("println(\"x }\");\nprintln(\"\\q\");");
+
+        assert_eq!(
+            errors[0].msg(),
+            Some("unmatched `}` in string, use `}}` to write a brace")
+        );
+        assert_eq!(errors[0].loc().unwrap().column, 12);
+        assert_eq!(errors[1].msg(), Some("unknown escape sequence `\\q`"));
+    }
+
+    #[test]
+    fn t_skip_statement() {
+        assert_eq!(Parser::skip_statement("a = ; b = 2;"), " b = 2;");
+        assert_eq!(Parser::skip_statement("f() { g(); } h();"), " h();");
+        assert_eq!(Parser::skip_statement("} a = 1;"), " a = 1;");
+        assert_eq!(Parser::skip_statement("s = \"; }\"; t = 1;"), " t = 1;");
+        assert_eq!(Parser::skip_statement("x // ; \n y;"), "");
     }
 }
@@ -1,9 +1,9 @@
 //! ShuntingYard parses operators and operands according to operator precedence,
-//! returning a BinaryOp in the end
+//! returning a BinaryOp or a UnaryOp in the end
 
 use crate::error::{ErrKind, Error};
-use crate::instruction::{BinaryOp, Instruction, Operator};
-use crate::parser::{BoxConstruct, Construct, ParseResult, Token};
+use crate::instruction::{BinaryOp, Index, Instruction, Operator, Try, UnaryOp};
+use crate::parser::{location, BoxConstruct, Construct, ParseResult, Token};
 use crate::utils::{Queue, Stack};
 
 use nom::branch::alt;
@@ -21,26 +21,63 @@ enum SyPair {
 pub struct ShuntingYard {
     operators: Stack<Operator>,
     output: Queue<SyPair>,
+    /// Number of parentheses opened and not closed yet
+    open_parentheses: usize,
+    /// Has an operator, other than parentheses, been found in the input
+    has_operator: bool,
+    /// Is the next token expected to be an operand
+    expect_operand: bool,
 }
 
 impl ShuntingYard {
     fn operator<'i>(&mut self, input: &'i str) -> ParseResult<&'i str, ()> {
         let (input, _) = Token::maybe_consume_extra(input)?;
 
+        // Operators starting with the same character as another operator need to be
+        // tried first
         let (input, op) = alt((
             Token::add,
             Token::sub,
             Token::mul,
             Token::div,
+            Token::modulo,
+            Token::equals,
+            Token::not_equals,
+            Token::lt_eq,
+            Token::gt_eq,
+            Token::lt,
+            Token::gt,
+            Token::and,
+            Token::or,
+            Token::not,
             Token::left_parenthesis,
             Token::right_parenthesis,
         ))(input)?;
 
         let (input, _) = Token::maybe_consume_extra(input)?;
 
-        let op = Operator::new(op);
+        let op = match Operator::new(op) {
+            // A minus sign in place of an operand negates it
+            Operator::Sub if self.expect_operand => Operator::Neg,
+            op => op,
+        };
+
+        if op.is_unary() {
+            // Unary operators are prefixes, and can only appear where an operand is
+            // expected. They do not pop anything since they apply to the next operand
+            if !self.expect_operand {
+                return Err(NomError(
+                    Error::new(ErrKind::Parsing)
+                        .with_msg(format!("unexpected unary operator: {}", op.as_str())),
+                ));
+            }
+
+            self.has_operator = true;
+            self.operators.push(op);
+        } else if op != Operator::LeftParenthesis && op != Operator::RightParenthesis {
+            self.has_operator = true;
+            self.expect_operand = true;
 
-        if op != Operator::LeftParenthesis && op != Operator::RightParenthesis {
             while !self.operators.is_empty()
                 && (self.operators.peek().unwrap().precedence() > op.precedence()
                     || (self.operators.peek().unwrap().precedence() == op.precedence()
@@ -54,8 +91,13 @@ impl ShuntingYard {
 
             self.operators.push(op);
         } else if op == Operator::LeftParenthesis {
+            self.open_parentheses += 1;
+            self.expect_operand = true;
             self.operators.push(op);
         } else if op == Operator::RightParenthesis {
+            self.open_parentheses -= 1;
+            self.expect_operand = false;
+
             while self.operators.peek() != Some(&Operator::LeftParenthesis) {
                 match self.operators.pop() {
                     None => {
@@ -77,15 +119,39 @@ impl ShuntingYard {
         Ok((input, ()))
     }
 
+    /// Parse an operand, which can be indexed and followed by the `?` operator
     fn operand<'i>(&mut self, input: &'i str) -> ParseResult<&'i str, ()> {
-        let (input, expr) = alt((
+        let start = input;
+        let (mut input, mut expr) = alt((
+            Construct::propagated_type_instantiation,
             BoxConstruct::method_call,
             BoxConstruct::function_call,
             Construct::constant,
             BoxConstruct::variable,
+            BoxConstruct::map,
+            BoxConstruct::array,
         ))(input)?;
 
+        loop {
+            let (next, _) = Token::maybe_consume_extra(input)?;
+
+            if let Ok((next, index)) = Construct::index(next) {
+                expr = Box::new(Index::new(expr, index));
+                input = next;
+            } else if let Ok((next, _)) = Token::question_mark(input) {
+                expr = Box::new(Try::new(expr));
+                input = next;
+            } else {
+                break;
+            }
+        }
+
+        if let Some(loc) = location::location(start, input) {
+            expr.set_location(loc);
+        }
+
         self.output.push(SyPair::Num(expr));
+        self.expect_operand = false;
 
         Ok((input, ()))
     }
@@ -101,8 +167,29 @@ impl ShuntingYard {
                 ));
             }
             Some(c) => {
-                // Return early if a finishing character is found
-                if c == '}' || c == ';' {
+                // A block following an operand ends the binary expression. However, a
+                // single operand followed by a block is not a binary expression, but
+                // could be a type instantiation for example
+                let ends_with_block = c == '{' && !self.expect_operand;
+
+                if ends_with_block && !self.has_operator {
+                    return Err(NomError(
+                        Error::new(ErrKind::Parsing)
+                            .with_msg(String::from("not a valid binary expression")),
+                    ));
+                }
+
+                // Return early if a finishing character is found. A closing
+                // parenthesis which was never opened belongs to an enclosing construct,
+                // such as a function call
+                if c == '}'
+                    || c == ']'
+                    || c == ':'
+                    || c == ';'
+                    || ends_with_block
+                    || c == ','
+                    || (c == ')' && self.open_parentheses == 0)
+                {
                     return Err(NomFailure(
                         Error::new(ErrKind::Parsing)
                             .with_msg(String::from("finished binary expression")),
@@ -126,6 +213,9 @@ impl ShuntingYard {
         ShuntingYard {
             operators: Stack::new(),
             output: Queue::new(),
+            open_parentheses: 0,
+            has_operator: false,
+            expect_operand: true,
         }
     }
 
@@ -138,14 +228,25 @@ impl ShuntingYard {
                 SyPair::Num(num) => {
                     stack.push(num);
                 }
-                SyPair::Op(op) => {
-                    if let Some(rhs) = stack.pop() {
-                        if let Some(lhs) = stack.pop() {
-                            stack.push(Box::new(BinaryOp::new(lhs, rhs, op)));
-                            continue;
-                        }
+                SyPair::Op(op) if op.is_unary() => match stack.pop() {
+                    Some(operand) => stack.push(Box::new(UnaryOp::new(operand, op))),
+                    // A unary operator at the end of the input has nothing to apply to
+                    None => {
+                        return Err(NomFailure(
+                            Error::new(ErrKind::Parsing)
+                                .with_msg(String::from("missing operand for unary operator")),
+                        ))
                     }
-                }
+                },
+                SyPair::Op(op) => match (stack.pop(), stack.pop()) {
+                    (Some(rhs), Some(lhs)) => stack.push(Box::new(BinaryOp::new(lhs, rhs, op))),
+                    _ => {
+                        return Err(NomFailure(
+                            Error::new(ErrKind::Parsing)
+                                .with_msg(String::from("missing operand for binary operator")),
+                        ))
+                    }
+                },
             }
         }
 
@@ -212,6 +313,12 @@ impl ShuntingYard {
             }
         }
 
+        if sy.open_parentheses > 0 {
+            return Err(NomFailure(
+                Error::new(ErrKind::Parsing).with_msg(String::from("unclosed parenthesis")),
+            ));
+        }
+
         // We are done, pop everything from the operator stack
         while !sy.operators.is_empty() {
             sy.output.push(SyPair::Op(sy.operators.pop().unwrap()));
@@ -302,10 +409,115 @@ mod tests {
     fn t_sy_valid_extremely_complex_expr() {
         sy_assert(
             "1 + 4 * 2 - 1 + 2 * (14 + (2 - 17) * 1) - 12 + 3 / 2",
-            1 + 4 * 2 - 1 + 2 * (14 + (2 - 17) * 1) - 12 + 3 / 2,
+            #[allow(clippy::identity_op)]
+            {
+                1 + 4 * 2 - 1 + 2 * (14 + (2 - 17) * 1) - 12 + 3 / 2
+            },
+        );
+    }
+
+    fn sy_assert_bool(input: &str, result: bool) {
+        use crate::instance::ToObjectInstance;
+        use crate::Context;
+
+        let output = ShuntingYard::parse(input).unwrap().1;
+
+        let mut i = Context::new();
+
+        assert_eq!(
+            output.execute(&mut i).unwrap(),
+            JkBool::from(result).to_instance()
         );
     }
 
+    #[test]
+    fn t_sy_comparison_priority() {
+        sy_assert_bool("1 + 2 == 3", true);
+        sy_assert_bool("2 * 3 < 5 + 1", false);
+        sy_assert_bool("2 * 3 <= 5 + 1", true);
+        sy_assert_bool("1 < 2 == 3 > 4", false);
+    }
+
+    #[test]
+    fn t_sy_comparison_operators() {
+        sy_assert_bool("4 != 4", false);
+        sy_assert_bool("4 >= 4", true);
+        sy_assert_bool("4.5 > 4.2", true);
+        sy_assert_bool("'a' < 'b'", true);
+        sy_assert_bool("\"jinko\" == \"jinko\"", true);
+        sy_assert_bool("true != false", true);
+    }
+
+    #[test]
+    fn t_sy_stops_at_delimiters() {
+        let (input, _) = ShuntingYard::parse("1 + 2, 3").unwrap();
+        assert_eq!(input, ", 3");
+
+        let (input, _) = ShuntingYard::parse("(1 + 2) * 3) + 4").unwrap();
+        assert_eq!(input, ") + 4");
+
+        let (input, _) = ShuntingYard::parse("a == 2 { }").unwrap();
+        assert_eq!(input, "{ }");
+    }
+
+    #[test]
+    fn t_sy_single_operand_before_block() {
+        assert!(ShuntingYard::parse("Point { x = 1 }").is_err());
+    }
+
+    #[test]
+    fn t_sy_logical_priority() {
+        sy_assert_bool("true || false && false", true);
+        sy_assert_bool("1 < 2 && 3 > 4", false);
+        sy_assert_bool("1 == 2 || 3 != 4", true);
+        sy_assert_bool("(true || false) && false", false);
+    }
+
+    #[test]
+    fn t_sy_unary_not() {
+        use crate::instance::ToObjectInstance;
+        use crate::Context;
+
+        let output = ShuntingYard::parse("!true").unwrap().1;
+        assert!(output.downcast_ref::<UnaryOp>().is_some());
+
+        let output = ShuntingYard::parse("!!false").unwrap().1;
+        assert_eq!(
+            output.execute(&mut Context::new()).unwrap(),
+            JkBool::from(false).to_instance()
+        );
+
+        sy_assert_bool("!true || true", true);
+        sy_assert_bool("!(true || true)", false);
+        sy_assert_bool("true && !false", true);
+    }
+
+    #[test]
+    fn t_sy_modulo_priority() {
+        sy_assert("7 % 3 * 2", 2);
+        sy_assert("1 + 7 % 4", 4);
+    }
+
+    #[test]
+    fn t_sy_unary_negation() {
+        sy_assert("-2 * 3", -6);
+        sy_assert("5 - -3", 8);
+        sy_assert("4 -3", 1);
+        sy_assert("-(1 + 2) * 2", -6);
+        sy_assert("2 * -(1 - 4)", 6);
+    }
+
+    #[test]
+    fn t_sy_unary_not_in_operator_position() {
+        assert!(ShuntingYard::parse("true ! false").is_err());
+    }
+
+    #[test]
+    fn t_sy_dangling_unary_not() {
+        assert!(ShuntingYard::parse("!").is_err());
+        assert!(ShuntingYard::parse("true && !").is_err());
+    }
+
     #[test]
     fn t_sy_eager_consume_error() {
         // https://github.com/CohenArthur/jinko/issues/172
@@ -3,33 +3,53 @@
 //! and so on. This module consists of a lot of uninteresting helper/wrapper functions
 
 use nom::{
-    branch::alt, bytes::complete::is_not, bytes::complete::tag, bytes::complete::take_until,
-    bytes::complete::take_while, bytes::complete::take_while1, character::complete::anychar,
-    character::complete::char, character::is_alphabetic, character::is_alphanumeric,
-    character::is_digit, combinator::opt, combinator::peek, multi::many0, sequence::delimited,
-    sequence::pair,
+    branch::alt, bytes::complete::tag, bytes::complete::take_until, bytes::complete::take_while,
+    bytes::complete::take_while1, character::complete::anychar, character::complete::char,
+    character::is_alphabetic, character::is_alphanumeric, character::is_digit, combinator::not,
+    combinator::opt, combinator::peek, multi::many0, sequence::delimited, sequence::pair,
 };
 
 use crate::{parser::ParseResult, ErrKind, Error};
 use nom::Err::Error as NomError;
 
 /// Reserved Keywords by jinko
-const RESERVED_KEYWORDS: [&str; 14] = [
+const RESERVED_KEYWORDS: [&str; 18] = [
     "func", "test", "mock", "type", "ext", "for", "while", "loop", "mut", "true", "false", "incl",
-    "as", "return",
+    "as", "return", "break", "continue", "enum", "match",
 ];
 
-const OPERATORS: [&str; 6] = ["+", "-", "*", "/", "(", ")"];
+const OPERATORS: [&str; 16] = [
+    "+", "-", "*", "/", "%", "==", "!=", "<=", ">=", "<", ">", "&&", "||", "!", "(", ")",
+];
+
+/// Part of a string literal
+#[derive(Debug, PartialEq)]
+pub enum StringFragment {
+    /// A character of the string, with escape sequences already replaced
+    Char(char),
+    /// The opening brace of an interpolated expression
+    Interpolation,
+    /// The closing double quote
+    End,
+}
 
 pub struct Token;
 
 impl Token {
+    /// Error produced when the input does not start with the expected token
+    fn expected(input: &str, token: &str) -> nom::Err<Error> {
+        NomError(
+            Error::new(ErrKind::Parsing)
+                .with_msg(format!("expected `{}`", token))
+                .with_remaining(input),
+        )
+    }
+
     /// Function used to recognize a specific character such as '[' or '>'. A function
     /// calling this is specifically trying to recognize the given character
     fn specific_char(input: &str, character: char) -> ParseResult<&str, char> {
-        let c = char::<&str, Error>(character)(input)?;
-
-        Ok(c)
+        char::<&str, Error>(character)(input)
+            .map_err(|_| Token::expected(input, &character.to_string()))
     }
 
     /// Match a simple token. No rules apply to the characters following it, unlike
@@ -38,9 +58,7 @@ impl Token {
         input: &'input str,
         token: &'input str,
     ) -> ParseResult<&'input str, &'input str> {
-        let tok = tag::<&str, &str, Error>(token)(input)?;
-
-        Ok(tok)
+        tag::<&str, &str, Error>(token)(input).map_err(|_| Token::expected(input, token))
     }
 
     /// Function used to recognize a specific string token such as "func" or "ext"
@@ -50,7 +68,7 @@ impl Token {
         input: &'tok str,
         token: &'tok str,
     ) -> ParseResult<&'tok str, &'tok str> {
-        let (input, tag) = tag::<&str, &str, Error>(token)(input)?;
+        let (input, tag) = Token::token(input, token)?;
         match input.len() {
             0 => Ok((input, tag)),
             _ => {
@@ -60,7 +78,9 @@ impl Token {
                     char('\n'),
                     char(' '),
                     char('{'),
+                    char('}'),
                     char(')'),
+                    char(','),
                     char(';'),
                 )))(input)?;
                 Ok((input, tag))
@@ -76,8 +96,12 @@ impl Token {
         Token::specific_char(input, '"')
     }
 
+    /// Parse a single equal sign, which is not the start of `==` or `=>`
     pub fn equal(input: &str) -> ParseResult<&str, char> {
-        Token::specific_char(input, '=')
+        let (input, equal) = Token::specific_char(input, '=')?;
+        let (input, _) = not(alt((char('='), char('>'))))(input)?;
+
+        Ok((input, equal))
     }
 
     pub fn comma(input: &str) -> ParseResult<&str, char> {
@@ -92,11 +116,11 @@ impl Token {
         Token::specific_char(input, '}')
     }
 
-    pub fn _left_bracket(input: &str) -> ParseResult<&str, char> {
+    pub fn left_bracket(input: &str) -> ParseResult<&str, char> {
         Token::specific_char(input, '[')
     }
 
-    pub fn _right_bracket(input: &str) -> ParseResult<&str, char> {
+    pub fn right_bracket(input: &str) -> ParseResult<&str, char> {
         Token::specific_char(input, ']')
     }
 
@@ -108,6 +132,10 @@ impl Token {
         Token::specific_char(input, ';')
     }
 
+    pub fn question_mark(input: &str) -> ParseResult<&str, char> {
+        Token::specific_char(input, '?')
+    }
+
     pub fn at_sign(input: &str) -> ParseResult<&str, char> {
         Token::specific_char(input, '@')
     }
@@ -116,6 +144,12 @@ impl Token {
         Token::specific_token(input, "func")
     }
 
+    /// The `func` keyword of function types, which is directly followed by the
+    /// arguments of the type: `func(int) -> int`
+    pub fn func_type_tok(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "func")
+    }
+
     pub fn ext_tok(input: &str) -> ParseResult<&str, &str> {
         Token::specific_token(input, "ext")
     }
@@ -160,10 +194,26 @@ impl Token {
         Token::specific_token(input, "return")
     }
 
+    pub fn break_tok(input: &str) -> ParseResult<&str, &str> {
+        Token::specific_token(input, "break")
+    }
+
+    pub fn continue_tok(input: &str) -> ParseResult<&str, &str> {
+        Token::specific_token(input, "continue")
+    }
+
     pub fn _type_tok(input: &str) -> ParseResult<&str, &str> {
         Token::specific_token(input, "type")
     }
 
+    pub fn enum_tok(input: &str) -> ParseResult<&str, &str> {
+        Token::specific_token(input, "enum")
+    }
+
+    pub fn match_tok(input: &str) -> ParseResult<&str, &str> {
+        Token::specific_token(input, "match")
+    }
+
     pub fn incl_tok(input: &str) -> ParseResult<&str, &str> {
         Token::specific_token(input, "incl")
     }
@@ -189,6 +239,46 @@ impl Token {
         Token::token(input, "/")
     }
 
+    pub fn modulo(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "%")
+    }
+
+    pub fn equals(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "==")
+    }
+
+    pub fn not_equals(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "!=")
+    }
+
+    pub fn lt_eq(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "<=")
+    }
+
+    pub fn gt_eq(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, ">=")
+    }
+
+    pub fn lt(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "<")
+    }
+
+    pub fn gt(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, ">")
+    }
+
+    pub fn and(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "&&")
+    }
+
+    pub fn or(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "||")
+    }
+
+    pub fn not(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "!")
+    }
+
     pub fn left_parenthesis(input: &str) -> ParseResult<&str, &str> {
         Token::token(input, "(")
     }
@@ -217,6 +307,15 @@ impl Token {
         Token::specific_token(input, "->")
     }
 
+    pub fn fat_arrow(input: &str) -> ParseResult<&str, &str> {
+        Token::token(input, "=>")
+    }
+
+    /// Parse the wildcard `_`, which is not a valid identifier on its own
+    pub fn wildcard(input: &str) -> ParseResult<&str, char> {
+        Token::specific_char(input, '_')
+    }
+
     pub fn comment_multi_start(input: &str) -> ParseResult<&str, &str> {
         let comment = tag("/*")(input)?;
 
@@ -347,20 +446,119 @@ impl Token {
     /// Parse a single character constant and return the character inside the quotes
     pub fn char_constant(input: &str) -> ParseResult<&str, char> {
         let (input, _) = Token::single_quote(input)?;
-        let (input, character) = anychar(input)?;
+        let (input, character) = match anychar(input)? {
+            (input, '\\') => Token::escape_sequence(input)?,
+            (_, '\'') => {
+                return Err(NomError(
+                    Error::new(ErrKind::Parsing).with_msg(String::from("empty character")),
+                ))
+            }
+            (input, c) => (input, c),
+        };
         let (input, _) = Token::single_quote(input)?;
 
-        // FIXME: Handle escaping as well
-
         Ok((input, character))
     }
 
-    /// Parse a string constant and return the characters between the double quotes
-    pub fn string_constant(input: &str) -> ParseResult<&str, &str> {
-        // FIXME: This does not allow for string escaping yet
-        let string = delimited(Token::double_quote, is_not("\""), Token::double_quote)(input)?;
+    /// Parse a string constant and return the characters between the double quotes,
+    /// with their escape sequences replaced. Strings containing interpolations are not
+    /// constants, and are not accepted
+    pub fn string_constant(input: &str) -> ParseResult<&str, String> {
+        let (mut input, _) = Token::double_quote(input)?;
+        let mut string = String::new();
+
+        loop {
+            let (next, fragment) = Token::string_fragment(input)?;
+
+            match fragment {
+                StringFragment::Char(c) => string.push(c),
+                StringFragment::End => return Ok((next, string)),
+                StringFragment::Interpolation => {
+                    return Err(NomError(Error::new(ErrKind::Parsing).with_msg(
+                        String::from("string containing interpolations is not a constant"),
+                    )))
+                }
+            }
+
+            input = next;
+        }
+    }
+
+    /// Parse the next fragment of a string, after its opening double quote. Braces
+    /// delimit interpolations, and are escaped by doubling them
+    pub fn string_fragment(start: &str) -> ParseResult<&str, StringFragment> {
+        let (input, c) = anychar(start)?;
+
+        match c {
+            '"' => Ok((input, StringFragment::End)),
+            '\\' => {
+                let (input, c) = Token::escape_sequence(input)?;
+                Ok((input, StringFragment::Char(c)))
+            }
+            '{' => match Token::left_curly_bracket(input) {
+                Ok((input, c)) => Ok((input, StringFragment::Char(c))),
+                Err(_) => Ok((input, StringFragment::Interpolation)),
+            },
+            '}' => match Token::right_curly_bracket(input) {
+                Ok((input, c)) => Ok((input, StringFragment::Char(c))),
+                Err(_) => Err(NomError(
+                    Error::new(ErrKind::Parsing)
+                        .with_msg(String::from(
+                            "unmatched `}` in string, use `}}` to write a brace",
+                        ))
+                        .with_remaining(start),
+                )),
+            },
+            c => Ok((input, StringFragment::Char(c))),
+        }
+    }
 
-        Ok(string)
+    /// Parse the escape sequence following a backslash in a string or character
+    /// constant, and return the character it represents
+    fn escape_sequence(start: &str) -> ParseResult<&str, char> {
+        let (input, c) = anychar(start)?;
+
+        let escaped = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' | '"' | '\'' => c,
+            'u' => return Token::unicode_escape(input),
+            c => {
+                return Err(NomError(
+                    Error::new(ErrKind::Parsing)
+                        .with_msg(format!("unknown escape sequence `\\{}`", c))
+                        .with_remaining(start),
+                ))
+            }
+        };
+
+        Ok((input, escaped))
+    }
+
+    /// Parse the `{XXXX}` part of a unicode escape sequence: One to six hexadecimal
+    /// digits giving the code point of the character
+    fn unicode_escape(start: &str) -> ParseResult<&str, char> {
+        let (input, digits) = delimited(
+            Token::left_curly_bracket,
+            take_while1(|c: char| c.is_ascii_hexdigit()),
+            Token::right_curly_bracket,
+        )(start)?;
+
+        let code = match digits.len() {
+            1..=6 => u32::from_str_radix(digits, 16).ok(),
+            _ => None,
+        };
+
+        match code.and_then(std::char::from_u32) {
+            Some(c) => Ok((input, c)),
+            None => Err(NomError(
+                Error::new(ErrKind::Parsing)
+                    .with_msg(format!("invalid unicode escape `\\u{{{}}}`", digits))
+                    .with_remaining(start),
+            )),
+        }
     }
 
     fn is_whitespace(c: char) -> bool {
@@ -436,24 +634,75 @@ mod tests {
     fn t_char_constant_valid() {
         assert_eq!(Token::char_constant("'a'"), Ok(("", 'a')));
         assert_eq!(Token::char_constant("'9'"), Ok(("", '9')));
+    }
 
-        // FIXME: Add escaping
+    #[test]
+    fn t_char_constant_escaped() {
+        assert_eq!(Token::char_constant("'\\n'"), Ok(("", '\n')));
+        assert_eq!(Token::char_constant("'\\''"), Ok(("", '\'')));
+        assert_eq!(Token::char_constant("'\\\\'"), Ok(("", '\\')));
+        assert_eq!(Token::char_constant("'\\u{e9}'"), Ok(("", 'é')));
     }
 
     #[test]
     fn t_char_constant_invalid() {
         // Multiple characters
         assert!(Token::char_constant("'abc'").is_err());
+
+        assert!(Token::char_constant("''").is_err());
+        assert!(Token::char_constant("'\\q'").is_err());
     }
 
     #[test]
     fn t_string_constant() {
         // Simple string
-        assert_eq!(Token::string_constant("\"a str\""), Ok(("", "a str")));
-        assert_eq!(Token::string_constant("\"999 89 9\""), Ok(("", "999 89 9")));
-        assert_eq!(Token::string_constant("\"4.01f\""), Ok(("", "4.01f")));
+        assert_eq!(
+            Token::string_constant("\"a str\""),
+            Ok(("", "a str".to_string()))
+        );
+        assert_eq!(
+            Token::string_constant("\"999 89 9\""),
+            Ok(("", "999 89 9".to_string()))
+        );
+        assert_eq!(
+            Token::string_constant("\"4.01f\""),
+            Ok(("", "4.01f".to_string()))
+        );
+        assert_eq!(Token::string_constant("\"\""), Ok(("", String::new())));
+    }
+
+    #[test]
+    fn t_string_constant_escaped() {
+        assert_eq!(
+            Token::string_constant(r#""a\tb\nc\r\0""#),
+            Ok(("", "a\tb\nc\r\0".to_string()))
+        );
+        assert_eq!(
+            Token::string_constant(r#""say \"hi\" \\o/""#),
+            Ok(("", "say \"hi\" \\o/".to_string()))
+        );
+        assert_eq!(
+            Token::string_constant(r#""\u{1F600}\u{41}""#),
+            Ok(("", "\u{1F600}A".to_string()))
+        );
+    }
 
-        // FIXME: Fix string escaping
+    #[test]
+    fn t_string_constant_braces() {
+        assert_eq!(
+            Token::string_constant(r#""{{a}} }}""#),
+            Ok(("", "{a} }".to_string()))
+        );
+        assert!(Token::string_constant(r#""{a}""#).is_err());
+        assert!(Token::string_constant(r#""a}""#).is_err());
+    }
+
+    #[test]
+    fn t_string_constant_invalid_escape() {
+        assert!(Token::string_constant(r#""\q""#).is_err());
+        assert!(Token::string_constant(r#""\u{110000}""#).is_err());
+        assert!(Token::string_constant(r#""\u{}""#).is_err());
+        assert!(Token::string_constant(r#""\""#).is_err());
     }
 
     #[test]
@@ -557,6 +806,14 @@ mod tests {
         assert_eq!(Token::loop_tok("loop{}"), Ok(("{}", "loop")));
     }
 
+    #[test]
+    fn t_equal_is_not_arrow_or_comparison() {
+        assert_eq!(Token::equal("= 1"), Ok((" 1", '=')));
+        assert!(Token::equal("=> 1").is_err());
+        assert!(Token::equal("== 1").is_err());
+        assert_eq!(Token::fat_arrow("=> 1"), Ok((" 1", "=>")));
+    }
+
     #[test]
     fn t_dot_token() {
         assert_eq!(Token::dot("."), Ok(("", ".")));
@@ -8,34 +8,9 @@ use std::path::PathBuf;
 use linefeed::{DefaultTerminal, Interface, ReadResult};
 
 use crate::args::Args;
-use crate::{
-    parser::Construct, Context, Error, FromObjectInstance, Instruction, InteractResult, JkConstant,
-    ObjectInstance,
-};
-
-// FIXME:
-// - Is Display really how we want to go about it?
-// - Cleanup the code
-// - This should not be here
-impl std::fmt::Display for ObjectInstance {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self.ty() {
-                Some(ty) => match ty.name() {
-                    "int" => JkConstant::<i64>::from_instance(self).print(),
-                    "float" => JkConstant::<f64>::from_instance(self).print(),
-                    "char" => JkConstant::<char>::from_instance(self).print(),
-                    "string" => JkConstant::<String>::from_instance(self).print(),
-                    "bool" => JkConstant::<bool>::from_instance(self).print(),
-                    _ => self.as_string(),
-                },
-                None => format!(""),
-            }
-        )
-    }
-}
+use crate::InteractResult;
+use jinko::parser::Construct;
+use jinko::{Context, Error, Instruction, TypeCtx};
 
 pub struct Repl<'args> {
     args: &'args Args,
@@ -56,7 +31,7 @@ impl<'args> Repl<'args> {
         }
     }
 
-    pub fn new(args: &Args) -> std::io::Result<Repl> {
+    pub fn new(args: &Args) -> std::io::Result<Repl<'_>> {
         Ok(Repl {
             args,
             ctx: None,
@@ -75,21 +50,34 @@ impl<'args> Repl<'args> {
         ctx.set_debug(args.debug());
         ctx.set_path(Some(PathBuf::from("repl")));
 
+        // Do not execute an ill-typed script, but still let the user interact with
+        // the context
+        ctx.type_check();
+        if ctx.has_errors() {
+            ctx.emit_errors();
+            ctx.clear_errors();
+            return;
+        }
+
         let ep = ctx.entry_point.block().unwrap().clone();
-        ep.instructions().iter().for_each(|inst| {
+        for inst in ep.instructions() {
             inst.execute(ctx);
-        });
-        if let Some(last) = ep.last() {
+
+            // A `return` stops the execution of the script
+            if ctx.control_flow().is_some() {
+                break;
+            }
+        }
+        if let (Some(last), None) = (ep.last(), ctx.control_flow()) {
             last.execute(ctx);
         }
+
+        ctx.take_control_flow();
     }
 
     /// Launch the REPL
     pub fn launch(self) -> InteractResult {
-        let mut ctx = match self.ctx {
-            Some(ctx) => ctx,
-            None => Context::new(),
-        };
+        let mut ctx = self.ctx.unwrap_or_default();
 
         Repl::setup_context(self.args, &mut ctx);
 
@@ -104,14 +92,18 @@ impl<'args> Repl<'args> {
                 }
             };
 
-            let inst = match inst {
+            let mut inst = match inst {
                 Some(i) => i,
                 None => continue,
             };
 
-            if let Some(result) = inst.execute(&mut ctx) {
-                println!("{}", result);
-            };
+            inst.type_of(&mut TypeCtx::new(&mut ctx));
+
+            if !ctx.has_errors() {
+                if let Some(result) = inst.execute(&mut ctx) {
+                    println!("{}", result);
+                };
+            }
 
             ctx.emit_errors();
             ctx.clear_errors();
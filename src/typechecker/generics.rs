@@ -0,0 +1,142 @@
+//! Generic declarations are instantiated by binding each of their type parameters to a
+//! type. The types are either given explicitly, as in `Pair<int, float> { ... }`,
+//! inferred from the types of the values given to the declaration, or taken from the
+//! defaults of the parameters.
+
+use std::collections::HashMap;
+
+use crate::instruction::{TypeId, TypeParam};
+use crate::{ErrKind, Error};
+
+/// Types bound to the type parameters of a declaration, by name
+pub type Bindings = HashMap<String, TypeId>;
+
+/// Bind the type parameters of the declaration named `name` to explicitly given types,
+/// in order. The parameters which are not given get their default type
+pub fn bind(name: &str, params: &[TypeParam], types: &[TypeId]) -> Result<Bindings, Error> {
+    if types.len() > params.len() {
+        return Err(Error::new(ErrKind::TypeChecker).with_msg(format!(
+            "too many generic arguments for `{}`: expected at most {}, got {}",
+            name,
+            params.len(),
+            types.len()
+        )));
+    }
+
+    let mut bindings = params
+        .iter()
+        .zip(types)
+        .map(|(param, ty)| (param.name().to_string(), ty.clone()))
+        .collect();
+
+    complete(name, params, &mut bindings)?;
+
+    Ok(bindings)
+}
+
+/// Infer the types bound to type parameters by matching a declared type, which may
+/// contain type parameters, with the type of the value given in its place. Parameters
+/// which are already bound keep their type: Conflicting types are reported when
+/// checking the values against the substituted declaration
+pub fn infer(params: &[TypeParam], declared: &TypeId, found: &TypeId, bindings: &mut Bindings) {
+    let is_param =
+        declared.generics().is_empty() && params.iter().any(|param| param.name() == declared.id());
+
+    if is_param {
+        bindings
+            .entry(declared.id().to_string())
+            .or_insert_with(|| found.clone());
+    } else if declared.id() == found.id() && declared.generics().len() == found.generics().len() {
+        declared
+            .generics()
+            .iter()
+            .zip(found.generics())
+            .for_each(|(declared, found)| infer(params, declared, found, bindings));
+    }
+}
+
+/// Bind the type parameters which are still unbound to their default type. Defaults
+/// can refer to the parameters declared before them
+pub fn complete(name: &str, params: &[TypeParam], bindings: &mut Bindings) -> Result<(), Error> {
+    for param in params {
+        if bindings.contains_key(param.name()) {
+            continue;
+        }
+
+        match param.default() {
+            Some(default) => {
+                let ty = default.substitute(bindings);
+                bindings.insert(param.name().to_string(), ty);
+            }
+            None => {
+                return Err(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                    "cannot infer type parameter `{}` of `{}`",
+                    param.name(),
+                    name
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Types bound to the parameters, in the order of the parameters
+pub fn bound_types(params: &[TypeParam], bindings: &Bindings) -> Vec<TypeId> {
+    params
+        .iter()
+        .map(|param| {
+            bindings
+                .get(param.name())
+                .cloned()
+                .unwrap_or_else(|| TypeId::from(param.name()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> Vec<TypeParam> {
+        vec![
+            TypeParam::new(String::from("T"), None),
+            TypeParam::new(String::from("U"), Some(TypeId::from("[T]"))),
+        ]
+    }
+
+    #[test]
+    fn t_bind_defaults() {
+        let bindings = bind("Pair", &params(), &[TypeId::from("int")]).unwrap();
+
+        assert_eq!(
+            bound_types(&params(), &bindings),
+            vec![TypeId::from("int"), TypeId::from("[int]")]
+        );
+        assert!(bind("Pair", &params(), &[]).is_err());
+        assert!(bind("Pair", &params(), &vec![TypeId::from("int"); 3]).is_err());
+    }
+
+    #[test]
+    fn t_infer_nested() {
+        let mut bindings = Bindings::new();
+
+        infer(
+            &params(),
+            &TypeId::from("Node<[T], U>"),
+            &TypeId::from("Node<[Pair<char>], float>"),
+            &mut bindings,
+        );
+        infer(
+            &params(),
+            &TypeId::from("T"),
+            &TypeId::from("int"),
+            &mut bindings,
+        );
+
+        assert_eq!(
+            bound_types(&params(), &bindings),
+            vec![TypeId::from("Pair<char>"), TypeId::from("float")]
+        );
+    }
+}
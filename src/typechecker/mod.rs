@@ -0,0 +1,943 @@
+//! The typechecker runs over a parsed `Context` before it gets executed. It resolves
+//! the type of every instruction and reports mismatched argument types, wrong return
+//! types or unknown types as `ErrKind::TypeChecker` errors. The typechecker never
+//! executes code: it only looks at declarations and at the types they produce.
+//!
+//! Type resolution is conservative. If the type of an instruction cannot be found, for
+//! example when an error was already reported for one of its operands, then it is
+//! `CheckedType::Unknown` and no error is emitted. Only known, conflicting types are
+//! reported. Calls to functions declared without a return type are `CheckedType::Void`.
+
+use std::collections::{HashMap, HashSet, LinkedList};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::builtins::Builtin;
+use crate::error::ErrSpaceLocation;
+use crate::instruction::{
+    DecArg, FunctionDec, FunctionKind, Instruction, TypeDec, TypeId, TypeParam,
+};
+use crate::{Context, ErrKind, Error};
+
+pub(crate) mod generics;
+
+/// The type of an instruction, as resolved by the typechecker
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckedType {
+    /// The instruction is an expression of a known type
+    Resolved(TypeId),
+    /// The instruction is a statement and does not produce an instance
+    Void,
+    /// The type of the instruction cannot be determined before execution
+    Unknown,
+}
+
+impl Display for CheckedType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            CheckedType::Resolved(ty) => write!(f, "{}", ty),
+            CheckedType::Void => write!(f, "void"),
+            CheckedType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// The `TypeCheck` trait is implemented by every `Instruction`. Resolving the type of
+/// an instruction also resolves the types of the instructions it contains, reporting
+/// errors to the `TypeCtx` along the way. Expressions record their resolved type, so
+/// that it can be trusted during execution.
+pub trait TypeCheck {
+    /// Resolve the type of the instruction, ignoring any previously recorded type
+    fn resolve_type(&mut self, ctx: &mut TypeCtx) -> CheckedType;
+
+    /// Record the resolved type of the instruction. Instructions which do not need to
+    /// keep their type, such as constants, simply ignore it
+    fn set_cached_type(&mut self, _ty: CheckedType) {}
+
+    /// Type of the instruction recorded during typechecking, if any
+    fn cached_type(&self) -> Option<&CheckedType> {
+        None
+    }
+
+    /// Get the type of the instruction, resolving and recording it if needed
+    fn type_of(&mut self, ctx: &mut TypeCtx) -> CheckedType {
+        if let Some(ty) = self.cached_type() {
+            return ty.clone();
+        }
+
+        let ty = self.resolve_type(ctx);
+        self.set_cached_type(ty.clone());
+
+        ty
+    }
+}
+
+/// Implement the methods of `TypeCheck` recording the resolved type of an instruction
+/// in its `cached_type: Option<CheckedType>` field
+macro_rules! cached_type {
+    () => {
+        fn set_cached_type(&mut self, ty: $crate::typechecker::CheckedType) {
+            self.cached_type = Some(ty)
+        }
+
+        fn cached_type(&self) -> Option<&$crate::typechecker::CheckedType> {
+            self.cached_type.as_ref()
+        }
+    };
+}
+
+pub(crate) use cached_type;
+
+/// Declarations visible in a given scope during typechecking
+#[derive(Default)]
+struct TypeScope {
+    variables: HashMap<String, CheckedType>,
+    functions: HashMap<String, Rc<FunctionDec>>,
+    types: HashMap<String, Rc<TypeDec>>,
+    /// Enums declared in the scope, by the names of their variants
+    variants: HashMap<String, Rc<TypeDec>>,
+}
+
+/// Information about the function currently being typechecked
+struct FunctionFrame {
+    return_type: Option<TypeId>,
+    /// Does every path typechecked so far in the function end with a `return`
+    returned: bool,
+    /// Number of loops entered before entering the function. A function's body cannot
+    /// break out of the loops its declaration is in
+    loops_base: usize,
+}
+
+/// Information about a loop currently being typechecked
+struct LoopFrame {
+    /// Only `loop` loops can evaluate to a value, since other loops might never execute
+    /// their body
+    allows_value: bool,
+    /// Type of the values given to the `break` instructions of the loop
+    break_type: Option<CheckedType>,
+}
+
+/// The `TypeCtx` mirrors the scopes of the `Context` without holding any instance.
+/// Functions and types which are not declared in the typechecked source, such as the
+/// ones coming from the standard library, are looked up in the wrapped `Context`.
+pub struct TypeCtx<'ctx> {
+    context: &'ctx mut Context,
+    scopes: LinkedList<TypeScope>,
+    functions: Vec<FunctionFrame>,
+    loops: Vec<LoopFrame>,
+    /// Location of the statement currently being typechecked
+    location: Option<ErrSpaceLocation>,
+    path: Option<PathBuf>,
+    included: HashSet<PathBuf>,
+}
+
+impl<'ctx> TypeCtx<'ctx> {
+    /// Create a new type context wrapping an existing context
+    pub fn new(context: &'ctx mut Context) -> TypeCtx<'ctx> {
+        let path = context.path().cloned();
+        let included = context.included().clone();
+
+        let mut scopes = LinkedList::new();
+        scopes.push_front(TypeScope::default());
+
+        TypeCtx {
+            context,
+            scopes,
+            functions: Vec::new(),
+            loops: Vec::new(),
+            location: None,
+            path,
+            included,
+        }
+    }
+
+    /// Report a typechecking error. Errors which do not have a location are reported
+    /// at the location of the statement being typechecked
+    pub fn error(&mut self, err: Error) {
+        self.context.error(err.with_loc(self.location.clone()))
+    }
+
+    /// Change the location of the statement being typechecked, returning the previous
+    /// one
+    pub fn set_location(&mut self, loc: Option<ErrSpaceLocation>) -> Option<ErrSpaceLocation> {
+        std::mem::replace(&mut self.location, loc)
+    }
+
+    /// Create a new empty scope
+    pub fn scope_enter(&mut self) {
+        self.scopes.push_front(TypeScope::default())
+    }
+
+    /// Exit the latest created scope
+    pub fn scope_exit(&mut self) {
+        // Much like the `ScopeMap`, we want to crash when popping an unexisting scope
+        self.scopes.pop_front().unwrap();
+    }
+
+    /// Declare a variable of a given type in the current scope, or replace its type if
+    /// it already exists in that scope
+    pub fn declare_variable(&mut self, name: &str, ty: CheckedType) {
+        // We can unwrap since there always is at least one scope
+        let scope = self.scopes.front_mut().unwrap();
+        scope.variables.insert(name.to_owned(), ty);
+    }
+
+    /// Get the type of a variable declared in any available scope. Variables which
+    /// already exist in the context, for example in interactive mode, have the type of
+    /// their instance
+    pub fn get_variable(&self, name: &str) -> Option<CheckedType> {
+        self.scopes
+            .iter()
+            .find_map(|scope| scope.variables.get(name))
+            .cloned()
+            .or_else(|| {
+                self.context.get_variable(name).and_then(|var| {
+                    var.instance()
+                        .ty()
+                        .map(|ty| CheckedType::Resolved(TypeId::from(ty.name())))
+                })
+            })
+    }
+
+    /// Declare a function in the current scope. Tests and mocks cannot be called and
+    /// are not declared
+    pub fn declare_function(&mut self, function: FunctionDec) {
+        if !matches!(function.fn_kind(), FunctionKind::Func | FunctionKind::Ext) {
+            return;
+        }
+
+        let scope = self.scopes.front_mut().unwrap();
+        scope
+            .functions
+            .insert(function.name().to_owned(), Rc::new(function));
+    }
+
+    /// Get a function declared in the typechecked source or in the context
+    pub fn get_function(&self, name: &str) -> Option<Rc<FunctionDec>> {
+        self.scopes
+            .iter()
+            .find_map(|scope| scope.functions.get(name))
+            .or_else(|| self.context.get_function(name))
+            .cloned()
+    }
+
+    /// Get a builtin function
+    pub fn get_builtin(&self, name: &str) -> Option<Builtin> {
+        self.context.get_builtin(name).cloned()
+    }
+
+    /// Declare a custom type in the current scope, along with its variants if it is an
+    /// enum
+    pub fn declare_type(&mut self, type_dec: TypeDec) {
+        let scope = self.scopes.front_mut().unwrap();
+        let type_dec = Rc::new(type_dec);
+
+        for variant in type_dec.variants() {
+            scope
+                .variants
+                .insert(variant.name().to_owned(), type_dec.clone());
+        }
+
+        scope.types.insert(type_dec.name().to_owned(), type_dec);
+    }
+
+    /// Declare the type parameters of a generic declaration in the current scope. They
+    /// are opaque types with no fields
+    pub fn declare_type_params(&mut self, params: &[TypeParam]) {
+        params
+            .iter()
+            .for_each(|param| self.declare_type(TypeDec::from(param.name())));
+    }
+
+    /// Get a type declared in the typechecked source or in the context
+    pub fn get_type(&self, type_id: &TypeId) -> Option<Rc<TypeDec>> {
+        self.scopes
+            .iter()
+            .find_map(|scope| scope.types.get(type_id.id()))
+            .or_else(|| self.context.get_type(type_id))
+            .cloned()
+    }
+
+    /// Get the enum declaring a variant, in the typechecked source or in the context
+    pub fn get_variant(&self, name: &str) -> Option<Rc<TypeDec>> {
+        self.scopes
+            .iter()
+            .find_map(|scope| scope.variants.get(name))
+            .or_else(|| self.context.get_variant(name))
+            .cloned()
+    }
+
+    /// Declare the functions and types contained in a list of instructions, without
+    /// typechecking them
+    pub fn hoist_declarations(&mut self, instructions: &[Box<dyn Instruction>]) {
+        for inst in instructions {
+            if let Some(function) = inst.downcast_ref::<FunctionDec>() {
+                self.declare_function(function.clone());
+            } else if let Some(type_dec) = inst.downcast_ref::<TypeDec>() {
+                self.declare_type(type_dec.clone());
+            }
+        }
+    }
+
+    /// Check that a type exists and is given as many generic arguments as it expects,
+    /// emitting an error if it does not
+    pub fn check_type_exists(&mut self, type_id: &TypeId) -> bool {
+        if let Some(element) = type_id.element_type() {
+            return self.check_type_exists(&element);
+        }
+
+        if type_id.signature().is_some() {
+            let mut types_exist = true;
+            for ty in type_id.generics() {
+                types_exist &= self.check_type_exists(ty);
+            }
+
+            return types_exist;
+        }
+
+        if type_id.is_map() {
+            if let Some((key, value)) = type_id.map_types() {
+                let key_exists = self.check_type_exists(key);
+
+                return self.check_type_exists(value) && key_exists;
+            }
+
+            self.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                "wrong number of generic arguments for type `{}`: expected 2, got {}",
+                type_id.id(),
+                type_id.generics().len()
+            )));
+            return false;
+        }
+
+        let type_dec = match self.get_type(type_id) {
+            Some(type_dec) => type_dec,
+            None => {
+                self.error(
+                    Error::new(ErrKind::TypeChecker)
+                        .with_msg(format!("unknown type `{}`", type_id)),
+                );
+                return false;
+            }
+        };
+
+        // Check every generic argument, so that all unknown types get reported
+        let mut generics_exist = true;
+        for generic in type_id.generics() {
+            generics_exist &= self.check_type_exists(generic);
+        }
+
+        let params = type_dec.generics();
+        let required = params.iter().filter(|p| p.default().is_none()).count();
+        let given = type_id.generics().len();
+
+        if given < required || given > params.len() {
+            let expected = match required == params.len() {
+                true => required.to_string(),
+                false => format!("{} to {}", required, params.len()),
+            };
+
+            self.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                "wrong number of generic arguments for type `{}`: expected {}, got {}",
+                type_id.id(),
+                expected,
+                given
+            )));
+            return false;
+        }
+
+        generics_exist
+    }
+
+    /// Check that the type of an argument or a field exists, reporting errors at the
+    /// location of the argument
+    pub fn check_arg_type_exists(&mut self, arg: &DecArg) -> bool {
+        let loc = arg.location().cloned().or_else(|| self.location.clone());
+        let previous = self.set_location(loc);
+
+        let exists = self.check_type_exists(arg.get_type());
+        self.set_location(previous);
+
+        exists
+    }
+
+    /// Complete a type with the default types of the generic arguments it omits, so
+    /// that `Pair<int>` and `Pair<int, int>` are the same type
+    pub fn normalize(&self, type_id: &TypeId) -> TypeId {
+        let generics: Vec<TypeId> = type_id
+            .generics()
+            .iter()
+            .map(|generic| self.normalize(generic))
+            .collect();
+
+        match self.get_type(type_id) {
+            Some(type_dec) if type_dec.generics().len() > generics.len() => {
+                let params = type_dec.generics();
+                // Missing arguments without defaults are reported by check_type_exists
+                let bindings = generics::bind(type_id.id(), params, &generics)
+                    .unwrap_or_else(|_| generics::Bindings::new());
+
+                TypeId::with_generics(
+                    type_id.id().to_string(),
+                    generics::bound_types(params, &bindings),
+                )
+            }
+            _ => TypeId::with_generics(type_id.id().to_string(), generics),
+        }
+    }
+
+    /// Check that a condition, as used in `if` or `while` instructions, is a boolean
+    pub fn check_condition(&mut self, condition: &mut dyn Instruction) {
+        match condition.type_of(self) {
+            CheckedType::Resolved(ty) if ty.id() != "bool" => {
+                self.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                    "condition `{}` should be of type `bool`, not `{}`",
+                    condition.print(),
+                    ty
+                )))
+            }
+            CheckedType::Void => self.error(Error::new(ErrKind::TypeChecker).with_msg(format!(
+                "statement `{}` cannot be used as a condition",
+                condition.print()
+            ))),
+            _ => {}
+        }
+    }
+
+    /// Start typechecking the body of a function with the given return type
+    pub fn function_enter(&mut self, return_type: Option<TypeId>) {
+        self.functions.push(FunctionFrame {
+            return_type,
+            returned: false,
+            loops_base: self.loops.len(),
+        })
+    }
+
+    /// Stop typechecking the body of the current function. Returns true if every path
+    /// through the function ends with a `return` instruction
+    pub fn function_exit(&mut self) -> bool {
+        // Crash if we exit a function we never entered
+        self.functions.pop().unwrap().returned
+    }
+
+    /// Return type of the function currently being typechecked. Returns `None` if
+    /// no function is being typechecked
+    pub fn return_type(&self) -> Option<Option<&TypeId>> {
+        self.functions
+            .last()
+            .map(|frame| frame.return_type.as_ref())
+    }
+
+    /// Mark the path being typechecked in the current function as ending with a
+    /// `return` instruction
+    pub fn mark_return(&mut self) {
+        self.set_returned(true);
+    }
+
+    /// Check if every path typechecked so far in the current function ends with a
+    /// `return` instruction
+    pub fn has_returned(&self) -> bool {
+        self.functions.last().is_some_and(|frame| frame.returned)
+    }
+
+    /// Change whether every path typechecked so far in the current function ends with
+    /// a `return` instruction. Instructions which might not execute one of their
+    /// blocks, such as conditions and loops, use it to forget the returns of that block
+    pub fn set_returned(&mut self, returned: bool) {
+        if let Some(frame) = self.functions.last_mut() {
+            frame.returned = returned;
+        }
+    }
+
+    /// Start typechecking the body of a loop. `allows_value` indicates if the loop can
+    /// be broken out of with a value
+    pub fn loop_enter(&mut self, allows_value: bool) {
+        self.loops.push(LoopFrame {
+            allows_value,
+            break_type: None,
+        })
+    }
+
+    /// Stop typechecking the body of the current loop. Returns the type of the values
+    /// given to its `break` instructions, if any
+    pub fn loop_exit(&mut self) -> Option<CheckedType> {
+        // Crash if we exit a loop we never entered
+        self.loops.pop().unwrap().break_type
+    }
+
+    /// Is a loop being typechecked in the current function
+    fn in_loop(&self) -> bool {
+        let base = self.functions.last().map_or(0, |frame| frame.loops_base);
+
+        self.loops.len() > base
+    }
+
+    /// Check that a `break` instruction is valid, with the type of its value if it has
+    /// one
+    pub fn check_break(&mut self, value: Option<CheckedType>) {
+        if !self.in_loop() {
+            return self.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(String::from("`break` used outside of a loop")),
+            );
+        }
+
+        // We can unwrap since we are in a loop
+        let frame = self.loops.last_mut().unwrap();
+
+        let error = match (value, frame.allows_value) {
+            (None, _) => None,
+            (Some(_), false) => Some(String::from(
+                "only `loop` loops can be broken out of with a value",
+            )),
+            (Some(CheckedType::Void), true) => {
+                Some(String::from("cannot break out of a loop with a statement"))
+            }
+            (Some(ty), true) => match &frame.break_type {
+                Some(CheckedType::Resolved(expected)) if matches!(&ty, CheckedType::Resolved(found) if found != expected) => {
+                    Some(format!(
+                        "loop breaks with values of different types: `{}` and `{}`",
+                        expected, ty
+                    ))
+                }
+                Some(CheckedType::Resolved(_)) => None,
+                _ => {
+                    frame.break_type = Some(ty);
+                    None
+                }
+            },
+        };
+
+        if let Some(msg) = error {
+            self.error(Error::new(ErrKind::TypeChecker).with_msg(msg));
+        }
+    }
+
+    /// Check that a `continue` instruction is used in a loop
+    pub fn check_continue(&mut self) {
+        if !self.in_loop() {
+            self.error(
+                Error::new(ErrKind::TypeChecker)
+                    .with_msg(String::from("`continue` used outside of a loop")),
+            );
+        }
+    }
+
+    /// Path of the source currently being typechecked
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    /// Change the path of the source currently being typechecked, marking it as
+    /// included
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        if let Some(p) = &path {
+            self.included.insert(p.clone());
+        }
+
+        self.path = path;
+    }
+
+    /// Has a source already been typechecked
+    pub fn is_included(&self, source: &Path) -> bool {
+        self.included.contains(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn type_errors(input: &str) -> bool {
+        let mut ctx = Parser::parse(input, None);
+        ctx.type_check();
+
+        ctx.error_handler.has_errors()
+    }
+
+    #[test]
+    fn t_valid_program() {
+        assert!(!type_errors(
+            "func add(a: int, b: int) -> int { a + b } x = add(1, 2); x"
+        ));
+    }
+
+    #[test]
+    fn t_wrong_argument_type() {
+        assert!(type_errors("func id(a: int) -> int { a } id(1.5)"));
+        assert!(type_errors("func id(a: int) -> int { a } id(\"string\")"));
+    }
+
+    #[test]
+    fn t_wrong_argument_count() {
+        assert!(type_errors("func id(a: int) -> int { a } id(1, 2)"));
+    }
+
+    #[test]
+    fn t_wrong_return_type() {
+        assert!(type_errors("func f() -> int { 1.5 }"));
+        assert!(type_errors("func f() -> int { }"));
+        assert!(type_errors("func f() -> int { return 'c' }"));
+    }
+
+    #[test]
+    fn t_no_return_type_is_void() {
+        assert!(!type_errors("func f() { 1 } f()"));
+        assert!(!type_errors(
+            "func g() {} func f(b: bool) { if b { return; } g() }"
+        ));
+        assert!(type_errors("func f() {} x = f()"));
+        assert!(type_errors("x = println(\"a\")"));
+        assert!(type_errors("func f() { return 3 }"));
+    }
+
+    #[test]
+    fn t_missing_return() {
+        assert!(!type_errors(
+            "func f(b: bool) -> int { if b { return 1 } else { return 2 } }"
+        ));
+        assert!(!type_errors(
+            "func f(b: bool) -> int { if b { return 1 } 2 }"
+        ));
+        assert!(!type_errors(
+            "func f(o: Option<int>) -> int { match o { Some(v) => { return v }, None => { return 0 } } }"
+        ));
+        assert!(type_errors("func f(b: bool) -> int { if b { return 1 } }"));
+        assert!(type_errors(
+            "func f(b: bool) -> int { while b { return 1; } }"
+        ));
+    }
+
+    #[test]
+    fn t_unknown_types() {
+        assert!(type_errors("func f(a: Unknown) {}"));
+        assert!(type_errors("func f() -> Unknown {}"));
+        assert!(type_errors("type Custom(a: Unknown);"));
+        assert!(type_errors("x = Unknown { a = 1 }"));
+    }
+
+    #[test]
+    fn t_hoisted_declarations() {
+        assert!(!type_errors(
+            "func f() -> Point { g() } func g() -> Point { Point { x = 1 } } type Point(x: int);"
+        ));
+    }
+
+    #[test]
+    fn t_type_instantiation() {
+        assert!(!type_errors(
+            "type Point(x: int, y: int); Point { x = 1, y = 2 }.x"
+        ));
+        assert!(type_errors(
+            "type Point(x: int, y: int); Point { x = 1, y = 2.0 }"
+        ));
+        assert!(type_errors(
+            "type Point(x: int, y: int); Point { x = 1, z = 2 }"
+        ));
+        assert!(type_errors("type Point(x: int, y: int); Point { x = 1 }"));
+    }
+
+    #[test]
+    fn t_field_access() {
+        assert!(type_errors(
+            "type Point(x: int, y: int); p = Point { x = 1, y = 2 }; p.z"
+        ));
+        assert!(type_errors(
+            "type Point(x: int); func f(a: float) {} f(Point { x = 1 }.x)"
+        ));
+    }
+
+    #[test]
+    fn t_binary_op() {
+        assert!(!type_errors("1 + 2 * 3"));
+        assert!(type_errors("1 + 2.0"));
+    }
+
+    #[test]
+    fn t_if_else() {
+        assert!(!type_errors("if true { 1 } else { 2 }"));
+        assert!(type_errors("if 1 { 1 } else { 2 }"));
+        assert!(type_errors("if true { 1 } else { 2.0 }"));
+        assert!(type_errors("x = if true { 1 }"));
+        assert!(type_errors("x = if false { 1 } else { }"));
+        assert!(type_errors("x = if false { } else { 1 }"));
+        assert!(!type_errors(
+            "func f(b: bool) -> int { if b { 1 } else { return 2 } }"
+        ));
+    }
+
+    #[test]
+    fn t_break_continue() {
+        assert!(!type_errors("loop { break; }"));
+        assert!(!type_errors("while true { continue; }"));
+        assert!(!type_errors(
+            "for i in range(0, 2) { if i == 1 { break; } }"
+        ));
+        assert!(type_errors("break"));
+        assert!(type_errors("continue"));
+        assert!(type_errors("loop { func f() { break; } }"));
+    }
+
+    #[test]
+    fn t_break_values() {
+        assert!(!type_errors("x = loop { break 1; }; x + 1"));
+        assert!(type_errors("x = loop { break 1; }; x + 1.5"));
+        assert!(!type_errors("func f() -> int { loop { break 1; } } f()"));
+        assert!(type_errors("func f() -> int { loop { break 1.5; } } f()"));
+        assert!(type_errors("loop { if true { break 1; } break 'c'; }"));
+        assert!(type_errors("while true { break 1; }"));
+    }
+
+    #[test]
+    fn t_var_assign() {
+        assert!(type_errors("mut x = 1; x = 2.5"));
+        assert!(!type_errors("mut x = 1; x = 2"));
+        assert!(type_errors("func f(a: int) {} x = 1.2; f(x)"));
+    }
+
+    #[test]
+    fn t_method_call() {
+        assert!(!type_errors(
+            "func double(a: int) -> int { a * 2 } 1.double()"
+        ));
+        assert!(type_errors(
+            "func double(a: int) -> int { a * 2 } 1.0.double()"
+        ));
+    }
+
+    #[test]
+    fn t_generic_types() {
+        assert!(!type_errors(
+            "type Pair<T, U = T>(f: T, s: U); func f(a: int) {} f(Pair<int> { f = 1, s = 2 }.s)"
+        ));
+        assert!(!type_errors(
+            "type Box<T>(x: T); b = Box { x = 'c' }; func f(b: Box<char>) {} f(b)"
+        ));
+        assert!(type_errors("type Box<T>(x: T); Box<int> { x = 1.5 }"));
+        assert!(type_errors("type Box<T>(x: T); Box<int, int> { x = 1 }"));
+        assert!(type_errors("type Box<T>(x: T); func f(b: Box) {}"));
+        assert!(type_errors("type Box<T>(x: T); func f(b: Box<Unknown>) {}"));
+        assert!(type_errors(
+            "type Box<T>(x: T); func f(a: float) {} f(Box { x = 1 }.x)"
+        ));
+    }
+
+    #[test]
+    fn t_generic_functions() {
+        assert!(!type_errors("func id<T>(a: T) -> T { a } id(1) + 2"));
+        assert!(!type_errors(
+            "func id<T>(a: T) -> T { a } id<float>(1.5) + 2.0"
+        ));
+        assert!(type_errors("func id<T>(a: T) -> T { a } id(1) + 2.0"));
+        assert!(type_errors("func id<T>(a: T) -> T { a } id<float>(1)"));
+        assert!(type_errors("func none<T>() {} none()"));
+        assert!(type_errors("func same<T>(a: T, b: T) {} same(1, 'c')"));
+        assert!(type_errors("func add<T>(a: T, b: T) -> T { a + b }"));
+    }
+
+    #[test]
+    fn t_enums() {
+        let shape = "enum Shape { Circle(radius: float), Empty } ";
+
+        assert!(!type_errors(&format!(
+            "{} func f(s: Shape) {{}} f(Circle {{ radius = 1.0 }}); f(Empty)",
+            shape
+        )));
+        assert!(type_errors(&format!("{} Circle {{ radius = 1 }}", shape)));
+        assert!(type_errors(&format!("{} Shape {{ radius = 1.0 }}", shape)));
+        assert!(type_errors(&format!("{} x = Circle; x", shape)));
+        assert!(type_errors("enum Dup { A, A }"));
+        assert!(type_errors("enum Fields { A(x: Unknown) }"));
+    }
+
+    #[test]
+    fn t_match() {
+        let shape = "enum Shape { Circle(radius: float), Rect(w: float, h: float), Empty } \
+            s = Empty; ";
+
+        assert!(!type_errors(&format!(
+            "{} x = match s {{ Circle(r) => r, Rect(w, _) => w, Empty => 0.0 }}; x + 1.0",
+            shape
+        )));
+        assert!(!type_errors(&format!(
+            "{} match s {{ Circle => 1, _ => 0 }}",
+            shape
+        )));
+        assert!(type_errors(&format!(
+            "{} match s {{ Circle(r) => r, Empty => 0.0 }}",
+            shape
+        )));
+        assert!(type_errors(&format!(
+            "{} match s {{ Circle(r) => r, _ => 0 }}",
+            shape
+        )));
+        assert!(type_errors(&format!(
+            "{} match s {{ Circle(r) => r + 1, _ => 0.0 }}",
+            shape
+        )));
+        assert!(type_errors(&format!(
+            "{} match s {{ Circle(r, x) => r, _ => 0.0 }}",
+            shape
+        )));
+        assert!(type_errors(&format!(
+            "{} match s {{ Square => 1, _ => 0 }}",
+            shape
+        )));
+        assert!(type_errors(&format!(
+            "{} match s {{ _ => 1, Empty => 0 }}",
+            shape
+        )));
+        assert!(type_errors("match 1 { _ => 0 }"));
+    }
+
+    #[test]
+    fn t_generic_enums() {
+        let maybe = "enum Maybe<T> { Just(value: T), Nothing } ";
+
+        assert!(!type_errors(&format!(
+            "{} func f() -> Maybe<int> {{ Nothing }} match f() {{ Just(v) => v + 1, Nothing => 0 }}",
+            maybe
+        )));
+        assert!(!type_errors(&format!(
+            "{} func f(m: Maybe<char>) {{}} f(Just {{ value = 'c' }})",
+            maybe
+        )));
+        assert!(type_errors(&format!(
+            "{} func f(m: Maybe<char>) {{}} f(Just {{ value = 1 }})",
+            maybe
+        )));
+        assert!(type_errors(&format!(
+            "{} m = Just {{ value = 1 }}; match m {{ Just(v) => v + 1.5, Nothing => 0.0 }}",
+            maybe
+        )));
+    }
+
+    #[test]
+    fn t_function_values() {
+        let apply = "func double(x: int) -> int { x * 2 } \
+            func apply(f: func(int) -> int, x: int) -> int { f(x) } ";
+
+        assert!(!type_errors(&format!("{} apply(double, 2)", apply)));
+        assert!(!type_errors(&format!("{} f = double; f(2) + 1", apply)));
+        assert!(type_errors(&format!("{} f = double; f('c')", apply)));
+        assert!(type_errors(&format!(
+            "{} func neg(b: bool) -> bool {{ !b }} apply(neg, 2)",
+            apply
+        )));
+        assert!(type_errors(&format!("{} func p() {{}} apply(p, 2)", apply)));
+        assert!(type_errors(&format!(
+            "{} func id<T>(x: T) -> T {{ x }} apply(id, 2)",
+            apply
+        )));
+        assert!(type_errors("func f(g: func(Unknown) -> int) {}"));
+    }
+
+    #[test]
+    fn t_try() {
+        assert!(!type_errors(
+            "func f(o: Option<int>) -> Option<float> { x = o?; Some { value = 1.5 } }"
+        ));
+        assert!(!type_errors(
+            "func f(r: Result<int, char>) -> Result<bool, char> { Ok { value = r? == 1 } }"
+        ));
+        assert!(type_errors(
+            "func f(r: Result<int, char>) -> Result<int, string> { Ok { value = r? } }"
+        ));
+        assert!(type_errors(
+            "func f(o: Option<int>) -> Result<int, char> { Ok { value = o? } }"
+        ));
+        assert!(type_errors("func f(o: Option<int>) -> int { o? }"));
+        assert!(type_errors(
+            "func f(o: Option<int>) -> Option<int> { o? + 1.5 }"
+        ));
+        assert!(type_errors("func f(x: int) -> Option<int> { x? }"));
+    }
+
+    #[test]
+    fn t_arrays() {
+        assert!(!type_errors(
+            "func first(v: [[int]]) -> int { v[0][0] } first([[1, 2], [3]])"
+        ));
+        assert!(!type_errors(
+            "func f<T>(x: T) -> [T] { [x] } v = f(1); v[0] + 1"
+        ));
+        assert!(!type_errors(
+            "mut v = [1]; v.push(2); v[0] = v.len(); is_some(v.pop())"
+        ));
+        assert!(!type_errors("mut v = []; v.push('c')"));
+        assert!(type_errors("v = [1, 1.5]"));
+        assert!(type_errors("v = [1]; v[true]"));
+        assert!(type_errors("v = 1; v[0]"));
+        assert!(type_errors("mut v = [1]; v[0] = 'c'"));
+        assert!(type_errors("mut v = [1]; v.push(1.5)"));
+        assert!(type_errors("mut v = [1]; v.pop(1)"));
+        assert!(type_errors("[1].push(2)"));
+        assert!(type_errors("func f(v: [Unknown]) {}"));
+    }
+
+    #[test]
+    fn t_maps() {
+        assert!(!type_errors(
+            "func total(m: Map<string, int>) -> int { unwrap(m.get(\"a\")) } total([\"a\": 1])"
+        ));
+        assert!(!type_errors(
+            "mut m = [1: 'a']; m.insert(2, 'b'); is_some(m.remove(1)); m.keys()[0] + 1"
+        ));
+        assert!(!type_errors("mut m = [:]; m.insert(1, true)"));
+        assert!(!type_errors(
+            "mut n = 0; for (k, v) in [1: 2] { n = n + k * v }"
+        ));
+        assert!(type_errors("m = [1: 'a', 1.5: 'b']"));
+        assert!(type_errors("m = [1: 'a', 2: true]"));
+        assert!(type_errors("m = [1: 'a']; m.get('a')"));
+        assert!(type_errors("mut m = [1: 'a']; m.insert(2, 3)"));
+        assert!(type_errors("m = [1: 'a']; m.contains()"));
+        assert!(type_errors("[1: 'a'].insert(2, 'b')"));
+        assert!(type_errors("for (k, v) in [1, 2] { k }"));
+        assert!(type_errors("func f(m: Map<int>) {}"));
+    }
+
+    #[test]
+    fn t_undeclared_variable() {
+        assert!(!type_errors("x = 1; func f() -> int { x } f()"));
+        assert!(type_errors("x = y + 1"));
+        assert!(type_errors("func f() -> int { y }"));
+    }
+
+    #[test]
+    fn t_generic_builtins() {
+        assert!(!type_errors("assert_eq(1, 2)"));
+        assert!(!type_errors("assert_eq(\"jinko\", \"jinko\")"));
+        assert!(type_errors("assert_eq(1, \"jinko\")"));
+        assert!(type_errors("assert_eq(1.5, 1)"));
+        assert!(!type_errors("assert_eq<int>(1, 2)"));
+        assert!(type_errors("assert_eq<float>(1, 2)"));
+    }
+
+    #[test]
+    fn t_unknown_function() {
+        assert!(type_errors("does_not_exist()"));
+    }
+
+    #[test]
+    fn t_execute_does_not_run_ill_typed_program() {
+        let mut ctx = Parser::parse("func id(a: int) -> int { a } id(true)", None);
+
+        assert!(ctx.execute().is_err());
+    }
+
+    #[test]
+    fn t_types_are_recorded() {
+        let mut ctx = Parser::parse("func f() -> float { 1.5 } x = f(); x", None);
+        ctx.type_check();
+
+        let last = ctx.entry_point.block().unwrap().last().unwrap();
+
+        assert_eq!(
+            last.cached_type(),
+            Some(&CheckedType::Resolved(TypeId::from("float")))
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use crate::instruction::{InstrKind, Instruction, Operator, TypeDec};
+use crate::instruction::{InstrKind, Instruction, Operator, TypeDec, TypeId};
+use crate::typechecker::{CheckedType, TypeCheck, TypeCtx};
 use crate::{
-    Context, Error, FromObjectInstance, JkString, ObjectInstance, ToObjectInstance, Value,
+    Context, Error, FromObjectInstance, JinkoType, JkBool, JkString, ObjectInstance,
+    ToObjectInstance, Value,
 };
 
 use std::convert::TryFrom;
@@ -9,7 +11,7 @@ use std::convert::TryFrom;
 /// A JkConstant represents a primitive type in Jinko. It is used in order to
 /// implement integers, floating point numbers, characters, booleans and strings, as
 /// well as raw byte values later for custom types.
-pub struct JkConstant<T>(pub(crate) T);
+pub struct JkConstant<T>(pub T);
 
 // We can do a generic implementation instead of copy pasting it 5 times.
 // However, this part of the rust compiler is still not ready
@@ -49,9 +51,10 @@ pub struct JkConstant<T>(pub(crate) T);
 // ```
 
 /// Circumvents the need for a generic implementation (see comment).
-/// Call it with the type contained in the JkConstant and the &str representation
+/// Call it with the type contained in the JkConstant and the &str representation,
+/// and optionally with the function used to print the constant's value
 ///
-/// ```
+/// ```text
 /// // Implements a JkConstant<i64> with type displayed as "int"
 /// jk_primitive!(i64, "int");
 /// ```
@@ -62,6 +65,7 @@ macro_rules! jk_primitive {
             fn to_instance(&self) -> ObjectInstance {
                 use std::mem::{size_of, transmute};
 
+                #[allow(unknown_lints, unnecessary_transmutes)]
                 unsafe {
                     ObjectInstance::from_bytes(
                         Some(TypeDec::from("bool")),
@@ -77,6 +81,7 @@ macro_rules! jk_primitive {
             fn from_instance(i: &ObjectInstance) -> Self {
                 use std::mem::{size_of, transmute};
 
+                #[allow(unknown_lints, unnecessary_transmutes)]
                 unsafe {
                     Self::from(transmute::<[u8; size_of::<bool>()], bool>(
                         TryFrom::try_from(i.data()).unwrap(),
@@ -106,12 +111,22 @@ macro_rules! jk_primitive {
                 Some(self.to_instance())
             }
         }
+
+        impl TypeCheck for JkConstant<bool> {
+            fn resolve_type(&mut self, _ctx: &mut TypeCtx) -> CheckedType {
+                CheckedType::Resolved(TypeId::from("bool"))
+            }
+        }
     };
     ($t:ty, $s:expr) => {
+        jk_primitive!($t, $s, |value: &$t| value.to_string());
+    };
+    ($t:ty, $s:expr, $print:expr) => {
         impl ToObjectInstance for JkConstant<$t> {
             fn to_instance(&self) -> ObjectInstance {
                 use std::mem::{size_of, transmute};
 
+                #[allow(unknown_lints, unnecessary_transmutes)]
                 unsafe {
                     ObjectInstance::from_bytes(
                         Some(TypeDec::from($s)),
@@ -127,6 +142,7 @@ macro_rules! jk_primitive {
             fn from_instance(i: &ObjectInstance) -> Self {
                 use std::mem::{size_of, transmute};
 
+                #[allow(unknown_lints, unnecessary_transmutes)]
                 unsafe {
                     Self::from(transmute::<[u8; size_of::<$t>()], $t>(
                         TryFrom::try_from(i.data()).unwrap(),
@@ -141,7 +157,7 @@ macro_rules! jk_primitive {
             }
 
             fn print(&self) -> String {
-                self.0.to_string()
+                ($print)(&self.0)
             }
 
             fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
@@ -152,33 +168,120 @@ macro_rules! jk_primitive {
                 Some(self.to_instance())
             }
         }
+
+        impl TypeCheck for JkConstant<$t> {
+            fn resolve_type(&mut self, _ctx: &mut TypeCtx) -> CheckedType {
+                CheckedType::Resolved(TypeId::from($s))
+            }
+        }
     };
 }
 
 jk_primitive!(i64, "int");
 jk_primitive!(f64, "float");
-jk_primitive!(char, "char");
+jk_primitive!(char, "char", |c: &char| format!("'{}'", escape(*c, '\'')));
 jk_primitive!(bool);
 
+/// Escape a character so that it can be written between `quote`s in jinko source code
+pub(crate) fn escape(c: char, quote: char) -> String {
+    match c {
+        '\n' => String::from("\\n"),
+        '\t' => String::from("\\t"),
+        '\r' => String::from("\\r"),
+        '\0' => String::from("\\0"),
+        '\\' => String::from("\\\\"),
+        c if c == quote => format!("\\{}", c),
+        c if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+/// Escape a character so that it can be written in a jinko string, in which braces
+/// delimit interpolations and are escaped by doubling them
+pub(crate) fn escape_in_string(c: char) -> String {
+    match c {
+        '{' | '}' => [c, c].iter().collect(),
+        c => escape(c, '"'),
+    }
+}
+
+/// Compare two values using a comparison operator, returning a boolean instance
+fn compare<T: PartialOrd>(lhs: &T, rhs: &T, op: Operator) -> ObjectInstance {
+    let res = match op {
+        Operator::Equals => lhs == rhs,
+        Operator::NotEquals => lhs != rhs,
+        Operator::Lt => lhs < rhs,
+        Operator::LtEq => lhs <= rhs,
+        Operator::Gt => lhs > rhs,
+        Operator::GtEq => lhs >= rhs,
+        _ => unreachable!("{} is not a comparison operator", op.as_str()),
+    };
+
+    JkBool::from(res).to_instance()
+}
+
 impl Value for JkConstant<i64> {
+    fn do_op(&self, other: &Self, op: Operator) -> Result<ObjectInstance, Error> {
+        // Integer arithmetic is checked: Overflowing is an error, and so is dividing by
+        // zero. The `wrapping_*` and `saturating_*` builtins offer the other behaviors
+        let res = match op {
+            Operator::Add => self.0.checked_add(other.0),
+            Operator::Sub => self.0.checked_sub(other.0),
+            Operator::Mul => self.0.checked_mul(other.0),
+            Operator::Div | Operator::Mod if other.0 == 0 => {
+                return Err(self.op_error(other, op, "division by zero"))
+            }
+            Operator::Div => self.0.checked_div(other.0),
+            Operator::Mod => self.0.checked_rem(other.0),
+            op if op.is_comparison() => return Ok(compare(&self.0, &other.0, op)),
+            _ => return self.no_op(other, op),
+        };
+
+        match res {
+            Some(value) => Ok(JkConstant::from(value).to_instance()),
+            None => Err(self.op_error(other, op, "integer overflow")),
+        }
+    }
+}
+
+impl Value for JkConstant<f64> {
     fn do_op(&self, other: &Self, op: Operator) -> Result<ObjectInstance, Error> {
         match op {
             Operator::Add => Ok(JkConstant::from(self.0 + other.0).to_instance()),
             Operator::Sub => Ok(JkConstant::from(self.0 - other.0).to_instance()),
             Operator::Mul => Ok(JkConstant::from(self.0 * other.0).to_instance()),
             Operator::Div => Ok(JkConstant::from(self.0 / other.0).to_instance()),
+            Operator::Mod => Ok(JkConstant::from(self.0 % other.0).to_instance()),
+            op if op.is_comparison() => Ok(compare(&self.0, &other.0, op)),
             _ => self.no_op(other, op),
         }
     }
 }
 
-impl Value for JkConstant<f64> {
+impl Value for JkConstant<char> {
     fn do_op(&self, other: &Self, op: Operator) -> Result<ObjectInstance, Error> {
         match op {
-            Operator::Add => Ok(JkConstant::from(self.0 + other.0).to_instance()),
-            Operator::Sub => Ok(JkConstant::from(self.0 - other.0).to_instance()),
-            Operator::Mul => Ok(JkConstant::from(self.0 * other.0).to_instance()),
-            Operator::Div => Ok(JkConstant::from(self.0 / other.0).to_instance()),
+            op if op.is_comparison() => Ok(compare(&self.0, &other.0, op)),
+            _ => self.no_op(other, op),
+        }
+    }
+}
+
+impl Value for JkString {
+    fn do_op(&self, other: &Self, op: Operator) -> Result<ObjectInstance, Error> {
+        match op {
+            Operator::Add => Ok(JkString::from(format!("{}{}", self.0, other.0)).to_instance()),
+            op if op.is_comparison() => Ok(compare(&self.0, &other.0, op)),
+            _ => self.no_op(other, op),
+        }
+    }
+}
+
+impl Value for JkConstant<bool> {
+    fn do_op(&self, other: &Self, op: Operator) -> Result<ObjectInstance, Error> {
+        match op {
+            // Booleans are not ordered
+            Operator::Equals | Operator::NotEquals => Ok(compare(&self.0, &other.0, op)),
             _ => self.no_op(other, op),
         }
     }
@@ -188,7 +291,7 @@ impl ToObjectInstance for JkString {
     fn to_instance(&self) -> ObjectInstance {
         ObjectInstance::from_bytes(
             Some(TypeDec::from("string")),
-            self.0.as_bytes().len(),
+            self.0.len(),
             self.0.as_bytes(),
             None,
         )
@@ -208,7 +311,9 @@ impl Instruction for JkString {
     }
 
     fn print(&self) -> String {
-        format!("\"{}\"", self.0.clone())
+        let escaped: String = self.0.chars().map(escape_in_string).collect();
+
+        format!("\"{}\"", escaped)
     }
 
     fn execute(&self, ctx: &mut Context) -> Option<ObjectInstance> {
@@ -218,6 +323,42 @@ impl Instruction for JkString {
     }
 }
 
+impl TypeCheck for JkString {
+    fn resolve_type(&mut self, _ctx: &mut TypeCtx) -> CheckedType {
+        CheckedType::Resolved(TypeId::from("string"))
+    }
+}
+
+/// Implement the conversion traits on the Rust type wrapped by a `JkConstant`, so that
+/// it can be used as the field of a type deriving `JinkoType`
+macro_rules! rust_primitive {
+    ($t:ty, $s:expr) => {
+        impl JinkoType for $t {
+            fn type_dec() -> TypeDec {
+                TypeDec::from($s)
+            }
+        }
+
+        impl ToObjectInstance for $t {
+            fn to_instance(&self) -> ObjectInstance {
+                JkConstant::<$t>::from(self.clone()).to_instance()
+            }
+        }
+
+        impl FromObjectInstance for $t {
+            fn from_instance(i: &ObjectInstance) -> Self {
+                JkConstant::<$t>::from_instance(i).0
+            }
+        }
+    };
+}
+
+rust_primitive!(i64, "int");
+rust_primitive!(f64, "float");
+rust_primitive!(char, "char");
+rust_primitive!(bool, "bool");
+rust_primitive!(String, "string");
+
 impl From<&str> for JkConstant<String> {
     fn from(s: &str) -> Self {
         JkConstant(s.to_string())
@@ -229,3 +370,71 @@ impl<T> From<T> for JkConstant<T> {
         JkConstant(rust_value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Construct;
+    use crate::{JkChar, JkInt};
+
+    #[test]
+    fn t_print_escapes() {
+        assert_eq!(JkString::from("a\t\"b\"\n").print(), r#""a\t\"b\"\n""#);
+        assert_eq!(JkString::from("it's \\").print(), r#""it's \\""#);
+        assert_eq!(JkString::from("{x} }").print(), r#""{{x}} }}""#);
+        assert_eq!(JkChar::from('\'').print(), r"'\''");
+        assert_eq!(JkChar::from('"').print(), "'\"'");
+        assert_eq!(JkChar::from('\u{1b}').print(), r"'\u{1b}'");
+    }
+
+    #[test]
+    fn t_print_round_trips() {
+        let mut ctx = Context::new();
+
+        for source in [
+            r#""a\t\"b\"\n\\\0""#,
+            r"'\n'",
+            r"'\''",
+            r#""\u{1b}[0m""#,
+            r#""{{x}} }}""#,
+        ] {
+            let printed = Construct::instruction(source).unwrap().1.print();
+            let reparsed = Construct::instruction(&printed).unwrap().1;
+
+            assert_eq!(reparsed.print(), printed);
+            assert_eq!(
+                reparsed.execute(&mut ctx),
+                Construct::instruction(source).unwrap().1.execute(&mut ctx)
+            );
+        }
+    }
+
+    fn int_op(lhs: i64, rhs: i64, op: &str) -> Result<ObjectInstance, Error> {
+        JkInt::from(lhs).do_op(&JkInt::from(rhs), Operator::new(op))
+    }
+
+    #[test]
+    fn t_int_checked_arithmetic() {
+        assert!(int_op(i64::MAX, 1, "+").is_err());
+        assert!(int_op(i64::MIN, 1, "-").is_err());
+        assert!(int_op(i64::MAX, 2, "*").is_err());
+        assert!(int_op(i64::MIN, -1, "/").is_err());
+        assert!(int_op(i64::MIN, -1, "%").is_err());
+        assert!(int_op(1, 0, "/").is_err());
+        assert!(int_op(1, 0, "%").is_err());
+
+        assert_eq!(int_op(-7, 3, "%").unwrap(), JkInt::from(-1).to_instance());
+        assert_eq!(int_op(-7, 3, "/").unwrap(), JkInt::from(-2).to_instance());
+    }
+
+    #[test]
+    fn t_string_concat() {
+        let mut ctx = Context::new();
+        let concat = Construct::instruction(r#""jin" + "ko" + "!""#).unwrap().1;
+
+        assert_eq!(
+            concat.execute(&mut ctx),
+            Some(JkString::from("jinko!").to_instance())
+        );
+    }
+}
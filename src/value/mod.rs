@@ -1,10 +1,11 @@
 //! A `Value` is a number instance in jinko. It refers to arithmetic primtive types, such
 //! as Ints and Floats
 
-use crate::{instruction::Operator, Error, Instruction, ObjectInstance};
+use crate::{instruction::Operator, ErrKind, Error, Instruction, ObjectInstance};
 
 mod jk_constant;
 
+pub(crate) use jk_constant::escape_in_string;
 pub use jk_constant::JkConstant;
 
 pub type JkBool = JkConstant<bool>;
@@ -17,8 +18,25 @@ pub type JkString = JkConstant<String>;
 pub trait Value: Instruction {
     /// Call this function when an operation is not implemented, rather than implementing
     /// your own. This will format the error nicely.
-    fn no_op(&self, _other: &Self, _op: Operator) -> Result<ObjectInstance, Error> {
-        unreachable!("NOP") // FIXME
+    fn no_op(&self, other: &Self, op: Operator) -> Result<ObjectInstance, Error> {
+        Err(Error::new(ErrKind::Context).with_msg(format!(
+            "operation not implemented: `{}` {} `{}`",
+            self.print(),
+            op.as_str(),
+            other.print()
+        )))
+    }
+
+    /// Call this function when an operation fails at runtime, such as an integer
+    /// overflow or a division by zero, with a description of the failure
+    fn op_error(&self, other: &Self, op: Operator, what: &str) -> Error {
+        Error::new(ErrKind::Context).with_msg(format!(
+            "{}: `{}` {} `{}`",
+            what,
+            self.print(),
+            op.as_str(),
+            other.print()
+        ))
     }
 
     /// Realize any operation implemented by the type, and return a new instance
@@ -26,7 +44,7 @@ pub trait Value: Instruction {
     /// Adding a floating point number and an integer together. Doing that will
     /// return a new JkFloat.
     ///
-    /// ```
+    /// ```text
     /// let ctx = Context::new();
     ///
     /// let a = JkInt::from(126);
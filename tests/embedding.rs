@@ -0,0 +1,25 @@
+//! The embedding API must not depend on the directory the host program is run from,
+//! which is why this test lives in its own process: It changes the working directory.
+
+use jinko::{FromObjectInstance, JkInt, Parser, Signature, ToObjectInstance, TypeId};
+
+#[test]
+fn t_embed_outside_of_source_tree() {
+    std::env::set_current_dir(std::env::temp_dir()).unwrap();
+
+    let mut ctx = Parser::parse("double(21)", None);
+
+    ctx.register_native(
+        "double",
+        Signature::new(vec![TypeId::from("int")], Some(TypeId::from("int"))),
+        |_, args| Some(JkInt::from(JkInt::from_instance(&args[0]).0 * 2).to_instance()),
+    );
+
+    let result = ctx.execute().unwrap().unwrap();
+    assert_eq!(JkInt::from_instance(&result).0, 42);
+
+    // The standard library is available as well
+    let mut ctx = Parser::parse("unwrap(Some { value = 3 })", None);
+    let result = ctx.execute().unwrap().unwrap();
+    assert_eq!(JkInt::from_instance(&result).0, 3);
+}
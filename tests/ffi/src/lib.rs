@@ -0,0 +1,38 @@
+//! Native functions called by the functional tests of jinko's external functions.
+//! Build the workspace to get `target/debug/libjinko_ffi_test.so`
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+#[no_mangle]
+pub extern "C" fn add(lhs: i64, rhs: i64) -> i64 {
+    lhs + rhs
+}
+
+#[no_mangle]
+pub extern "C" fn scale(value: i64, factor: f64) -> f64 {
+    value as f64 * factor
+}
+
+#[no_mangle]
+pub extern "C" fn negate(value: bool) -> bool {
+    !value
+}
+
+#[no_mangle]
+pub extern "C" fn next_char(c: u32) -> u32 {
+    c + 1
+}
+
+/// # Safety
+///
+/// `s` must be a valid null-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn length(s: *const c_char) -> i64 {
+    CStr::from_ptr(s).to_bytes().len() as i64
+}
+
+#[no_mangle]
+pub extern "C" fn greeting() -> *const c_char {
+    b"hello from C\0".as_ptr() as *const c_char
+}